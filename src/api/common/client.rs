@@ -3,8 +3,8 @@ use crate::api::common::{
     request::ApiRequest,
     response::{ApiResponse, OutputFormat, ResponseProcessor},
 };
+use crate::{FetcherConfig, HtmlFetcher, RequestDelayConfig, UserAgentMode};
 use async_trait::async_trait;
-use reqwest;
 
 pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36";
 
@@ -24,27 +24,36 @@ pub trait ApiClient: Send + Sync {
     }
 }
 
+/// Executes API requests on top of `HtmlFetcher`, so user-agent policy, request
+/// delay, retries, caching and metrics are shared with the HTML scrapers.
+#[derive(Clone)]
 pub struct HttpClient {
-    client: reqwest::Client,
+    fetcher: HtmlFetcher,
 }
 
 impl HttpClient {
+    /// API requests with `DEFAULT_USER_AGENT` and no request delay.
     pub fn new() -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .user_agent(DEFAULT_USER_AGENT)
-            .build()
-            .map_err(|e| ApiError::Other(e.to_string()))?;
-
-        Ok(HttpClient { client })
+        Self::with_user_agent(DEFAULT_USER_AGENT)
     }
 
     pub fn with_user_agent(user_agent: &str) -> Result<Self> {
-        let client = reqwest::Client::builder()
-            .user_agent(user_agent)
-            .build()
-            .map_err(|e| ApiError::Other(e.to_string()))?;
+        let config = FetcherConfig::new()
+            .user_agent_mode(UserAgentMode::Fixed(Some(user_agent.to_string())))
+            .delay(RequestDelayConfig::disabled());
+        let fetcher = HtmlFetcher::from_config(config).map_err(|e| ApiError::Other(e.to_string()))?;
+
+        Ok(HttpClient { fetcher })
+    }
 
-        Ok(HttpClient { client })
+    /// Runs API requests through an existing fetcher (clones share cookies,
+    /// delay state, cache and metrics).
+    pub fn from_fetcher(fetcher: HtmlFetcher) -> Self {
+        HttpClient { fetcher }
+    }
+
+    pub fn fetcher(&self) -> &HtmlFetcher {
+        &self.fetcher
     }
 
     pub async fn execute<C>(&self, api_client: &C, request: &C::Request) -> Result<C::Response>
@@ -57,20 +66,7 @@ impl HttpClient {
         let format = request.output_format();
         let is_gzip = request.is_gzip();
 
-        let response = self
-            .client
-            .get(url)
-            .query(&query_params)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(ApiError::Network(
-                reqwest::Error::from(response.error_for_status().unwrap_err())
-            ));
-        }
-
-        let data = response.bytes().await?.to_vec();
+        let data = self.fetcher.fetch_bytes(url, &query_params).await?;
         api_client.parse_response(data, &format, is_gzip).await
     }
 }
@@ -79,4 +75,59 @@ impl Default for HttpClient {
     fn default() -> Self {
         Self::new().expect("Failed to create HTTP client")
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::endpoints::{NarouRequest, NarouResponse};
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct StubNarouApi(String);
+
+    impl ApiClient for StubNarouApi {
+        type Request = NarouRequest;
+        type Response = NarouResponse;
+
+        fn base_url(&self) -> &str {
+            &self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_through_shared_fetcher() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/novelapi/api/"))
+            .and(query_param("ncode", "n7775do"))
+            .and(query_param("out", "json"))
+            .and(header("user-agent", "SharedBot/1.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"[{"allcount":1},{"ncode":"N7775DO","title":"テスト"}]"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let fetcher = HtmlFetcher::from_config(
+            FetcherConfig::new()
+                .user_agent_mode(UserAgentMode::Fixed(Some("SharedBot/1.0".to_string())))
+                .delay(RequestDelayConfig::disabled()),
+        )
+        .unwrap();
+        let client = HttpClient::from_fetcher(fetcher.clone());
+
+        let mut request = NarouRequest::new();
+        request.ncode = Some("n7775do".to_string());
+        let api = StubNarouApi(format!("{}/novelapi/api/", server.uri()));
+        let response = client.execute(&api, &request).await.unwrap();
+
+        assert_eq!(response.allcount, Some(1));
+        assert_eq!(response.novels[0].title.as_deref(), Some("テスト"));
+        assert_eq!(fetcher.metrics().requests, 1);
+
+        let missing = StubNarouApi(format!("{}/missing/", server.uri()));
+        let err = client.execute(&missing, &request).await.unwrap_err();
+        assert!(matches!(err, ApiError::HttpStatus(status) if status.as_u16() == 404));
+    }
+}
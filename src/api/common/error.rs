@@ -3,6 +3,7 @@ use std::fmt;
 #[derive(Debug)]
 pub enum ApiError {
     Network(reqwest::Error),
+    HttpStatus(reqwest::StatusCode),
     Serialization(String),
     Deserialization(String),
     InvalidFormat(String),
@@ -14,6 +15,7 @@ impl fmt::Display for ApiError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ApiError::Network(e) => write!(f, "Network error: {}", e),
+            ApiError::HttpStatus(status) => write!(f, "HTTP status error: {}", status),
             ApiError::Serialization(e) => write!(f, "Serialization error: {}", e),
             ApiError::Deserialization(e) => write!(f, "Deserialization error: {}", e),
             ApiError::InvalidFormat(format) => write!(f, "Invalid format: {}", format),
@@ -31,6 +33,18 @@ impl From<reqwest::Error> for ApiError {
     }
 }
 
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        if let Some(status) = err.downcast_ref::<crate::HttpStatusError>() {
+            return ApiError::HttpStatus(status.status);
+        }
+        match err.downcast::<reqwest::Error>() {
+            Ok(e) => ApiError::Network(e),
+            Err(e) => ApiError::Other(e.to_string()),
+        }
+    }
+}
+
 impl From<serde_json::Error> for ApiError {
     fn from(err: serde_json::Error) -> Self {
         ApiError::Deserialization(err.to_string())
@@ -29,15 +29,18 @@ impl OutputFormat {
             _ => OutputFormat::Yaml,
         }
     }
+}
 
-    pub fn to_string(&self) -> String {
-        match self {
-            OutputFormat::Yaml => "yaml".to_string(),
-            OutputFormat::Json => "json".to_string(),
-            OutputFormat::Php => "php".to_string(),
-            OutputFormat::Atom => "atom".to_string(),
-            OutputFormat::Jsonp(_) => "jsonp".to_string(),
-        }
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Json => "json",
+            OutputFormat::Php => "php",
+            OutputFormat::Atom => "atom",
+            OutputFormat::Jsonp(_) => "jsonp",
+        };
+        f.write_str(s)
     }
 }
 
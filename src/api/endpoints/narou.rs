@@ -1,4 +1,4 @@
-use crate::api::common::{ApiClient, ApiResponse, ApiRequest};
+use crate::api::common::{ApiClient, ApiResponse};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
@@ -55,6 +55,12 @@ pub struct NarouRequest {
     pub updatetype: Option<u8>,
 }
 
+impl Default for NarouRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NarouRequest {
     pub fn new() -> Self {
         Self {
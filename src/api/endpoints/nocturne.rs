@@ -51,6 +51,12 @@ pub struct NocturneRequest {
     pub updatetype: Option<u8>,
 }
 
+impl Default for NocturneRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NocturneRequest {
     pub fn new() -> Self {
         Self {
@@ -22,6 +22,12 @@ pub struct UserRequest {
     pub callback: Option<String>,
 }
 
+impl Default for UserRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UserRequest {
     pub fn new() -> Self {
         Self {
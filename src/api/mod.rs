@@ -1,5 +1,6 @@
 pub mod common;
 pub mod endpoints;
+pub mod paging;
 
 pub use common::{ApiClient, ApiError, ApiRequest, ApiResponse, HttpClient, OutputFormat, Result};
 pub use endpoints::{
@@ -8,4 +9,5 @@ pub use endpoints::{
     NocturneApiClient, NocturneRequest, NocturneResponse,
     RankingApiClient, RankingRequest, RankingResponse,
     UserApiClient, UserRequest, UserResponse,
-};
\ No newline at end of file
+};
+pub use paging::{Paged, PagedRequest, PagedResponse};
@@ -0,0 +1,231 @@
+use crate::api::common::{ApiClient, HttpClient, Result};
+use crate::api::endpoints::narou::{NarouNovelInfo, NarouRequest, NarouResponse};
+use crate::api::endpoints::nocturne::{NocturneNovelInfo, NocturneRequest, NocturneResponse};
+use crate::api::endpoints::user::{NarouUserInfo, UserRequest, UserResponse};
+
+/// Largest `lim` the search APIs accept.
+pub const MAX_PAGE_SIZE: u32 = 500;
+/// Largest `st` the search APIs accept; results beyond it cannot be reached.
+pub const MAX_START: u32 = 2000;
+
+/// A search request whose result window is set with `st` and `lim`.
+pub trait PagedRequest: Clone {
+    fn set_window(&mut self, start: u32, limit: u32);
+}
+
+/// A search response with `allcount` and a list of results.
+pub trait PagedResponse {
+    type Item;
+
+    fn allcount(&self) -> Option<u32>;
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+/// Results collected over several pages.
+#[derive(Debug, Clone)]
+pub struct Paged<T> {
+    /// Total number of matches reported by the API.
+    pub allcount: Option<u32>,
+    pub items: Vec<T>,
+}
+
+impl HttpClient {
+    /// Runs a search and keeps requesting the next window until `max_results`
+    /// items (all matches if `None`) are collected or the API's `st` limit is reached
+    /// (at most `MAX_START + MAX_PAGE_SIZE - 1` items).
+    pub async fn execute_all<C>(
+        &self,
+        api_client: &C,
+        request: &C::Request,
+        max_results: Option<u32>,
+    ) -> Result<Paged<<C::Response as PagedResponse>::Item>>
+    where
+        C: ApiClient + Send + Sync,
+        C::Request: PagedRequest + Send + Sync,
+        C::Response: PagedResponse,
+    {
+        let mut request = request.clone();
+        let mut allcount = None;
+        let mut items = Vec::new();
+        let mut start: u32 = 1;
+
+        loop {
+            let remaining = max_results.map(|max| max.saturating_sub(items.len() as u32)).unwrap_or(MAX_PAGE_SIZE);
+            // Past `MAX_START` the last window is moved back to `st=MAX_START`,
+            // and the results already collected are skipped.
+            let overlap = start.saturating_sub(MAX_START);
+            let limit = remaining.min(MAX_PAGE_SIZE.saturating_sub(overlap));
+            if limit == 0 {
+                break;
+            }
+
+            request.set_window(start - overlap, limit + overlap);
+            let response = self.execute(api_client, &request).await?;
+            allcount = response.allcount().or(allcount);
+            let page = response.into_items();
+            let received = (page.len() as u32).saturating_sub(overlap);
+            items.extend(page.into_iter().skip(overlap as usize));
+
+            if overlap > 0 || received < limit || allcount.is_some_and(|count| items.len() as u32 >= count) {
+                break;
+            }
+            start += received;
+        }
+
+        Ok(Paged { allcount, items })
+    }
+}
+
+impl PagedRequest for NarouRequest {
+    fn set_window(&mut self, start: u32, limit: u32) {
+        self.st = Some(start);
+        self.lim = Some(limit);
+    }
+}
+
+impl PagedRequest for NocturneRequest {
+    fn set_window(&mut self, start: u32, limit: u32) {
+        self.st = Some(start);
+        self.lim = Some(limit);
+    }
+}
+
+impl PagedRequest for UserRequest {
+    fn set_window(&mut self, start: u32, limit: u32) {
+        self.st = Some(start);
+        self.lim = Some(limit);
+    }
+}
+
+impl PagedResponse for NarouResponse {
+    type Item = NarouNovelInfo;
+
+    fn allcount(&self) -> Option<u32> {
+        self.allcount
+    }
+
+    fn into_items(self) -> Vec<NarouNovelInfo> {
+        self.novels
+    }
+}
+
+impl PagedResponse for NocturneResponse {
+    type Item = NocturneNovelInfo;
+
+    fn allcount(&self) -> Option<u32> {
+        self.allcount
+    }
+
+    fn into_items(self) -> Vec<NocturneNovelInfo> {
+        self.novels
+    }
+}
+
+impl PagedResponse for UserResponse {
+    type Item = NarouUserInfo;
+
+    fn allcount(&self) -> Option<u32> {
+        self.allcount
+    }
+
+    fn into_items(self) -> Vec<NarouUserInfo> {
+        self.users
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FetcherConfig, HtmlFetcher, RequestDelayConfig, UserAgentMode};
+    use wiremock::matchers::{method, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct StubNarouApi(String);
+
+    impl ApiClient for StubNarouApi {
+        type Request = NarouRequest;
+        type Response = NarouResponse;
+
+        fn base_url(&self) -> &str {
+            &self.0
+        }
+    }
+
+    fn page(allcount: u32, ncodes: std::ops::Range<u32>) -> String {
+        let mut body = vec![format!(r#"{{"allcount":{}}}"#, allcount)];
+        body.extend(ncodes.map(|n| format!(r#"{{"ncode":"N{:04}AA"}}"#, n)));
+        format!("[{}]", body.join(","))
+    }
+
+    #[tokio::test]
+    async fn test_execute_all_pages() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("st", "1"))
+            .and(query_param("lim", "500"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(page(502, 0..500)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(query_param("st", "501"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(page(502, 500..502)))
+            .mount(&server)
+            .await;
+
+        let fetcher = HtmlFetcher::from_config(
+            FetcherConfig::new()
+                .user_agent_mode(UserAgentMode::Fixed(Some("PagerBot/1.0".to_string())))
+                .delay(RequestDelayConfig::disabled()),
+        )
+        .unwrap();
+        let client = HttpClient::from_fetcher(fetcher.clone());
+        let api = StubNarouApi(format!("{}/novelapi/api/", server.uri()));
+
+        let all = client.execute_all(&api, &NarouRequest::new(), None).await.unwrap();
+        assert_eq!(all.allcount, Some(502));
+        assert_eq!(all.items.len(), 502);
+        assert_eq!(all.items[501].ncode.as_deref(), Some("N0501AA"));
+        assert_eq!(fetcher.metrics().requests, 2);
+
+        // Stops once max_results is reached without asking for the next window
+        let first = client.execute_all(&api, &NarouRequest::new(), Some(500)).await.unwrap();
+        assert_eq!(first.items.len(), 500);
+        assert_eq!(fetcher.metrics().requests, 3);
+    }
+
+    #[tokio::test]
+    async fn test_execute_all_clamps_last_window() {
+        let server = MockServer::start().await;
+        for start in [1, 501, 1001, 1501] {
+            Mock::given(method("GET"))
+                .and(query_param("st", start.to_string()))
+                .and(query_param("lim", "500"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(page(3000, start - 1..start + 499)))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(query_param("st", "2000"))
+            .and(query_param("lim", "500"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(page(3000, 1999..2499)))
+            .mount(&server)
+            .await;
+
+        let fetcher = HtmlFetcher::from_config(
+            FetcherConfig::new()
+                .user_agent_mode(UserAgentMode::Fixed(Some("PagerBot/1.0".to_string())))
+                .delay(RequestDelayConfig::disabled()),
+        )
+        .unwrap();
+        let client = HttpClient::from_fetcher(fetcher.clone());
+        let api = StubNarouApi(format!("{}/novelapi/api/", server.uri()));
+
+        let all = client.execute_all(&api, &NarouRequest::new(), None).await.unwrap();
+        assert_eq!(all.items.len(), 2499);
+        // The overlapping first item of the clamped window is not duplicated
+        assert_eq!(all.items[1999].ncode.as_deref(), Some("N1999AA"));
+        assert_eq!(all.items[2000].ncode.as_deref(), Some("N2000AA"));
+        assert_eq!(all.items[2498].ncode.as_deref(), Some("N2498AA"));
+        assert_eq!(fetcher.metrics().requests, 5);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::export::{
+    BlankLines, EpubExporter, EpubMetadata, EpubOptions, HtmlExporter, HtmlOptions, HtmlTemplate, MarkdownExporter,
+    MarkdownOptions, TextExporter, TextOptions, TextStyle,
+};
+use crate::illustrations::{relink_illustrations, IllustrationStore};
+use crate::novel_parser::NovelIndex;
+use crate::novel_scraper::{Illustration, NovelContent, NovelType};
+use crate::ruby::RubyMode;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 保存する形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// 取得したHTMLそのまま（リーダーとOPDSサーバーが読む形式）
+    Html,
+    Epub,
+    /// プレーンテキスト
+    Text,
+    /// 青空文庫形式のテキスト
+    Aozora,
+    Markdown,
+    /// サイトの部品を取り除いたHTML
+    CleanHtml,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 6] = [
+        ExportFormat::Html,
+        ExportFormat::Epub,
+        ExportFormat::Text,
+        ExportFormat::Aozora,
+        ExportFormat::Markdown,
+        ExportFormat::CleanHtml,
+    ];
+
+    /// 設定ファイルの`export_formats`やJSONで使う名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Html => "html",
+            ExportFormat::Epub => "epub",
+            ExportFormat::Text => "text",
+            ExportFormat::Aozora => "aozora",
+            ExportFormat::Markdown => "markdown",
+            ExportFormat::CleanHtml => "clean-html",
+        }
+    }
+
+    /// 名前から形式を得る（大文字小文字は区別しない）
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// 保存のオプション
+#[derive(Debug, Clone)]
+pub struct SaveOptions {
+    /// 全話を1ファイルにまとめる（EPUB以外）
+    pub single_file: bool,
+    /// 縦書き（EPUBのみ）
+    pub vertical: bool,
+    /// 前書きを含める（生のHTML以外）
+    pub include_preface: bool,
+    /// 後書きを含める（生のHTML以外）
+    pub include_afterword: bool,
+    /// ルビの出力方法（Noneならプレーンテキストは括弧書き、それ以外はそのまま）
+    pub ruby: Option<RubyMode>,
+    /// 空行の扱い（プレーンテキスト・青空文庫のみ）
+    pub blank_lines: BlankLines,
+    /// 整形HTMLのテンプレート
+    pub template: Option<HtmlTemplate>,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            single_file: false,
+            vertical: false,
+            include_preface: true,
+            include_afterword: true,
+            ruby: None,
+            blank_lines: BlankLines::Keep,
+            template: None,
+        }
+    }
+}
+
+/// 取得した作品を出力ディレクトリに保存する（`fetch_novel`と`narou download`・`export`で共通）
+///
+/// EPUBは`{output}/{ncode}.epub`、それ以外は`{output}/{ncode}/`以下（1ファイルなら`{output}/{ncode}.*`）に書く。
+pub struct NovelWriter {
+    output: PathBuf,
+    options: SaveOptions,
+    metadata: EpubMetadata,
+    index: Option<NovelIndex>,
+    nocturne: bool,
+    /// 挿絵URL→`{output}/{ncode}/`からの相対パス（EPUB以外）
+    illustration_paths: BTreeMap<String, String>,
+    /// EPUBに埋め込む挿絵
+    illustrations: Vec<Illustration>,
+}
+
+impl NovelWriter {
+    /// `metadata`の作品名・作者名はEPUB以外の見出しにも使う
+    pub fn new(output: impl Into<PathBuf>, metadata: EpubMetadata, options: SaveOptions) -> Self {
+        Self {
+            output: output.into(),
+            options,
+            metadata,
+            index: None,
+            nocturne: false,
+            illustration_paths: BTreeMap::new(),
+            illustrations: Vec::new(),
+        }
+    }
+
+    /// 目次を設定（章見出しに使う）
+    pub fn index(mut self, index: NovelIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// ノクターンの作品か（`metadata.json`に記録する）
+    pub fn nocturne(mut self, nocturne: bool) -> Self {
+        self.nocturne = nocturne;
+        self
+    }
+
+    /// `{output}/{ncode}/images`の挿絵にリンクし直す（EPUB以外）
+    pub fn illustration_store(mut self, store: &IllustrationStore) -> Self {
+        self.illustration_paths = store.local_paths("images/");
+        self
+    }
+
+    /// EPUBに埋め込む挿絵
+    pub fn illustrations(mut self, illustrations: Vec<Illustration>) -> Self {
+        self.illustrations = illustrations;
+        self
+    }
+
+    /// 指定の形式で保存し、書き出したファイルを返す
+    pub fn write(&self, novel: &NovelContent, format: ExportFormat) -> Result<Vec<PathBuf>> {
+        let ncode = &novel.ncode;
+        let options = &self.options;
+        if format == ExportFormat::Epub {
+            fs::create_dir_all(&self.output)
+                .with_context(|| format!("Failed to create {}", self.output.display()))?;
+            let path = self.output.join(format!("{}.epub", ncode));
+            let mut exporter = EpubExporter::new(self.metadata.clone())
+                .options(EpubOptions {
+                    vertical: options.vertical,
+                    include_preface: options.include_preface,
+                    include_afterword: options.include_afterword,
+                    ruby: options.ruby.unwrap_or_default(),
+                })
+                .illustrations(self.illustrations.clone());
+            if let Some(index) = &self.index {
+                exporter = exporter.index(index.clone());
+            }
+            exporter.write_to(novel, &path)?;
+            return Ok(vec![path]);
+        }
+
+        let output_dir = if options.single_file { self.output.clone() } else { self.output.join(ncode) };
+        fs::create_dir_all(&output_dir).with_context(|| format!("Failed to create {}", output_dir.display()))?;
+        let combined_path = |ext: &str| output_dir.join(format!("{}.{}", ncode, ext));
+        // 1ファイルにまとめると出力先が1つ上になるので、画像の場所も`{ncode}/`を付ける
+        let illustration_paths: BTreeMap<String, String> = if options.single_file {
+            self.illustration_paths.iter().map(|(url, path)| (url.clone(), format!("{}/{}", ncode, path))).collect()
+        } else {
+            self.illustration_paths.clone()
+        };
+        let ruby = options.ruby.unwrap_or(match format {
+            ExportFormat::Text => RubyMode::Parenthesize,
+            _ => RubyMode::Keep,
+        });
+        let (title, author) = (self.metadata.title.clone(), self.metadata.author.clone());
+
+        match format {
+            ExportFormat::Html => self.write_raw_html(novel, &illustration_paths, &output_dir),
+            ExportFormat::Markdown => {
+                let mut exporter = MarkdownExporter::new(MarkdownOptions {
+                    include_preface: options.include_preface,
+                    include_afterword: options.include_afterword,
+                    ruby,
+                })
+                .header(title, author);
+                if let Some(index) = &self.index {
+                    exporter = exporter.index(index.clone());
+                }
+                for (url, path) in &illustration_paths {
+                    exporter = exporter.illustration_path(url, path);
+                }
+                if options.single_file {
+                    exporter.write_combined(novel, &combined_path("md"))?;
+                    Ok(vec![combined_path("md")])
+                } else {
+                    exporter.write_separate(novel, &output_dir)
+                }
+            }
+            ExportFormat::CleanHtml => {
+                let mut exporter = HtmlExporter::new(HtmlOptions {
+                    include_preface: options.include_preface,
+                    include_afterword: options.include_afterword,
+                    ruby,
+                })
+                .header(title, author);
+                if let Some(template) = &options.template {
+                    exporter = exporter.template(template.clone());
+                }
+                if let Some(index) = &self.index {
+                    exporter = exporter.index(index.clone());
+                }
+                for (url, path) in &illustration_paths {
+                    exporter = exporter.illustration_path(url, path);
+                }
+                if options.single_file {
+                    exporter.write_combined(novel, &combined_path("html"))?;
+                    Ok(vec![combined_path("html")])
+                } else {
+                    exporter.write_separate(novel, &output_dir)
+                }
+            }
+            ExportFormat::Text | ExportFormat::Aozora => {
+                let mut exporter = TextExporter::new(TextOptions {
+                    style: if format == ExportFormat::Aozora { TextStyle::Aozora } else { TextStyle::Plain },
+                    blank_lines: options.blank_lines,
+                    ruby,
+                    include_preface: options.include_preface,
+                    include_afterword: options.include_afterword,
+                })
+                .header(title, author);
+                if let Some(index) = &self.index {
+                    exporter = exporter.index(index.clone());
+                }
+                for (url, path) in &illustration_paths {
+                    exporter = exporter.illustration_path(url, path);
+                }
+                if options.single_file {
+                    exporter.write_combined(novel, &combined_path("txt"))?;
+                    Ok(vec![combined_path("txt")])
+                } else {
+                    exporter.write_separate(novel, &output_dir)
+                }
+            }
+            ExportFormat::Epub => unreachable!("EPUB is written above"),
+        }
+    }
+
+    /// 取得したHTMLをそのまま保存し、`metadata.json`も書く
+    fn write_raw_html(
+        &self,
+        novel: &NovelContent,
+        illustration_paths: &BTreeMap<String, String>,
+        output_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        if self.options.single_file {
+            let path = output_dir.join(format!("{}.html", novel.ncode));
+            let mut combined_html = String::new();
+            for episode in &novel.episodes {
+                combined_html.push_str(&format!("<!-- Episode {} -->\n", episode.episode_number));
+                combined_html.push_str(&relink_illustrations(&episode.html, illustration_paths));
+                combined_html.push_str("\n\n");
+            }
+            fs::write(&path, combined_html).with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path);
+        } else {
+            for episode in &novel.episodes {
+                let path = output_dir.join(episode_file_name(novel, episode.episode_number));
+                fs::write(&path, relink_illustrations(&episode.html, illustration_paths))
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                paths.push(path);
+            }
+        }
+
+        let metadata_path = output_dir.join("metadata.json");
+        let metadata = serde_json::json!({
+            "ncode": novel.ncode,
+            "type": match novel.novel_type {
+                NovelType::ShortStory => "short".to_string(),
+                NovelType::Serial { total_episodes } => format!("serial_{}", total_episodes),
+            },
+            "episode_count": novel.episode_count(),
+            "total_bytes": novel.total_size_bytes(),
+            "nocturne": self.nocturne,
+            "fetched_at": chrono::Utc::now().to_rfc3339(),
+        });
+        fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?)
+            .with_context(|| format!("Failed to write {}", metadata_path.display()))?;
+        paths.push(metadata_path);
+        Ok(paths)
+    }
+}
+
+/// 生HTMLのファイル名（連載は`0001.html`、短編は`{ncode}.html`）
+fn episode_file_name(novel: &NovelContent, episode_number: u32) -> String {
+    match novel.novel_type {
+        NovelType::ShortStory => format!("{}.html", novel.ncode),
+        NovelType::Serial { .. } => format!("{:04}.html", episode_number),
+    }
+}
+
+/// `fetch_novel`が保存した生HTML（`{ncode}/0001.html`・短編は`{ncode}/{ncode}.html`）を話数順に列挙
+pub fn episode_files(novel_dir: &Path, ncode: &str) -> Result<Vec<(u32, PathBuf)>> {
+    let mut files = Vec::new();
+    for file in fs::read_dir(novel_dir).with_context(|| format!("Failed to read {}", novel_dir.display()))? {
+        let path = file?.path();
+        let Some(stem) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".html")) else {
+            continue;
+        };
+        let episode_number = if stem == ncode {
+            0
+        } else {
+            match stem.parse() {
+                Ok(n) => n,
+                Err(_) => continue,
+            }
+        };
+        files.push((episode_number, path));
+    }
+    files.sort_by_key(|(n, _)| *n);
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel_scraper::Episode;
+
+    fn novel() -> NovelContent {
+        let html = fs::read_to_string("target_pages/narou/novel/n7775do-2.html").unwrap();
+        NovelContent {
+            ncode: "n7775do".to_string(),
+            novel_type: NovelType::Serial { total_episodes: 2 },
+            episodes: vec![
+                Episode { episode_number: 1, html: html.clone() },
+                Episode { episode_number: 2, html },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_write_formats() {
+        let dir = std::env::temp_dir().join(format!("wns-archive-{}", std::process::id()));
+        let index = NovelIndex::parse(&fs::read_to_string("target_pages/narou/novel_list/n7775do.html").unwrap());
+        let writer = NovelWriter::new(&dir, EpubMetadata::from_index("n7775do", &index), SaveOptions::default()).index(index);
+
+        // 生のHTMLはリーダーが読む配置で書く
+        let paths = writer.write(&novel(), ExportFormat::Html).unwrap();
+        assert_eq!(paths.last(), Some(&dir.join("n7775do/metadata.json")));
+        let files = episode_files(&dir.join("n7775do"), "n7775do").unwrap();
+        assert_eq!(files.iter().map(|(n, _)| *n).collect::<Vec<_>>(), vec![1, 2]);
+
+        // 目次の章見出しが残り、テキストのルビは括弧書きになる
+        let paths = writer.write(&novel(), ExportFormat::Text).unwrap();
+        let text = fs::read_to_string(&paths[0]).unwrap();
+        assert!(text.contains("はじめに"));
+        assert!(!text.contains('《'));
+
+        assert_eq!(writer.write(&novel(), ExportFormat::Epub).unwrap(), vec![dir.join("n7775do.epub")]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(ExportFormat::from_name("Clean-HTML"), Some(ExportFormat::CleanHtml));
+        assert_eq!(ExportFormat::from_name("pdf"), None);
+    }
+}
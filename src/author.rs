@@ -0,0 +1,217 @@
+use crate::api::endpoints::narou::NarouNovelInfo;
+use crate::api::endpoints::user::NarouUserInfo;
+use crate::api::{HttpClient, NarouApiClient, NarouRequest, UserApiClient, UserRequest};
+use crate::{BookmarkEntry, HtmlFetcher, NarouBookmarkScraper, NarouRatingScraper, RatingEntry};
+use anyhow::{bail, Context, Result};
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+
+/// ユーザー別の活動報告Atomフィード（`{}`はユーザーID）
+pub const ACTIVITY_FEED_URL: &str = "https://api.syosetu.com/writerblog/{}.Atom";
+
+/// 活動報告1件（Atomフィードの`entry`）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActivityReport {
+    pub title: String,
+    pub url: Option<String>,
+    /// 投稿日時（RFC 3339）
+    pub published: Option<String>,
+    pub updated: Option<String>,
+    /// 本文の出だし100文字
+    pub summary: Option<String>,
+}
+
+/// 作者についてまとめて取得した情報
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorProfile {
+    pub userid: u32,
+    /// ユーザ検索APIの情報
+    pub user: NarouUserInfo,
+    /// 小説APIで`userid`を指定して検索した全作品（新着順）
+    pub novels: Vec<NarouNovelInfo>,
+    /// 評価をつけた作品
+    pub ratings: Vec<RatingEntry>,
+    /// ブックマーク（カテゴリ番号と組）
+    pub bookmarks: Vec<(u32, BookmarkEntry)>,
+    /// 最近の活動報告（最大100件）
+    pub activity_reports: Vec<ActivityReport>,
+    /// 取得できなかった項目（非公開のブックマークなど）
+    pub warnings: Vec<String>,
+}
+
+/// `AuthorProfile`を組み立てる
+///
+/// 評価・ブックマークは件数に応じてページ数が増えるので、不要なら外せる。
+pub struct AuthorProfileFetcher {
+    fetcher: HtmlFetcher,
+    client: HttpClient,
+    novels: bool,
+    ratings: bool,
+    bookmarks: bool,
+    activity_reports: bool,
+}
+
+impl AuthorProfileFetcher {
+    pub fn new(fetcher: HtmlFetcher) -> Self {
+        Self {
+            client: HttpClient::from_fetcher(fetcher.clone()),
+            fetcher,
+            novels: true,
+            ratings: true,
+            bookmarks: true,
+            activity_reports: true,
+        }
+    }
+
+    pub fn novels(mut self, enabled: bool) -> Self {
+        self.novels = enabled;
+        self
+    }
+
+    pub fn ratings(mut self, enabled: bool) -> Self {
+        self.ratings = enabled;
+        self
+    }
+
+    pub fn bookmarks(mut self, enabled: bool) -> Self {
+        self.bookmarks = enabled;
+        self
+    }
+
+    pub fn activity_reports(mut self, enabled: bool) -> Self {
+        self.activity_reports = enabled;
+        self
+    }
+
+    /// 指定ユーザーの情報をまとめて取得
+    ///
+    /// ユーザーが見つからない場合はエラー。評価・ブックマーク・活動報告が取得できない場合は`warnings`に残して続ける。
+    pub async fn fetch(&self, userid: u32) -> Result<AuthorProfile> {
+        let mut request = UserRequest::new();
+        request.userid = Some(userid);
+        let Some(user) = self.client.execute(&UserApiClient, &request).await?.users.into_iter().next() else {
+            bail!("User {} was not found", userid);
+        };
+
+        let mut profile = AuthorProfile {
+            userid,
+            user,
+            novels: Vec::new(),
+            ratings: Vec::new(),
+            bookmarks: Vec::new(),
+            activity_reports: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        if self.novels {
+            let mut request = NarouRequest::new();
+            request.userid = Some(userid.to_string());
+            request.order = Some("new".to_string());
+            profile.novels = self.client.execute_all(&NarouApiClient, &request, None).await?.items;
+        }
+        if self.ratings {
+            match NarouRatingScraper::new(self.fetcher.clone()).fetch_all_ratings(userid).await {
+                Ok(ratings) => profile.ratings = ratings,
+                Err(e) => profile.warnings.push(format!("ratings: {:#}", e)),
+            }
+        }
+        if self.bookmarks {
+            match NarouBookmarkScraper::new(self.fetcher.clone()).fetch_all_bookmarks(&userid.to_string()).await {
+                Ok(bookmarks) => profile.bookmarks = bookmarks,
+                Err(e) => profile.warnings.push(format!("bookmarks: {:#}", e)),
+            }
+        }
+        if self.activity_reports {
+            match self.fetch_activity_reports(userid).await {
+                Ok(reports) => profile.activity_reports = reports,
+                Err(e) => profile.warnings.push(format!("activity reports: {:#}", e)),
+            }
+        }
+        Ok(profile)
+    }
+
+    /// 活動報告のAtomフィードを取得
+    pub async fn fetch_activity_reports(&self, userid: u32) -> Result<Vec<ActivityReport>> {
+        let url = ACTIVITY_FEED_URL.replace("{}", &userid.to_string());
+        let xml = self.fetcher.fetch(&url).await.with_context(|| format!("Failed to fetch {}", url))?;
+        Ok(parse_activity_feed(&xml))
+    }
+}
+
+/// 活動報告のAtomフィードから`entry`を抽出
+pub fn parse_activity_feed(xml: &str) -> Vec<ActivityReport> {
+    let document = Html::parse_document(xml);
+    let entry_selector = Selector::parse("entry").unwrap();
+    let title_selector = Selector::parse("title").unwrap();
+    let link_selector = Selector::parse("link").unwrap();
+    let published_selector = Selector::parse("published").unwrap();
+    let updated_selector = Selector::parse("updated").unwrap();
+    let summary_selector = Selector::parse("summary").unwrap();
+
+    let text = |entry: ElementRef, selector: &Selector| {
+        entry
+            .select(selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .filter(|t| !t.is_empty())
+    };
+
+    document
+        .select(&entry_selector)
+        .filter_map(|entry| {
+            Some(ActivityReport {
+                title: text(entry, &title_selector)?,
+                url: entry
+                    .select(&link_selector)
+                    .find(|link| link.value().attr("rel").is_none_or(|rel| rel == "alternate"))
+                    .and_then(|link| link.value().attr("href"))
+                    .map(str::to_string),
+                published: text(entry, &published_selector),
+                updated: text(entry, &updated_selector),
+                summary: text(entry, &summary_selector),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_activity_feed() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xml:lang="ja" xmlns="http://www.w3.org/2005/Atom">
+<title>黒留ハガネの活動報告</title>
+<updated>2025-08-10T21:00:00+09:00</updated>
+<entry>
+<title>書籍化のお知らせ</title>
+<link rel="alternate" type="text/html" href="https://mypage.syosetu.com/mypageblog/view/userid/59791/blogkey/3400001/"/>
+<id>https://mypage.syosetu.com/mypageblog/view/userid/59791/blogkey/3400001/</id>
+<published>2025-08-10T21:00:00+09:00</published>
+<updated>2025-08-10T21:05:00+09:00</updated>
+<summary>いつも&lt;応援&gt;ありがとうございます。</summary>
+<comment>59791/黒留ハガネ</comment>
+</entry>
+<entry>
+<title>更新再開</title>
+<link href="https://mypage.syosetu.com/mypageblog/view/userid/59791/blogkey/3300001/"/>
+<published>2025-07-01T12:00:00+09:00</published>
+</entry>
+</feed>"#;
+        let reports = parse_activity_feed(xml);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(
+            reports[0],
+            ActivityReport {
+                title: "書籍化のお知らせ".to_string(),
+                url: Some("https://mypage.syosetu.com/mypageblog/view/userid/59791/blogkey/3400001/".to_string()),
+                published: Some("2025-08-10T21:00:00+09:00".to_string()),
+                updated: Some("2025-08-10T21:05:00+09:00".to_string()),
+                summary: Some("いつも<応援>ありがとうございます。".to_string()),
+            }
+        );
+        assert_eq!(reports[1].updated, None);
+        assert!(reports[1].url.as_deref().unwrap().ends_with("/3300001/"));
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use std::path::PathBuf;
+use web_novel_scraper::{diff_paragraphs, ParagraphChange, ParsedEpisode, RevisionStore};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Show paragraph-level changes between stored episode revisions", long_about = None)]
+struct Args {
+    /// Revision directory written by `fetch_novel --keep-revisions` (e.g., ./output/n7775do/revisions)
+    #[arg(short, long)]
+    dir: PathBuf,
+
+    /// Episode number (lists episodes with more than one revision when omitted)
+    #[arg(short, long)]
+    episode: Option<u32>,
+
+    /// Older revision id (defaults to the one before --to)
+    #[arg(long)]
+    from: Option<String>,
+
+    /// Newer revision id (defaults to the latest)
+    #[arg(long)]
+    to: Option<String>,
+
+    /// List the stored revisions of the episode instead of diffing
+    #[arg(short, long)]
+    list: bool,
+
+    /// Output format (json or text)
+    #[arg(short, long, default_value = "text")]
+    format: String,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let store = RevisionStore::open(&args.dir);
+
+    let Some(episode_number) = args.episode else {
+        // 改稿のある話の一覧
+        for number in store.episode_numbers()? {
+            let revisions = store.revisions(number)?;
+            if revisions.len() > 1 {
+                println!("episode {:>4}: {} revisions", number, revisions.len());
+            }
+        }
+        return Ok(());
+    };
+
+    let revisions = store.revisions(episode_number)?;
+    if args.list {
+        for revision in &revisions {
+            println!("{}", revision.id);
+        }
+        return Ok(());
+    }
+
+    let position = |id: &str| {
+        revisions.iter().position(|r| r.id == id)
+            .ok_or_else(|| anyhow!("Revision {} of episode {} not found", id, episode_number))
+    };
+    let to = match &args.to {
+        Some(id) => position(id)?,
+        None => revisions.len().checked_sub(1)
+            .ok_or_else(|| anyhow!("No revisions stored for episode {}", episode_number))?,
+    };
+    let from = match &args.from {
+        Some(id) => position(id)?,
+        None => to.checked_sub(1)
+            .ok_or_else(|| anyhow!("Episode {} has only one revision", episode_number))?,
+    };
+
+    let (from, to) = (&revisions[from], &revisions[to]);
+    let old = ParsedEpisode::parse(&store.load(episode_number, &from.id)?);
+    let new = ParsedEpisode::parse(&store.load(episode_number, &to.id)?);
+    let changes = diff_paragraphs(&old, &new);
+
+    match args.format.as_str() {
+        "json" => {
+            let json_output = serde_json::json!({
+                "episode": episode_number,
+                "from": from.id,
+                "to": to.id,
+                "changes": changes,
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        }
+        _ => {
+            println!("--- {} ({})", from.id, old.subtitle);
+            println!("+++ {} ({})", to.id, new.subtitle);
+            for change in &changes {
+                match change {
+                    ParagraphChange::Added { id, text } => println!("+{:<6} {}", id, text),
+                    ParagraphChange::Removed { id, text } => println!("-{:<6} {}", id, text),
+                    ParagraphChange::Modified { old_id, new_id, old_text, new_text } => {
+                        println!("-{:<6} {}", old_id, old_text);
+                        println!("+{:<6} {}", new_id, new_text);
+                    }
+                }
+            }
+            println!("\n{} paragraphs changed", changes.len());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use anyhow::{bail, Result};
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use clap::Parser;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use web_novel_scraper::feed::{fetch_feed_entries, library_feed_entries};
+use web_novel_scraper::tracker::read_watchlist;
+use web_novel_scraper::{AtomFeed, HtmlFetcher, Library, NarouNovelScraper, RequestDelayConfig};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Generate an Atom feed of new episodes for followed novels", long_about = None)]
+struct Args {
+    /// Watchlist file (one ncode per line, # starts a comment)
+    #[arg(short, long)]
+    watchlist: Option<PathBuf>,
+
+    /// Novel codes to include (in addition to the watchlist)
+    #[arg(short, long)]
+    ncode: Vec<String>,
+
+    /// Number of latest episodes per novel
+    #[arg(long, default_value_t = 5)]
+    latest: usize,
+
+    /// Maximum number of entries in the feed
+    #[arg(long, default_value_t = 100)]
+    limit: usize,
+
+    /// Build the feed from episodes stored in this SQLite library instead of fetching
+    #[arg(long)]
+    library: Option<PathBuf>,
+
+    /// Write the feed to this file
+    #[arg(short, long, default_value = "./output/feed.atom")]
+    output: PathBuf,
+
+    /// Serve the feed at http://ADDR/feed.atom (e.g., 127.0.0.1:8080)
+    #[arg(long)]
+    serve: Option<String>,
+
+    /// Seconds between feed rebuilds while serving
+    #[arg(long, default_value_t = 1800)]
+    refresh: u64,
+
+    /// Use Nocturne site (R18) instead of regular Narou
+    #[arg(long)]
+    nocturne: bool,
+
+    /// Minimum delay between requests in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    min_delay: u64,
+
+    /// Maximum delay between requests in milliseconds
+    #[arg(long, default_value_t = 3000)]
+    max_delay: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let mut ncodes = match &args.watchlist {
+        Some(path) => read_watchlist(path)?,
+        None => Vec::new(),
+    };
+    for ncode in &args.ncode {
+        let ncode = ncode.to_lowercase();
+        if !ncodes.contains(&ncode) {
+            ncodes.push(ncode);
+        }
+    }
+    if ncodes.is_empty() {
+        bail!("No novels to include: pass --watchlist or --ncode");
+    }
+
+    let fetcher = HtmlFetcher::default();
+    fetcher.set_delay_config(RequestDelayConfig::new(args.min_delay, args.max_delay));
+    let scraper = if args.nocturne {
+        NarouNovelScraper::new_nocturne(fetcher)
+    } else {
+        NarouNovelScraper::new(fetcher)
+    };
+
+    let Some(addr) = &args.serve else {
+        let atom = build_feed(&args, &scraper, &ncodes).await?;
+        write_feed(&args, &atom)?;
+        println!("📰 Feed saved to: {}", args.output.display());
+        return Ok(());
+    };
+
+    // 配信しながら定期的に作り直す
+    let feed = Arc::new(RwLock::new(build_feed(&args, &scraper, &ncodes).await?));
+    write_feed(&args, &feed.read().unwrap())?;
+
+    let app = Router::new()
+        .route("/feed.atom", get(serve_feed))
+        .with_state(feed.clone());
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    println!("📰 Serving feed at http://{}/feed.atom", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(args.refresh)).await;
+        match build_feed(&args, &scraper, &ncodes).await {
+            Ok(atom) => {
+                write_feed(&args, &atom)?;
+                *feed.write().unwrap() = atom;
+            }
+            Err(e) => eprintln!("⚠️  Failed to rebuild feed: {:#}", e),
+        }
+    }
+}
+
+async fn serve_feed(State(feed): State<Arc<RwLock<String>>>) -> impl IntoResponse {
+    let atom = feed.read().unwrap().clone();
+    ([(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")], atom)
+}
+
+async fn build_feed(args: &Args, scraper: &NarouNovelScraper, ncodes: &[String]) -> Result<String> {
+    let mut feed = AtomFeed::new(
+        if args.nocturne { "ノクターン 新着話" } else { "小説家になろう 新着話" },
+        format!("urn:narou:feed:{}", ncodes.join("-")),
+    );
+    feed.self_url = args.serve.as_ref().map(|addr| format!("http://{}/feed.atom", addr));
+
+    match &args.library {
+        Some(path) => {
+            let library = Library::open(path)?;
+            for ncode in ncodes {
+                feed.extend(library_feed_entries(&library, ncode, args.latest, args.nocturne)?);
+            }
+        }
+        None => {
+            for ncode in ncodes {
+                match fetch_feed_entries(scraper, ncode, args.latest).await {
+                    Ok(entries) => feed.extend(entries),
+                    Err(e) => eprintln!("⚠️  Skipping {}: {:#}", ncode, e),
+                }
+            }
+        }
+    }
+
+    Ok(feed.render(args.limit))
+}
+
+fn write_feed(args: &Args, atom: &str) -> Result<()> {
+    if let Some(parent) = args.output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&args.output, atom)?;
+    Ok(())
+}
@@ -1,8 +1,12 @@
 use anyhow::Result;
-use clap::Parser;
-use web_novel_scraper::{HtmlFetcher, NarouNovelScraper, NovelType, RequestDelayConfig};
-use std::fs;
-use std::path::PathBuf;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use web_novel_scraper::api::{HttpClient, NarouApiClient, NarouRequest, NocturneApiClient, NocturneRequest};
+use web_novel_scraper::{
+    BlankLines, Config, EpubMetadata, ExportFormat, HtmlFetcher, HtmlTemplate, IllustrationStore, Library, NarouNovelScraper,
+    NovelContent, NovelIndex, NovelType, NovelWriter, ParsedEpisode, Profile, RevisionStore, RubyMode, SaveOptions, SearchIndex,
+};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Fetch all episodes from Narou novel", long_about = None)]
@@ -38,6 +42,134 @@ struct Args {
     /// Save as single file instead of separate files
     #[arg(long)]
     single_file: bool,
+
+    /// Output format (repeatable; default: the profile's export_formats, else html)
+    #[arg(short, long, value_enum)]
+    format: Vec<OutputFormatArg>,
+
+    /// Vertical writing (EPUB only)
+    #[arg(long)]
+    vertical: bool,
+
+    /// Omit prefaces (all formats except html)
+    #[arg(long)]
+    no_preface: bool,
+
+    /// Omit afterwords (all formats except html)
+    #[arg(long)]
+    no_afterword: bool,
+
+    /// HTML template file for clean-html ({{title}}, {{novel_title}}, {{author}}, {{nav}}, {{content}})
+    #[arg(long)]
+    template: Option<PathBuf>,
+
+    /// How to render ruby (default: parenthesize for text, keep for other formats)
+    #[arg(long, value_enum)]
+    ruby: Option<RubyModeArg>,
+
+    /// How to treat blank lines (text and aozora only)
+    #[arg(long, value_enum, default_value = "keep")]
+    blank_lines: BlankLinesArg,
+
+    /// Do not download illustrations (EPUB only)
+    #[arg(long)]
+    no_illustrations: bool,
+
+    /// Keep illustrations in {output}/{ncode}/images and relink them for offline reading (non-EPUB formats)
+    #[arg(long, conflicts_with = "no_illustrations")]
+    illustrations: bool,
+
+    /// Keep every changed version of each episode in {output}/{ncode}/revisions (see diff_revisions)
+    #[arg(long)]
+    keep_revisions: bool,
+
+    /// Also store the episodes and API metadata in this SQLite library
+    #[arg(long)]
+    library: Option<PathBuf>,
+
+    /// Also add the episodes to this full-text search index (see search_episodes)
+    #[arg(long)]
+    index: Option<PathBuf>,
+
+    /// Config file (default: $XDG_CONFIG_HOME/web-novel-scraper/config.toml)
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Profile from the config file; options given here take precedence
+    #[arg(long)]
+    profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+enum OutputFormatArg {
+    /// Raw episode HTML
+    Html,
+    /// EPUB 3 e-book
+    Epub,
+    /// UTF-8 plain text
+    Text,
+    /// Aozora Bunko notation text
+    Aozora,
+    /// Markdown with ruby kept as inline HTML
+    Markdown,
+    /// Novel text only, without ads and site widgets
+    CleanHtml,
+}
+
+impl From<OutputFormatArg> for ExportFormat {
+    fn from(arg: OutputFormatArg) -> Self {
+        match arg {
+            OutputFormatArg::Html => ExportFormat::Html,
+            OutputFormatArg::Epub => ExportFormat::Epub,
+            OutputFormatArg::Text => ExportFormat::Text,
+            OutputFormatArg::Aozora => ExportFormat::Aozora,
+            OutputFormatArg::Markdown => ExportFormat::Markdown,
+            OutputFormatArg::CleanHtml => ExportFormat::CleanHtml,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum RubyModeArg {
+    /// Keep ruby markup (<ruby> in HTML, ｜漢字《かな》 in text)
+    Keep,
+    /// Base text only
+    Strip,
+    /// 漢字（かな）
+    Parenthesize,
+    /// Replace the base text with its hiragana reading
+    HiraganaOnly,
+}
+
+impl From<RubyModeArg> for RubyMode {
+    fn from(arg: RubyModeArg) -> Self {
+        match arg {
+            RubyModeArg::Keep => RubyMode::Keep,
+            RubyModeArg::Strip => RubyMode::Strip,
+            RubyModeArg::Parenthesize => RubyMode::Parenthesize,
+            RubyModeArg::HiraganaOnly => RubyMode::HiraganaOnly,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum BlankLinesArg {
+    /// Keep every blank line
+    Keep,
+    /// Collapse consecutive blank lines into one
+    Collapse,
+    /// Remove all blank lines
+    Remove,
+}
+
+impl From<BlankLinesArg> for BlankLines {
+    fn from(arg: BlankLinesArg) -> Self {
+        match arg {
+            BlankLinesArg::Keep => BlankLines::Keep,
+            BlankLinesArg::Collapse => BlankLines::Collapse,
+            BlankLinesArg::Remove => BlankLines::Remove,
+        }
+    }
 }
 
 #[derive(Debug, Clone, clap::ValueEnum)]
@@ -48,7 +180,36 @@ enum NovelTypeArg {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches)?;
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    // 設定ファイルのプロファイル（コマンドラインで指定した値が優先）
+    let profile = Config::load_or_default(args.config.as_deref())?
+        .profile(args.profile.as_deref())?
+        .merge(&Profile {
+            min_delay: given("min_delay").then_some(args.min_delay),
+            max_delay: given("max_delay").then_some(args.max_delay),
+            nocturne: args.nocturne.then_some(true),
+            ..Profile::default()
+        });
+    if !given("output") {
+        if let Some(archive) = &profile.archive {
+            args.output = archive.clone();
+        }
+    }
+    // 保存する形式（`--format`、なければプロファイルの`export_formats`のすべて）
+    let formats: Vec<ExportFormat> = match &profile.export_formats {
+        _ if !args.format.is_empty() => args.format.iter().map(|&f| f.into()).collect(),
+        Some(names) if !names.is_empty() => names.iter()
+            .map(|name| ExportFormat::from_name(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown export format in profile: {}", name)))
+            .collect::<Result<_>>()?,
+        _ => vec![ExportFormat::Html],
+    };
+    let needs_header = formats.iter().any(|f| *f != ExportFormat::Html);
+    args.library = args.library.or_else(|| profile.library.clone());
+    args.nocturne = profile.nocturne();
 
     // 引数検証
     let novel_type = match args.novel_type {
@@ -60,16 +221,14 @@ async fn main() -> Result<()> {
         }
     };
 
-    // Fetcherの設定
-    let fetcher = HtmlFetcher::default();
-    let delay_config = RequestDelayConfig::new(args.min_delay, args.max_delay);
-    fetcher.set_delay_config(delay_config);
+    // Fetcherの設定（遅延・User-Agent・プロキシ・Cookieはプロファイルから）
+    let fetcher = profile.fetcher()?;
 
     // スクレイパーの作成
     let scraper = if args.nocturne {
-        NarouNovelScraper::new_nocturne(fetcher)
+        NarouNovelScraper::new_nocturne(fetcher.clone())
     } else {
-        NarouNovelScraper::new(fetcher)
+        NarouNovelScraper::new(fetcher.clone())
     };
 
     println!("🔍 Fetching novel: {}", args.ncode);
@@ -86,66 +245,153 @@ async fn main() -> Result<()> {
     println!("\n✅ Successfully fetched {} episodes", novel_content.episode_count());
     println!("📊 Total size: {} bytes", novel_content.total_size_bytes());
 
-    // 出力ディレクトリの作成
-    let output_dir = if args.single_file {
-        args.output.clone()
+    if let Some(path) = &args.library {
+        save_to_library(path, &args, &fetcher, &novel_content).await?;
+    }
+
+    if let Some(path) = &args.index {
+        let updated = SearchIndex::open(path)?.index_novel(&novel_content)?;
+        println!("🔎 {} episodes indexed in: {}", updated, path.display());
+    }
+
+    // 章立てとサブタイトル・改稿日時は目次ページから取得（短編には目次がない）
+    let index = match novel_content.novel_type {
+        NovelType::Serial { .. } if args.keep_revisions || needs_header => {
+            Some(scraper.fetch_index(&args.ncode).await?)
+        }
+        _ => None,
+    };
+
+    // 改稿された話は過去の版を残して保存
+    if args.keep_revisions {
+        let store = RevisionStore::open(args.output.join(&args.ncode).join("revisions"));
+        let saved = store.save_novel(&novel_content, index.as_ref())?;
+        println!("📝 {} new revisions saved to: {}", saved, store.dir().display());
+    }
+
+    // 挿絵の取得（保存済みの画像は取り直さない）
+    let store = if args.illustrations {
+        let mut store = IllustrationStore::open(args.output.join(&args.ncode).join("images"))?;
+        let saved = scraper.download_illustrations(&novel_content, &mut store).await?;
+        println!("🖼️  {} new illustrations saved to: {}", saved, store.dir().display());
+        Some(store)
     } else {
-        args.output.join(&args.ncode)
+        None
     };
-    
-    fs::create_dir_all(&output_dir)?;
+
+    let metadata = fetch_metadata(&args, needs_header, &fetcher, &novel_content, index.as_ref()).await;
+    let options = SaveOptions {
+        single_file: args.single_file,
+        vertical: args.vertical,
+        include_preface: !args.no_preface,
+        include_afterword: !args.no_afterword,
+        ruby: args.ruby.map(RubyMode::from),
+        blank_lines: args.blank_lines.into(),
+        template: args.template.as_deref().map(HtmlTemplate::from_file).transpose()?,
+    };
+    let mut writer = NovelWriter::new(&args.output, metadata, options).nocturne(args.nocturne);
+    if let Some(index) = index {
+        writer = writer.index(index);
+    }
+    if let Some(store) = &store {
+        writer = writer.illustration_store(store);
+    }
+    // EPUBには画像ストアがあればそこから、なければその場で取得して埋め込む
+    if formats.contains(&ExportFormat::Epub) && !args.no_illustrations {
+        let illustrations = match &store {
+            Some(store) => store.load_all(&novel_content.illustration_urls())?,
+            None => scraper.fetch_illustrations(&novel_content.illustration_urls()).await,
+        };
+        writer = writer.illustrations(illustrations);
+    }
 
     // ファイルの保存
-    if args.single_file {
-        // 単一ファイルとして保存
-        let file_path = output_dir.join(format!("{}.html", args.ncode));
-        let mut combined_html = String::new();
-        
-        for episode in &novel_content.episodes {
-            combined_html.push_str(&format!("<!-- Episode {} -->\n", episode.episode_number));
-            combined_html.push_str(&episode.html);
-            combined_html.push_str("\n\n");
-        }
-        
-        fs::write(&file_path, combined_html)?;
-        println!("\n💾 Saved to: {}", file_path.display());
-    } else {
-        // エピソードごとに個別ファイルとして保存
-        for episode in &novel_content.episodes {
-            let filename = match novel_content.novel_type {
-                NovelType::ShortStory => format!("{}.html", args.ncode),
-                NovelType::Serial { .. } => format!("{:04}.html", episode.episode_number),
-            };
-            
-            let file_path = output_dir.join(&filename);
-            fs::write(&file_path, &episode.html)?;
-            
-            if novel_content.episode_count() <= 10 {
-                println!("💾 Saved: {}", file_path.display());
+    for format in formats {
+        let paths = writer.write(&novel_content, format)?;
+        println!();
+        if paths.len() <= 10 {
+            for path in &paths {
+                println!("💾 Saved: {}", path.display());
             }
+        } else {
+            println!("💾 {} {} files saved to: {}", paths.len(), format.name(), args.output.join(&args.ncode).display());
         }
-        
-        if novel_content.episode_count() > 10 {
-            println!("\n💾 All episodes saved to: {}", output_dir.display());
+    }
+
+    Ok(())
+}
+
+/// 作品名・作者名などの書誌情報（APIが使えなければ目次、短編なら本文のタイトルを使う）
+async fn fetch_metadata(
+    args: &Args,
+    needs_header: bool,
+    fetcher: &HtmlFetcher,
+    novel: &NovelContent,
+    index: Option<&NovelIndex>,
+) -> EpubMetadata {
+    // 生のHTMLだけなら作品名を使わないので問い合わせない
+    if needs_header {
+        match fetch_api_metadata(&args.ncode, args.nocturne, fetcher).await {
+            Ok(Some(metadata)) => return metadata,
+            Ok(None) => {}
+            Err(e) => eprintln!("⚠️  Failed to fetch metadata from API: {}", e),
+        }
+    }
+    match index {
+        Some(index) => EpubMetadata::from_index(&args.ncode, index),
+        None => {
+            let title = novel.episodes.first()
+                .map(|e| ParsedEpisode::parse(e).subtitle)
+                .unwrap_or_else(|| args.ncode.clone());
+            EpubMetadata::new(&args.ncode, title)
         }
     }
+}
+
+/// ライブラリに本文とAPIのメタデータを保存（メタデータの取得失敗は警告のみ）
+async fn save_to_library(path: &Path, args: &Args, fetcher: &HtmlFetcher, novel: &NovelContent) -> Result<()> {
+    let mut library = Library::open(path)?;
+    let changed = library.upsert_novel_content(novel)?;
 
-    // メタデータの保存
-    let metadata_path = output_dir.join("metadata.json");
-    let metadata = serde_json::json!({
-        "ncode": novel_content.ncode,
-        "type": match novel_content.novel_type {
-            NovelType::ShortStory => "short".to_string(),
-            NovelType::Serial { total_episodes } => format!("serial_{}", total_episodes),
-        },
-        "episode_count": novel_content.episode_count(),
-        "total_bytes": novel_content.total_size_bytes(),
-        "nocturne": args.nocturne,
-        "fetched_at": chrono::Utc::now().to_rfc3339(),
-    });
-    
-    fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?)?;
-    println!("📄 Metadata saved to: {}", metadata_path.display());
+    let client = HttpClient::from_fetcher(fetcher.clone());
+    let saved = if args.nocturne {
+        let mut request = NocturneRequest::new();
+        request.ncode = Some(args.ncode.clone());
+        match client.execute(&NocturneApiClient, &request).await {
+            Ok(response) => response.novels.first().map(|info| library.upsert_nocturne_novel(info)).transpose(),
+            Err(e) => Err(e.into()),
+        }
+    } else {
+        let mut request = NarouRequest::new();
+        request.ncode = Some(args.ncode.clone());
+        match client.execute(&NarouApiClient, &request).await {
+            Ok(response) => response.novels.first().map(|info| library.upsert_novel(info)).transpose(),
+            Err(e) => Err(e.into()),
+        }
+    };
+    if let Err(e) = saved {
+        eprintln!("⚠️  Failed to store API metadata: {}", e);
+    }
 
+    println!("🗄️  {} new or changed episodes stored in: {}", changed, path.display());
     Ok(())
-}
\ No newline at end of file
+}
+
+/// なろう小説APIから書誌情報を取得
+async fn fetch_api_metadata(ncode: &str, nocturne: bool, fetcher: &HtmlFetcher) -> Result<Option<EpubMetadata>> {
+    let client = HttpClient::from_fetcher(fetcher.clone());
+
+    let metadata = if nocturne {
+        let mut request = NocturneRequest::new();
+        request.ncode = Some(ncode.to_string());
+        let response = client.execute(&NocturneApiClient, &request).await?;
+        response.novels.first().map(|info| EpubMetadata::from_nocturne_info(ncode, info))
+    } else {
+        let mut request = NarouRequest::new();
+        request.ncode = Some(ncode.to_string());
+        let response = client.execute(&NarouApiClient, &request).await?;
+        response.novels.first().map(|info| EpubMetadata::from_narou_info(ncode, info))
+    };
+
+    Ok(metadata)
+}
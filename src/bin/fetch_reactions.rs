@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+use web_novel_scraper::{
+    Episode, HtmlFetcher, NarouNovelScraper, NovelContent, NovelType, ReactionSeries, RequestDelayConfig,
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Build a per-episode reaction time series for a Narou novel", long_about = None)]
+struct Args {
+    /// Novel code (e.g., n7775do)
+    #[arg(short, long)]
+    ncode: String,
+
+    /// Number of episodes (read from the index page when omitted; 0 means a short story)
+    #[arg(short, long)]
+    episodes: Option<u32>,
+
+    /// Read episodes saved by fetch_novel from this directory instead of fetching them
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+
+    /// Number of most-reacted episodes to show
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+
+    /// Output format (json, csv, or text)
+    #[arg(short, long, default_value = "text")]
+    format: String,
+
+    /// Minimum delay between requests in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    min_delay: u64,
+
+    /// Maximum delay between requests in milliseconds
+    #[arg(long, default_value_t = 3000)]
+    max_delay: u64,
+
+    /// Use Nocturne site (R18) instead of regular Narou
+    #[arg(long)]
+    nocturne: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let novel = match &args.input {
+        Some(dir) => load_saved(&args.ncode, dir)?,
+        None => fetch(&args).await?,
+    };
+
+    let series = ReactionSeries::from_novel(&novel);
+    let ids = series.reaction_ids();
+
+    match args.format.as_str() {
+        "json" => {
+            let json_output = serde_json::json!({
+                "ncode": series.ncode,
+                "episode_count": series.episodes.len(),
+                "totals": series.totals(),
+                "episodes": series.episodes,
+                "most_reacted": series.most_reacted(args.top).iter().map(|e| e.episode_number).collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        }
+        "csv" => {
+            // 1行1話の時系列（列はリアクションIDごと）
+            let header: Vec<String> = ids.iter().map(|id| format!("reaction_{}", id)).collect();
+            println!("episode,{},total", header.join(","));
+            for episode in &series.episodes {
+                let counts: Vec<String> = ids.iter().map(|id| episode.count(*id).to_string()).collect();
+                println!("{},{},{}", episode.episode_number, counts.join(","), episode.total());
+            }
+        }
+        _ => {
+            println!("\n📈 Reactions for {} ({} episodes)", series.ncode, series.episodes.len());
+            for (id, total) in series.totals() {
+                println!("  reaction #{}: {}", id, total);
+            }
+
+            println!("\n🏆 Most reacted episodes:");
+            for (rank, episode) in series.most_reacted(args.top).iter().enumerate() {
+                let counts: Vec<String> = ids.iter().map(|id| format!("#{}={}", id, episode.count(*id))).collect();
+                println!(
+                    "  {:>2}. episode {:>4}: {:>5} ({})",
+                    rank + 1,
+                    episode.episode_number,
+                    episode.total(),
+                    counts.join(" ")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// サイトから全話を取得
+async fn fetch(args: &Args) -> Result<NovelContent> {
+    let fetcher = HtmlFetcher::default();
+    fetcher.set_delay_config(RequestDelayConfig::new(args.min_delay, args.max_delay));
+    let scraper = if args.nocturne {
+        NarouNovelScraper::new_nocturne(fetcher)
+    } else {
+        NarouNovelScraper::new(fetcher)
+    };
+
+    let total_episodes = match args.episodes {
+        Some(n) => n,
+        None => scraper.fetch_index(&args.ncode).await?.episode_count() as u32,
+    };
+    let novel_type = match total_episodes {
+        0 => NovelType::ShortStory,
+        n => NovelType::Serial { total_episodes: n },
+    };
+
+    eprintln!("🔍 Fetching {} episodes of {}", total_episodes, args.ncode);
+    scraper.fetch_all_episodes(&args.ncode, novel_type).await
+}
+
+/// fetch_novelが1話ずつ保存したHTML（`0001.html`・短編は`{ncode}.html`）を読み込む
+fn load_saved(ncode: &str, dir: &Path) -> Result<NovelContent> {
+    let short_story = dir.join(format!("{}.html", ncode));
+    if short_story.exists() {
+        let html = fs::read_to_string(&short_story)
+            .with_context(|| format!("Failed to read {}", short_story.display()))?;
+        return Ok(NovelContent {
+            ncode: ncode.to_string(),
+            novel_type: NovelType::ShortStory,
+            episodes: vec![Episode { episode_number: 0, html }],
+        });
+    }
+
+    let mut episodes = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let number = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".html"))
+            .and_then(|stem| stem.parse::<u32>().ok());
+        if let Some(episode_number) = number {
+            let html = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            episodes.push(Episode { episode_number, html });
+        }
+    }
+    episodes.sort_by_key(|e| e.episode_number);
+
+    Ok(NovelContent {
+        ncode: ncode.to_string(),
+        novel_type: NovelType::Serial { total_episodes: episodes.len() as u32 },
+        episodes,
+    })
+}
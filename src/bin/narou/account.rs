@@ -0,0 +1,273 @@
+use crate::info::{add_narou, novel_table};
+use crate::output::{opt, Report, Table};
+use crate::GlobalArgs;
+use anyhow::{anyhow, bail, Result};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use std::path::PathBuf;
+use web_novel_scraper::session::DEFAULT_LOGIN_URL;
+use web_novel_scraper::{
+    AuthorProfileFetcher, BookmarkRecord, Library, LoginCredentials, MypageScraper, NarouBookmarkScraper,
+    NarouRatingScraper, NarouSession, Profile,
+};
+
+#[derive(Args, Debug)]
+pub struct RatingsArgs {
+    /// Narou user ID
+    #[arg(long)]
+    pub user_id: u32,
+
+    /// Also store the ratings in this SQLite library (default: the profile's library)
+    #[arg(long)]
+    pub library: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct BookmarksArgs {
+    /// Narou user ID (with --nocturne: the XID, e.g. x9487b)
+    #[arg(long)]
+    pub user: String,
+
+    /// Only this bookmark category (default: all categories)
+    #[arg(long)]
+    pub category: Option<u32>,
+
+    /// Also store the bookmarks in this SQLite library (Narou user IDs only; default: the profile's library)
+    #[arg(long)]
+    pub library: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct UserArgs {
+    /// Narou user ID
+    pub user_id: u32,
+
+    /// Leave out these parts of the profile (ratings and bookmarks take one request per page)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub skip: Vec<ProfileSection>,
+}
+
+#[derive(Args, Debug)]
+pub struct WorksArgs {
+    /// Narou user ID (with --nocturne: the XID, e.g. x9487b)
+    pub user: String,
+}
+
+#[derive(Args, Debug)]
+pub struct LoginArgs {
+    /// Login ID (email address or user ID)
+    #[arg(long)]
+    pub id: String,
+
+    /// Password (falls back to the NAROU_PASSWORD environment variable)
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// Login form URL
+    #[arg(long, default_value = DEFAULT_LOGIN_URL)]
+    pub login_url: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ProfileSection {
+    /// Novels from the novel API
+    Novels,
+    /// Rated novels
+    Ratings,
+    Bookmarks,
+    /// Recent activity reports
+    Activity,
+}
+
+/// ログインしてセッションのクッキーを`--cookie-file`（またはプロファイルの`cookie_file`）に保存
+pub async fn login(global: &GlobalArgs, args: LoginArgs) -> Result<Report> {
+    let Some(cookie_file) = global.settings.cookie_file.clone() else {
+        bail!("No cookie file: pass --cookie-file or set cookie_file in the profile");
+    };
+    let password = match args.password {
+        Some(password) => password,
+        None => std::env::var("NAROU_PASSWORD").map_err(|_| anyhow!("--password or NAROU_PASSWORD is required"))?,
+    };
+
+    // まだクッキーファイルがなくてもよいので、読み込みはセッションに任せる
+    let fetcher = Profile { cookie_file: None, ..global.settings.clone() }.fetcher()?;
+    let session = NarouSession::new(fetcher)
+        .with_login_url(args.login_url)
+        .with_credentials(LoginCredentials { narouid: args.id, password })
+        .with_cookie_file(&cookie_file)?;
+    session.login().await?;
+
+    let cookies = session.fetcher().cookies().len();
+    let mut report = Report::new("login", Table::new(&["cookie_file", "cookies"]));
+    report.table.row(vec![cookie_file.display().to_string(), cookies.to_string()]);
+    report.item(json!({ "cookie_file": cookie_file, "cookies": cookies }));
+    Ok(report)
+}
+
+pub async fn ratings(global: &GlobalArgs, args: RatingsArgs) -> Result<Report> {
+    let scraper = NarouRatingScraper::new(global.fetcher()?);
+    let ratings = scraper.fetch_all_ratings(args.user_id).await?;
+
+    if let Some(path) = global.library(args.library.clone()) {
+        Library::open(&path)?.upsert_ratings(args.user_id, &ratings)?;
+        eprintln!("🗄️  {} ratings stored in: {}", ratings.len(), path.display());
+    }
+
+    let mut report = Report::new("ratings", Table::new(&["ncode", "rating", "first_rated", "last_rated"]));
+    for rating in &ratings {
+        report.table.row(vec![
+            rating.ncode.clone(),
+            rating.rating_point.to_string(),
+            rating.first_rating_date.clone(),
+            opt(rating.last_rating_date.as_deref()),
+        ]);
+        report.item(json!({
+            "ncode": rating.ncode,
+            "rating_point": rating.rating_point,
+            "first_rating_date": rating.first_rating_date,
+            "last_rating_date": rating.last_rating_date,
+        }));
+    }
+    Ok(report.extra("user_id", json!(args.user_id)))
+}
+
+pub async fn bookmarks(global: &GlobalArgs, args: BookmarksArgs) -> Result<Report> {
+    let fetcher = global.fetcher()?;
+    let scraper = if global.nocturne() {
+        NarouBookmarkScraper::new_nocturne(fetcher)
+    } else {
+        NarouBookmarkScraper::new(fetcher)
+    };
+
+    let bookmarks = match args.category {
+        Some(category) => scraper
+            .fetch_category(&args.user, category)
+            .await?
+            .into_iter()
+            .map(|entry| (category, entry))
+            .collect(),
+        None => scraper.fetch_all_bookmarks(&args.user).await?,
+    };
+
+    if let Some(path) = global.library(args.library.clone()) {
+        let Ok(user_id) = args.user.parse::<u32>() else {
+            bail!("--library needs a numeric Narou user ID, got {}", args.user);
+        };
+        let records: Vec<BookmarkRecord> = bookmarks
+            .iter()
+            .map(|(category, entry)| BookmarkRecord {
+                user_id,
+                category: *category,
+                ncode: entry.ncode.clone(),
+                title: Some(entry.title.clone()),
+            })
+            .collect();
+        Library::open(&path)?.upsert_bookmarks(&records)?;
+        eprintln!("🗄️  {} bookmarks stored in: {}", records.len(), path.display());
+    }
+
+    let mut report = Report::new("bookmarks", Table::new(&["category", "ncode", "title", "episodes", "last_updated"]));
+    for (category, entry) in &bookmarks {
+        report.table.row(vec![
+            category.to_string(),
+            entry.ncode.clone(),
+            entry.title.clone(),
+            entry.episode_count.to_string(),
+            opt(entry.last_updated.as_deref()),
+        ]);
+        let mut item = serde_json::to_value(entry)?;
+        item["category"] = json!(category);
+        report.item(item);
+    }
+    Ok(report.extra("user", json!(args.user)))
+}
+
+/// 作者のプロフィール（表は作品一覧、JSONではユーザー情報・評価・ブックマーク・活動報告も出す）
+pub async fn user(global: &GlobalArgs, args: UserArgs) -> Result<Report> {
+    if global.nocturne() {
+        bail!("The user API only covers Narou user IDs");
+    }
+    let profile = AuthorProfileFetcher::new(global.fetcher()?)
+        .novels(!args.skip.contains(&ProfileSection::Novels))
+        .ratings(!args.skip.contains(&ProfileSection::Ratings))
+        .bookmarks(!args.skip.contains(&ProfileSection::Bookmarks))
+        .activity_reports(!args.skip.contains(&ProfileSection::Activity))
+        .fetch(args.user_id)
+        .await?;
+
+    eprintln!(
+        "👤 {} ({}): {} novels, {} reviews, {} points",
+        opt(profile.user.name.as_deref()),
+        profile.userid,
+        opt(profile.user.novel_cnt),
+        opt(profile.user.review_cnt),
+        opt(profile.user.sum_global_point),
+    );
+    eprintln!(
+        "   {} ratings, {} bookmarks, {} recent activity reports",
+        profile.ratings.len(),
+        profile.bookmarks.len(),
+        profile.activity_reports.len(),
+    );
+    for warning in &profile.warnings {
+        eprintln!("⚠️  Skipped {}", warning);
+    }
+
+    let mut report = Report::new("user", novel_table());
+    for info in &profile.novels {
+        add_narou(&mut report, info)?;
+    }
+    let bookmarks: Vec<_> = profile
+        .bookmarks
+        .iter()
+        .map(|(category, entry)| {
+            let mut item = serde_json::to_value(entry)?;
+            item["category"] = json!(category);
+            Ok(item)
+        })
+        .collect::<Result<_>>()?;
+    Ok(report
+        .extra("user", serde_json::to_value(&profile.user)?)
+        .extra("ratings", serde_json::to_value(&profile.ratings)?)
+        .extra("bookmarks", json!(bookmarks))
+        .extra("activity_reports", serde_json::to_value(&profile.activity_reports)?)
+        .extra("warnings", json!(profile.warnings)))
+}
+
+/// マイページの作品タブ（表示順・シリーズ・作者のユーザーID/XID）
+pub async fn works(global: &GlobalArgs, args: WorksArgs) -> Result<Report> {
+    let fetcher = global.fetcher()?;
+    let scraper = if global.nocturne() {
+        MypageScraper::new_nocturne(fetcher)
+    } else {
+        MypageScraper::new(fetcher)
+    };
+    let works = scraper.fetch_works(&args.user).await?;
+    eprintln!(
+        "📚 {}: {} works in {} series",
+        opt(works.author.name.as_deref()),
+        works.works.len(),
+        works.series.len()
+    );
+
+    let mut report = Report::new(
+        "works",
+        Table::new(&["order", "ncode", "title", "series", "episodes", "last_updated"]),
+    );
+    for (i, work) in works.works.iter().enumerate() {
+        report.table.row(vec![
+            (i + 1).to_string(),
+            work.ncode.clone(),
+            work.title.clone(),
+            opt(work.series_title.as_deref()),
+            work.episode_count.to_string(),
+            opt(work.last_updated.as_deref()),
+        ]);
+        let mut item = serde_json::to_value(work)?;
+        item["order"] = json!(i + 1);
+        report.item(item);
+    }
+    Ok(report
+        .extra("author", serde_json::to_value(&works.author)?)
+        .extra("series", serde_json::to_value(&works.series)?))
+}
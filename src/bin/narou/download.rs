@@ -0,0 +1,357 @@
+use crate::info::ApiNovel;
+use crate::output::{Report, Table};
+use crate::GlobalArgs;
+use anyhow::{anyhow, Result};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use std::path::PathBuf;
+use web_novel_scraper::{
+    BlankLines, EpubMetadata, ExportFormat, HtmlTemplate, IllustrationStore, Library, NarouNovelScraper, NovelContent,
+    NovelIndex, NovelType, NovelWriter, RevisionStore, RubyMode, SaveOptions,
+};
+
+#[derive(Args, Debug)]
+pub struct DownloadArgs {
+    /// Novel code (ncode); type and episode count are looked up with the API
+    pub ncode: String,
+
+    #[command(flatten)]
+    pub save: SaveArgs,
+
+    /// Also store the episodes and API metadata in this SQLite library (default: the profile's library)
+    #[arg(long)]
+    pub library: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// Novel code (ncode)
+    pub ncode: String,
+
+    #[command(flatten)]
+    pub save: SaveArgs,
+
+    /// SQLite library that holds the novel (default: the profile's library)
+    #[arg(long)]
+    pub library: Option<PathBuf>,
+}
+
+/// downloadとexportで共通の保存オプション
+#[derive(Args, Debug, Clone)]
+pub struct SaveArgs {
+    /// Format to save in (repeatable; default: the profile's export_formats, else html)
+    #[arg(long = "as", value_enum)]
+    pub save_as: Vec<SaveFormat>,
+
+    /// Output directory (default: the profile's archive, else ./output)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Save as single file instead of separate files
+    #[arg(long)]
+    pub single_file: bool,
+
+    /// Vertical writing (EPUB only)
+    #[arg(long)]
+    pub vertical: bool,
+
+    /// Omit prefaces (all formats except html)
+    #[arg(long)]
+    pub no_preface: bool,
+
+    /// Omit afterwords (all formats except html)
+    #[arg(long)]
+    pub no_afterword: bool,
+
+    /// HTML template file for clean-html ({{title}}, {{novel_title}}, {{author}}, {{nav}}, {{content}})
+    #[arg(long)]
+    pub template: Option<PathBuf>,
+
+    /// How to render ruby (default: parenthesize for text, keep for other formats)
+    #[arg(long, value_enum)]
+    pub ruby: Option<RubyModeArg>,
+
+    /// How to treat blank lines (text and aozora only)
+    #[arg(long, value_enum, default_value = "keep")]
+    pub blank_lines: BlankLinesArg,
+
+    /// Do not embed illustrations (EPUB only)
+    #[arg(long)]
+    pub no_illustrations: bool,
+
+    /// Keep illustrations in {output}/{ncode}/images and relink them for offline reading (export only uses images already saved)
+    #[arg(long, conflicts_with = "no_illustrations")]
+    pub illustrations: bool,
+
+    /// Keep every changed version of each episode in {output}/{ncode}/revisions
+    #[arg(long)]
+    pub keep_revisions: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SaveFormat {
+    /// Raw episode HTML (the layout read by the reader and opds_server)
+    Html,
+    /// EPUB 3 e-book
+    Epub,
+    /// UTF-8 plain text
+    Text,
+    /// Aozora Bunko notation text
+    Aozora,
+    /// Markdown with ruby kept as inline HTML
+    Markdown,
+    /// Novel text only, without ads and site widgets
+    CleanHtml,
+}
+
+impl From<SaveFormat> for ExportFormat {
+    fn from(format: SaveFormat) -> Self {
+        match format {
+            SaveFormat::Html => ExportFormat::Html,
+            SaveFormat::Epub => ExportFormat::Epub,
+            SaveFormat::Text => ExportFormat::Text,
+            SaveFormat::Aozora => ExportFormat::Aozora,
+            SaveFormat::Markdown => ExportFormat::Markdown,
+            SaveFormat::CleanHtml => ExportFormat::CleanHtml,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RubyModeArg {
+    /// Keep ruby markup (<ruby> in HTML, ｜漢字《かな》 in text)
+    Keep,
+    /// Base text only
+    Strip,
+    /// 漢字（かな）
+    Parenthesize,
+    /// Replace the base text with its hiragana reading
+    HiraganaOnly,
+}
+
+impl From<RubyModeArg> for RubyMode {
+    fn from(arg: RubyModeArg) -> Self {
+        match arg {
+            RubyModeArg::Keep => RubyMode::Keep,
+            RubyModeArg::Strip => RubyMode::Strip,
+            RubyModeArg::Parenthesize => RubyMode::Parenthesize,
+            RubyModeArg::HiraganaOnly => RubyMode::HiraganaOnly,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BlankLinesArg {
+    /// Keep every blank line
+    Keep,
+    /// Collapse consecutive blank lines into one
+    Collapse,
+    /// Remove all blank lines
+    Remove,
+}
+
+impl From<BlankLinesArg> for BlankLines {
+    fn from(arg: BlankLinesArg) -> Self {
+        match arg {
+            BlankLinesArg::Keep => BlankLines::Keep,
+            BlankLinesArg::Collapse => BlankLines::Collapse,
+            BlankLinesArg::Remove => BlankLines::Remove,
+        }
+    }
+}
+
+impl SaveArgs {
+    /// 保存する形式（`--as`、なければプロファイルの`export_formats`）
+    fn formats(&self, global: &GlobalArgs) -> Result<Vec<ExportFormat>> {
+        if !self.save_as.is_empty() {
+            return Ok(self.save_as.iter().map(|&f| f.into()).collect());
+        }
+        match &global.settings.export_formats {
+            Some(names) if !names.is_empty() => names
+                .iter()
+                .map(|name| ExportFormat::from_name(name).ok_or_else(|| anyhow!("Unknown export format in profile: {}", name)))
+                .collect(),
+            _ => Ok(vec![ExportFormat::Html]),
+        }
+    }
+
+    fn options(&self) -> Result<SaveOptions> {
+        Ok(SaveOptions {
+            single_file: self.single_file,
+            vertical: self.vertical,
+            include_preface: !self.no_preface,
+            include_afterword: !self.no_afterword,
+            ruby: self.ruby.map(RubyMode::from),
+            blank_lines: self.blank_lines.into(),
+            template: self.template.as_deref().map(HtmlTemplate::from_file).transpose()?,
+        })
+    }
+}
+
+pub async fn run(global: &GlobalArgs, args: DownloadArgs) -> Result<Report> {
+    let ncode = args.ncode.to_lowercase();
+    let fetcher = global.fetcher()?;
+    let client = global.api_client(&fetcher);
+    let scraper = if global.nocturne() {
+        NarouNovelScraper::new_nocturne(fetcher.clone())
+    } else {
+        NarouNovelScraper::new(fetcher.clone())
+    };
+
+    let formats = args.save.formats(global)?;
+    let output = global.archive(args.save.output.clone());
+    let api = ApiNovel::lookup(&client, &ncode, global.nocturne()).await?;
+    eprintln!("🔍 Fetching novel: {} ({})", ncode, api.metadata.title);
+    let novel = scraper.fetch_all_episodes(&ncode, api.novel_type.clone()).await?;
+    eprintln!("✅ Successfully fetched {} episodes", novel.episode_count());
+
+    // 章立てとサブタイトル・改稿日時は目次ページから取得（短編には目次がない）
+    let library_path = global.library(args.library.clone());
+    let needs_index =
+        library_path.is_some() || args.save.keep_revisions || formats.iter().any(|f| *f != ExportFormat::Html);
+    let index = match novel.novel_type {
+        NovelType::Serial { .. } if needs_index => Some(scraper.fetch_index(&ncode).await?),
+        _ => None,
+    };
+
+    if let Some(path) = library_path {
+        let mut library = Library::open(&path)?;
+        let changed = library.upsert_novel_content(&novel)?;
+        api.store(&library)?;
+        // exportで章見出しを付けられるよう目次も残す
+        if let Some(index) = &index {
+            library.upsert_index(&ncode, index)?;
+        }
+        eprintln!("🗄️  {} new or changed episodes stored in: {}", changed, path.display());
+    }
+
+    if args.save.keep_revisions {
+        let store = RevisionStore::open(output.join(&ncode).join("revisions"));
+        let saved = store.save_novel(&novel, index.as_ref())?;
+        eprintln!("📝 {} new revisions saved to: {}", saved, store.dir().display());
+    }
+
+    // 挿絵の取得（保存済みの画像は取り直さない）
+    let store = if args.save.illustrations {
+        let mut store = IllustrationStore::open(output.join(&ncode).join("images"))?;
+        let saved = scraper.download_illustrations(&novel, &mut store).await?;
+        eprintln!("🖼️  {} new illustrations saved to: {}", saved, store.dir().display());
+        Some(store)
+    } else {
+        None
+    };
+
+    let mut writer = NovelWriter::new(&output, api.metadata.clone(), args.save.options()?).nocturne(global.nocturne());
+    if let Some(index) = index {
+        writer = writer.index(index);
+    }
+    if let Some(store) = &store {
+        writer = writer.illustration_store(store);
+    }
+    // EPUBには画像ストアがあればそこから、なければその場で取得して埋め込む
+    if formats.contains(&ExportFormat::Epub) && !args.save.no_illustrations {
+        let illustrations = match &store {
+            Some(store) => store.load_all(&novel.illustration_urls())?,
+            None => scraper.fetch_illustrations(&novel.illustration_urls()).await,
+        };
+        writer = writer.illustrations(illustrations);
+    }
+
+    let mut report = saved_report("download");
+    for format in formats {
+        let paths = writer.write(&novel, format)?;
+        add_saved(&mut report, &novel, &api.metadata, format, &paths);
+    }
+    Ok(report)
+}
+
+pub async fn export(global: &GlobalArgs, args: ExportArgs) -> Result<Report> {
+    let ncode = args.ncode.to_lowercase();
+    let path = global
+        .library(args.library.clone())
+        .ok_or_else(|| anyhow!("No library: pass --library or set library in the profile"))?;
+    let library = Library::open(&path)?;
+    let novel = library
+        .novel_content(&ncode)?
+        .ok_or_else(|| anyhow!("{} has no episodes in {}", ncode, path.display()))?;
+    let record = library.novel(&ncode)?;
+    let metadata = match &record {
+        Some(record) => EpubMetadata::from_novel_record(record),
+        None => EpubMetadata::new(&ncode, ncode.clone()),
+    };
+    let nocturne = record.map(|r| r.is_nocturne).unwrap_or(global.nocturne());
+
+    // 目次はライブラリに保存したもの、なければ取得して保存する（取得できなければ章見出しなし）
+    let index = match (&novel.novel_type, library.index(&ncode)?) {
+        (NovelType::ShortStory, _) => None,
+        (_, Some(index)) => Some(index),
+        (_, None) => match fetch_index(global, &ncode, nocturne).await {
+            Ok(index) => {
+                library.upsert_index(&ncode, &index)?;
+                Some(index)
+            }
+            Err(e) => {
+                eprintln!("⚠️  Failed to fetch the index of {}: {}", ncode, e);
+                None
+            }
+        },
+    };
+
+    let output = global.archive(args.save.output.clone());
+    let formats = args.save.formats(global)?;
+
+    if args.save.keep_revisions {
+        let store = RevisionStore::open(output.join(&ncode).join("revisions"));
+        let saved = store.save_novel(&novel, index.as_ref())?;
+        eprintln!("📝 {} new revisions saved to: {}", saved, store.dir().display());
+    }
+
+    let mut writer = NovelWriter::new(&output, metadata.clone(), args.save.options()?).nocturne(nocturne);
+    if let Some(index) = index {
+        writer = writer.index(index);
+    }
+    // ライブラリからの書き出しでは取得せず、保存済みの挿絵だけを使う
+    if args.save.illustrations {
+        let store = IllustrationStore::open(output.join(&ncode).join("images"))?;
+        if !args.save.no_illustrations {
+            writer = writer.illustrations(store.load_all(&novel.illustration_urls())?);
+        }
+        writer = writer.illustration_store(&store);
+    }
+
+    let mut report = saved_report("export");
+    for format in formats {
+        let paths = writer.write(&novel, format)?;
+        add_saved(&mut report, &novel, &metadata, format, &paths);
+    }
+    Ok(report)
+}
+
+async fn fetch_index(global: &GlobalArgs, ncode: &str, nocturne: bool) -> Result<NovelIndex> {
+    let fetcher = global.fetcher()?;
+    let scraper = if nocturne {
+        NarouNovelScraper::new_nocturne(fetcher)
+    } else {
+        NarouNovelScraper::new(fetcher)
+    };
+    scraper.fetch_index(ncode).await
+}
+
+fn saved_report(command: &'static str) -> Report {
+    Report::new(command, Table::new(&["format", "path"]))
+}
+
+/// 保存した形式ごとに1項目
+fn add_saved(report: &mut Report, novel: &NovelContent, metadata: &EpubMetadata, format: ExportFormat, paths: &[PathBuf]) {
+    for path in paths {
+        report.table.row(vec![format.name().to_string(), path.display().to_string()]);
+    }
+    report.item(json!({
+        "ncode": novel.ncode,
+        "title": metadata.title,
+        "author": metadata.author,
+        "format": format.name(),
+        "episode_count": novel.episode_count(),
+        "paths": paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+    }));
+}
@@ -0,0 +1,111 @@
+use crate::output::{Report, Table};
+use crate::GlobalArgs;
+use anyhow::{anyhow, bail, Result};
+use clap::Args;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+use web_novel_scraper::{FetchOptions, FetcherConfig};
+
+#[derive(Args, Debug)]
+pub struct GetArgs {
+    /// URL to fetch
+    pub url: String,
+
+    /// Cookies in key=value format (separated by ';')
+    #[arg(short = 'c', long = "cookie", value_delimiter = ';')]
+    pub cookies: Vec<String>,
+
+    /// Output file (default: stdout)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Show only response info (size and lines)
+    #[arg(short, long)]
+    pub info_only: bool,
+
+    /// Request timeout in seconds
+    #[arg(long, default_value_t = 10)]
+    pub timeout: u64,
+
+    /// Connect timeout in seconds
+    #[arg(long)]
+    pub connect_timeout: Option<u64>,
+
+    /// Additional trusted root certificate (PEM)
+    #[arg(long = "ca-cert")]
+    pub ca_certs: Vec<PathBuf>,
+
+    /// Extra request header in "Name: value" format (repeatable)
+    #[arg(short = 'H', long = "header")]
+    pub headers: Vec<String>,
+}
+
+/// 1ページ取得して表示・保存（`--info-only`の場合だけ表にする）
+pub async fn run(global: &GlobalArgs, args: GetArgs) -> Result<Option<Report>> {
+    let mut config = FetcherConfig::new().timeout(Duration::from_secs(args.timeout));
+    if let Some(secs) = args.connect_timeout {
+        config = config.connect_timeout(Duration::from_secs(secs));
+    }
+    for path in &args.ca_certs {
+        config = config.ca_certificate(path);
+    }
+    for (name, value) in parse_headers(&args.headers)? {
+        config = config.default_header(name, value);
+    }
+    let fetcher = global.settings.fetcher_with(config)?;
+
+    let cookies = parse_cookies(&args.cookies)?;
+    let options = FetchOptions {
+        cookies: (!cookies.is_empty()).then(|| cookies.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()),
+        custom_user_agent: None,
+    };
+    match fetcher.get_current_user_agent() {
+        Some(ua) => eprintln!("Using User-Agent: {}", ua),
+        None => eprintln!("Using random User-Agent for each request"),
+    }
+    let html = fetcher.fetch_with_options(&args.url, options).await?;
+
+    // ログイン中のセッションが更新されていれば書き戻す
+    if let Some(path) = &global.settings.cookie_file {
+        fetcher.save_cookies(path)?;
+        eprintln!("Saved {} cookies to {}", fetcher.cookies().len(), path.display());
+    }
+
+    if args.info_only {
+        let mut report = Report::new("get", Table::new(&["url", "bytes", "lines"]));
+        report.table.row(vec![args.url.clone(), html.len().to_string(), html.lines().count().to_string()]);
+        report.item(json!({ "url": args.url, "bytes": html.len(), "lines": html.lines().count() }));
+        return Ok(Some(report));
+    }
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, &html)?;
+            eprintln!("Saved {} bytes to {}", html.len(), path.display());
+        }
+        None => print!("{}", html),
+    }
+    Ok(None)
+}
+
+fn parse_headers(headers: &[String]) -> Result<Vec<(String, String)>> {
+    headers
+        .iter()
+        .map(|header| {
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid header format: {}. Expected Name: value", header))?;
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn parse_cookies(cookies: &[String]) -> Result<Vec<(String, String)>> {
+    cookies
+        .iter()
+        .map(|cookie| match cookie.split_once('=') {
+            Some((key, value)) => Ok((key.trim().to_string(), value.to_string())),
+            None => bail!("Invalid cookie format: {}. Expected key=value", cookie),
+        })
+        .collect()
+}
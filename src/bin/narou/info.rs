@@ -0,0 +1,143 @@
+use crate::output::{opt, Report, Table};
+use crate::GlobalArgs;
+use anyhow::{bail, Result};
+use clap::Args;
+use web_novel_scraper::api::endpoints::narou::NarouNovelInfo;
+use web_novel_scraper::api::endpoints::nocturne::NocturneNovelInfo;
+use web_novel_scraper::api::HttpClient;
+use web_novel_scraper::opds::{genre_name, nocgenre_name};
+use web_novel_scraper::tracker::{fetch_narou_infos, fetch_nocturne_infos};
+use web_novel_scraper::{EpubMetadata, Library, NovelType};
+
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    /// Novel codes
+    #[arg(required = true)]
+    pub ncodes: Vec<String>,
+}
+
+pub async fn run(global: &GlobalArgs, args: InfoArgs) -> Result<Report> {
+    let fetcher = global.fetcher()?;
+    let client = global.api_client(&fetcher);
+    let ncodes: Vec<String> = args.ncodes.iter().map(|n| n.to_lowercase()).collect();
+
+    let mut report = Report::new("info", novel_table());
+    if global.nocturne() {
+        for info in fetch_nocturne_infos(&client, &ncodes).await? {
+            add_nocturne(&mut report, &info)?;
+        }
+    } else {
+        for info in fetch_narou_infos(&client, &ncodes).await? {
+            add_narou(&mut report, &info)?;
+        }
+    }
+    Ok(report)
+}
+
+/// 作品一覧の表（search・info・rankingで共通）
+pub fn novel_table() -> Table {
+    Table::new(&["ncode", "title", "writer", "genre", "type", "episodes", "length", "points", "last_up"])
+}
+
+pub fn add_narou(report: &mut Report, info: &NarouNovelInfo) -> Result<()> {
+    report.table.row(vec![
+        opt(info.ncode.as_deref()).to_lowercase(),
+        opt(info.title.as_deref()),
+        opt(info.writer.as_deref()),
+        opt(info.genre.and_then(genre_name)),
+        type_label(info.novel_type, info.end).to_string(),
+        opt(info.general_all_no),
+        opt(info.length),
+        opt(info.global_point),
+        opt(info.general_lastup.as_deref()),
+    ]);
+    report.item(serde_json::to_value(info)?);
+    Ok(())
+}
+
+pub fn add_nocturne(report: &mut Report, info: &NocturneNovelInfo) -> Result<()> {
+    report.table.row(vec![
+        opt(info.ncode.as_deref()).to_lowercase(),
+        opt(info.title.as_deref()),
+        opt(info.writer.as_deref()),
+        opt(info.nocgenre.and_then(nocgenre_name)),
+        type_label(info.novel_type, info.end).to_string(),
+        opt(info.general_all_no),
+        opt(info.length),
+        opt(info.global_point),
+        opt(info.general_lastup.as_deref()),
+    ]);
+    report.item(serde_json::to_value(info)?);
+    Ok(())
+}
+
+/// APIの`novel_type`（1: 連載、2: 短編）と`end`（0: 完結）の表示
+fn type_label(novel_type: Option<u8>, end: Option<u8>) -> &'static str {
+    match (novel_type, end) {
+        (Some(2), _) => "short",
+        (_, Some(0)) => "complete",
+        _ => "serial",
+    }
+}
+
+/// ダウンロード・同期で使う1作品分のAPI情報
+pub struct ApiNovel {
+    pub novel_type: NovelType,
+    pub metadata: EpubMetadata,
+    narou: Option<NarouNovelInfo>,
+    nocturne: Option<NocturneNovelInfo>,
+}
+
+impl ApiNovel {
+    pub async fn lookup(client: &HttpClient, ncode: &str, nocturne: bool) -> Result<Self> {
+        let ncodes = [ncode.to_lowercase()];
+        let novel = if nocturne {
+            fetch_nocturne_infos(client, &ncodes)
+                .await?
+                .into_iter()
+                .next()
+                .map(|info| Self {
+                    novel_type: novel_type(info.novel_type, info.general_all_no),
+                    metadata: EpubMetadata::from_nocturne_info(ncode, &info),
+                    narou: None,
+                    nocturne: Some(info),
+                })
+        } else {
+            fetch_narou_infos(client, &ncodes)
+                .await?
+                .into_iter()
+                .next()
+                .map(|info| Self {
+                    novel_type: novel_type(info.novel_type, info.general_all_no),
+                    metadata: EpubMetadata::from_narou_info(ncode, &info),
+                    narou: Some(info),
+                    nocturne: None,
+                })
+        };
+        match novel {
+            Some(novel) => Ok(novel),
+            None if nocturne => bail!("{} was not found in the Nocturne API", ncode),
+            None => bail!("{} was not found in the novel API (use --nocturne for R18 novels)", ncode),
+        }
+    }
+
+    /// ライブラリにAPIの情報を保存
+    pub fn store(&self, library: &Library) -> Result<()> {
+        if let Some(info) = &self.narou {
+            library.upsert_novel(info)?;
+        }
+        if let Some(info) = &self.nocturne {
+            library.upsert_nocturne_novel(info)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn novel_type(novel_type: Option<u8>, general_all_no: Option<u32>) -> NovelType {
+    match novel_type {
+        Some(2) => NovelType::ShortStory,
+        _ => NovelType::Serial {
+            total_episodes: general_all_no.unwrap_or(0),
+        },
+    }
+}
@@ -0,0 +1,262 @@
+mod account;
+mod download;
+mod get;
+mod info;
+mod output;
+mod ranking;
+mod search;
+mod sync;
+mod watch;
+
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use output::{Format, Report};
+use std::path::PathBuf;
+use web_novel_scraper::api::HttpClient;
+use web_novel_scraper::{Config, HtmlFetcher, Profile, UserAgentSetting};
+
+#[derive(Parser, Debug)]
+#[command(name = "narou", author, version, about = "Search, download and follow novels on Narou and Nocturne", long_about = None)]
+struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// すべてのサブコマンドで共通のオプション（指定したものが設定ファイルのプロファイルより優先される）
+#[derive(Args, Debug, Clone)]
+pub struct GlobalArgs {
+    /// Config file (default: $XDG_CONFIG_HOME/web-novel-scraper/config.toml)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Profile from the config file (default: its default_profile)
+    #[arg(short = 'P', long, global = true)]
+    pub profile: Option<String>,
+
+    /// Minimum delay between requests in milliseconds [default: 1000]
+    #[arg(long, global = true)]
+    pub min_delay: Option<u64>,
+
+    /// Maximum delay between requests in milliseconds [default: 3000]
+    #[arg(long, global = true)]
+    pub max_delay: Option<u64>,
+
+    /// User agent mode [default: random]
+    #[arg(long, global = true, value_enum)]
+    pub ua_mode: Option<UaMode>,
+
+    /// User agent string for --ua-mode custom
+    #[arg(long, global = true)]
+    pub user_agent: Option<String>,
+
+    /// Proxy URL (repeatable; requests rotate through them)
+    #[arg(long, global = true)]
+    pub proxy: Vec<String>,
+
+    /// Load cookies from this file (Netscape cookies.txt or JSON)
+    #[arg(long, global = true)]
+    pub cookie_file: Option<PathBuf>,
+
+    /// Cache responses on disk in this directory
+    #[arg(long, global = true)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Lifetime of cached responses in seconds [default: 3600]
+    #[arg(long, global = true)]
+    pub cache_ttl: Option<u64>,
+
+    /// Output format (progress messages always go to stderr)
+    #[arg(short, long, global = true, value_enum, default_value = "text")]
+    pub format: Format,
+
+    /// Use Nocturne site and API (R18) instead of regular Narou
+    #[arg(long, global = true)]
+    pub nocturne: bool,
+
+    /// Use regular Narou even if the profile sets nocturne
+    #[arg(long, global = true, conflicts_with = "nocturne")]
+    pub no_nocturne: bool,
+
+    /// 設定ファイルのプロファイルにコマンドラインの指定を重ねたもの
+    #[arg(skip)]
+    pub settings: Profile,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum UaMode {
+    /// Use a different random user agent for each request
+    Random,
+    /// Pick one random user agent and keep it
+    Fixed,
+    /// Use the user agent given with --user-agent
+    Custom,
+}
+
+impl From<UaMode> for UserAgentSetting {
+    fn from(mode: UaMode) -> Self {
+        match mode {
+            UaMode::Random => UserAgentSetting::Random,
+            UaMode::Fixed => UserAgentSetting::Fixed,
+            UaMode::Custom => UserAgentSetting::Custom,
+        }
+    }
+}
+
+impl GlobalArgs {
+    /// 設定ファイルを読み、プロファイルにコマンドラインの指定を重ねる
+    pub fn resolve(&mut self) -> Result<()> {
+        let config = Config::load_or_default(self.config.as_deref())?;
+        let overrides = Profile {
+            min_delay: self.min_delay,
+            max_delay: self.max_delay,
+            ua_mode: self.ua_mode.map(UserAgentSetting::from),
+            user_agent: self.user_agent.clone(),
+            proxies: (!self.proxy.is_empty()).then(|| self.proxy.clone()),
+            cookie_file: self.cookie_file.clone(),
+            cache_dir: self.cache_dir.clone(),
+            cache_ttl: self.cache_ttl,
+            nocturne: if self.nocturne {
+                Some(true)
+            } else if self.no_nocturne {
+                Some(false)
+            } else {
+                None
+            },
+            ..Profile::default()
+        };
+        self.settings = config.profile(self.profile.as_deref())?.merge(&overrides);
+        Ok(())
+    }
+
+    /// 共通オプションに従ったフェッチャー
+    pub fn fetcher(&self) -> Result<HtmlFetcher> {
+        self.settings.fetcher()
+    }
+
+    /// API用のクライアント（フェッチャーと遅延・キャッシュを共有する）
+    pub fn api_client(&self, fetcher: &HtmlFetcher) -> HttpClient {
+        HttpClient::from_fetcher(fetcher.clone())
+    }
+
+    pub fn nocturne(&self) -> bool {
+        self.settings.nocturne()
+    }
+
+    /// `--library`、なければプロファイルの`library`
+    pub fn library(&self, explicit: Option<PathBuf>) -> Option<PathBuf> {
+        explicit.or_else(|| self.settings.library.clone())
+    }
+
+    /// `--output`、なければプロファイルの`archive`
+    pub fn archive(&self, explicit: Option<PathBuf>) -> PathBuf {
+        explicit
+            .or_else(|| self.settings.archive.clone())
+            .unwrap_or_else(|| PathBuf::from("./output"))
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Search novels with the novel API
+    Search(Box<search::SearchArgs>),
+    /// Show API information for novels
+    Info(info::InfoArgs),
+    /// Download a novel and save it in the chosen format
+    Download(download::DownloadArgs),
+    /// Fetch new episodes for novels stored in a library
+    Sync(sync::SyncArgs),
+    /// Fetch a user's ratings
+    Ratings(account::RatingsArgs),
+    /// Fetch a user's bookmarks
+    Bookmarks(account::BookmarksArgs),
+    /// Show a ranking with title, author, genre and length
+    Ranking(ranking::RankingArgs),
+    /// Show every ranking a novel has entered
+    HallOfFame(ranking::HallOfFameArgs),
+    /// Show an author's profile, novels, ratings, bookmarks and activity reports
+    User(account::UserArgs),
+    /// List an author's works from their mypage, in display order with series
+    Works(account::WorksArgs),
+    /// Export a novel stored in a library
+    Export(download::ExportArgs),
+    /// Poll followed novels and print new episodes as JSON lines
+    Watch(watch::WatchArgs),
+    /// Log in to syosetu and save the session cookies to --cookie-file
+    Login(account::LoginArgs),
+    /// Fetch one page with the profile's user agent, proxies and cookies
+    Get(get::GetArgs),
+}
+
+impl Commands {
+    fn name(&self) -> &'static str {
+        match self {
+            Commands::Search(_) => "search",
+            Commands::Info(_) => "info",
+            Commands::Download(_) => "download",
+            Commands::Sync(_) => "sync",
+            Commands::Ratings(_) => "ratings",
+            Commands::Bookmarks(_) => "bookmarks",
+            Commands::Ranking(_) => "ranking",
+            Commands::HallOfFame(_) => "hall-of-fame",
+            Commands::User(_) => "user",
+            Commands::Works(_) => "works",
+            Commands::Export(_) => "export",
+            Commands::Watch(_) => "watch",
+            Commands::Login(_) => "login",
+            Commands::Get(_) => "get",
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut cli = Cli::parse();
+    let name = cli.command.name();
+
+    let result = match cli.global.resolve() {
+        Ok(()) => run(&cli.global, cli.command).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(Some(report)) => {
+            report.print(cli.global.format)?;
+            Ok(())
+        }
+        Ok(None) => Ok(()),
+        // JSONで受け取る側のためにエラーもJSONで出す
+        Err(e) if cli.global.format == Format::Json => {
+            println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+                "command": name,
+                "error": format!("{:#}", e),
+            }))?);
+            std::process::exit(1);
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn run(global: &GlobalArgs, command: Commands) -> Result<Option<Report>> {
+    let report = match command {
+        Commands::Search(args) => search::run(global, *args).await?,
+        Commands::Info(args) => info::run(global, args).await?,
+        Commands::Download(args) => download::run(global, args).await?,
+        Commands::Sync(args) => sync::run(global, args).await?,
+        Commands::Ratings(args) => account::ratings(global, args).await?,
+        Commands::Bookmarks(args) => account::bookmarks(global, args).await?,
+        Commands::Ranking(args) => ranking::run(global, args).await?,
+        Commands::HallOfFame(args) => ranking::hall_of_fame(global, args).await?,
+        Commands::User(args) => account::user(global, args).await?,
+        Commands::Works(args) => account::works(global, args).await?,
+        Commands::Export(args) => download::export(global, args).await?,
+        Commands::Login(args) => account::login(global, args).await?,
+        Commands::Watch(args) => {
+            watch::run(global, args).await?;
+            return Ok(None);
+        }
+        Commands::Get(args) => return get::run(global, args).await,
+    };
+    Ok(Some(report))
+}
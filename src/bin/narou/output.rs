@@ -0,0 +1,227 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Human-readable table
+    Text,
+    /// One JSON document: {"command", "count", "items", ...}
+    Json,
+    /// CSV with a header row
+    Csv,
+}
+
+/// テキストの表で1つのセルに使う最大の表示幅（CSVでは切り詰めない）
+const MAX_CELL_WIDTH: usize = 40;
+
+/// 表形式の出力（テキストとCSVで共通）
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&'static str]) -> Self {
+        Self {
+            headers: headers.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    /// 列を揃えたテキスト（全角文字は2桁として数える）
+    pub fn to_text(&self) -> String {
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|cell| truncate(cell, MAX_CELL_WIDTH)).collect())
+            .collect();
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| display_width(h)).collect();
+        for row in &rows {
+            for (i, cell) in row.iter().enumerate() {
+                if let Some(width) = widths.get_mut(i) {
+                    *width = (*width).max(display_width(cell));
+                }
+            }
+        }
+
+        let line = |cells: Vec<&str>| {
+            let mut out = String::new();
+            for (i, cell) in cells.iter().enumerate() {
+                out.push_str(cell);
+                if i + 1 < cells.len() {
+                    out.push_str(&" ".repeat(widths[i] - display_width(cell) + 2));
+                }
+            }
+            out.trim_end().to_string()
+        };
+
+        let mut out = line(self.headers.clone());
+        out.push('\n');
+        for row in &rows {
+            out.push_str(&line(row.iter().map(String::as_str).collect()));
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = self.headers.iter().map(|h| csv_field(h)).collect::<Vec<_>>().join(",");
+        out.push('\n');
+        for row in &self.rows {
+            out.push_str(&row.iter().map(|c| csv_field(c)).collect::<Vec<_>>().join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// サブコマンドの結果
+///
+/// JSONは`{"command": ..., "count": ..., "items": [...]}`に`extra`のキーを加えた形に揃える。
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub command: &'static str,
+    pub items: Vec<Value>,
+    pub extra: Map<String, Value>,
+    pub table: Table,
+}
+
+impl Report {
+    pub fn new(command: &'static str, table: Table) -> Self {
+        Self {
+            command,
+            items: Vec::new(),
+            extra: Map::new(),
+            table,
+        }
+    }
+
+    pub fn item(&mut self, item: Value) {
+        self.items.push(item);
+    }
+
+    pub fn extra(mut self, key: &str, value: Value) -> Self {
+        self.extra.insert(key.to_string(), value);
+        self
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("command".to_string(), Value::from(self.command));
+        object.insert("count".to_string(), Value::from(self.items.len()));
+        for (key, value) in &self.extra {
+            object.insert(key.clone(), value.clone());
+        }
+        object.insert("items".to_string(), Value::Array(self.items.clone()));
+        Value::Object(object)
+    }
+
+    /// 指定の形式で文字列にする
+    pub fn render(&self, format: Format) -> Result<String> {
+        Ok(match format {
+            Format::Json => serde_json::to_string_pretty(&self.to_json())? + "\n",
+            Format::Csv => self.table.to_csv(),
+            Format::Text => self.table.to_text(),
+        })
+    }
+
+    pub fn print(&self, format: Format) -> Result<()> {
+        print!("{}", self.render(format)?);
+        Ok(())
+    }
+}
+
+/// 端末での表示幅（ASCIIと半角カナは1、それ以外は2）
+pub fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c.is_ascii() || ('\u{FF61}'..='\u{FF9F}').contains(&c) { 1 } else { 2 })
+        .sum()
+}
+
+/// 表示幅が`max`を超える文字列を切り詰める
+fn truncate(text: &str, max: usize) -> String {
+    if display_width(text) <= max {
+        return text.to_string();
+    }
+    // 省略記号の…も全角として数える
+    let max = max - display_width("…");
+    let mut out = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let w = display_width(c.encode_utf8(&mut [0; 4]));
+        if width + w > max {
+            break;
+        }
+        out.push(c);
+        width += w;
+    }
+    out.push('…');
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn opt<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_columns_align_full_width() {
+        let mut table = Table::new(&["ncode", "title", "episodes"]);
+        table.row(vec!["n7775do".to_string(), "神を殺したい".to_string(), "2".to_string()]);
+        table.row(vec!["n0001aa".to_string(), "ｶﾅ".to_string(), "120".to_string()]);
+
+        let text = table.to_text();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "ncode    title         episodes");
+        assert_eq!(lines[1], "n7775do  神を殺したい  2");
+        assert_eq!(lines[2], "n0001aa  ｶﾅ            120");
+    }
+
+    #[test]
+    fn test_text_truncates_long_cells() {
+        let mut table = Table::new(&["title", "ncode"]);
+        table.row(vec!["あ".repeat(30), "n7775do".to_string()]);
+        table.row(vec!["a".repeat(45), "n0001aa".to_string()]);
+
+        let text = table.to_text();
+        let lines: Vec<&str> = text.lines().collect();
+        let cell = |line: &str| line.split("  ").next().unwrap().to_string();
+        assert_eq!(cell(lines[1]), format!("{}…", "あ".repeat(19)));
+        assert_eq!(cell(lines[2]), format!("{}…", "a".repeat(38)));
+        assert!(lines[1..].iter().all(|line| display_width(&cell(line)) <= MAX_CELL_WIDTH));
+        // 切り詰めた後の幅で列を揃える
+        assert_eq!(display_width(lines[1]), display_width(lines[2]));
+        // CSVでは切り詰めない
+        assert!(table.to_csv().contains(&"a".repeat(45)));
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("n7775do"), "n7775do");
+        assert_eq!(csv_field("剣と魔法, そして"), "\"剣と魔法, そして\"");
+        assert_eq!(csv_field(r#"「"神"」"#), r#""「""神""」""#);
+        assert_eq!(csv_field("一行目\n二行目"), "\"一行目\n二行目\"");
+        assert_eq!(csv_field(""), "");
+
+        let mut table = Table::new(&["ncode", "title"]);
+        table.row(vec!["n7775do".to_string(), "a,b".to_string()]);
+        assert_eq!(table.to_csv(), "ncode,title\nn7775do,\"a,b\"\n");
+    }
+}
@@ -0,0 +1,272 @@
+use crate::output::{opt, Report, Table};
+use crate::GlobalArgs;
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+use clap::{Args, ValueEnum};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use web_novel_scraper::api::endpoints::hall_of_fame::HallOfFameEntry;
+use web_novel_scraper::api::{HallOfFameApiClient, HallOfFameRequest, RankingApiClient, RankingRequest};
+use web_novel_scraper::opds::genre_name;
+use web_novel_scraper::tracker::fetch_narou_infos;
+use web_novel_scraper::Library;
+
+#[derive(Args, Debug)]
+pub struct RankingArgs {
+    /// Ranking date as YYYYMMDD (default: the latest published ranking)
+    #[arg(long)]
+    pub date: Option<String>,
+
+    /// Ranking type
+    #[arg(long = "type", value_enum, default_value = "daily")]
+    pub ranking_type: RankingType,
+
+    /// Only show ncode, points and rank (skip the novel API lookup)
+    #[arg(long)]
+    pub no_details: bool,
+
+    /// Also write the ranking to this file in the chosen --format
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Also store the ranking in this SQLite library (default: the profile's library)
+    #[arg(long)]
+    pub library: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct HallOfFameArgs {
+    /// Novel code
+    pub ncode: String,
+
+    /// Also write the history to this file in the chosen --format
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum RankingType {
+    Daily,
+    /// Weekly (dates must be Tuesdays)
+    Weekly,
+    /// Monthly (dates must be the 1st)
+    Monthly,
+    /// Quarterly (dates must be the 1st)
+    Quarterly,
+}
+
+impl RankingType {
+    /// 表示順（日間・週間・月間・四半期）
+    const ALL: [RankingType; 4] = [RankingType::Daily, RankingType::Weekly, RankingType::Monthly, RankingType::Quarterly];
+
+    fn name(&self) -> &'static str {
+        match self {
+            RankingType::Daily => "daily",
+            RankingType::Weekly => "weekly",
+            RankingType::Monthly => "monthly",
+            RankingType::Quarterly => "quarterly",
+        }
+    }
+
+    fn suffix(&self) -> char {
+        match self {
+            RankingType::Daily => 'd',
+            RankingType::Weekly => 'w',
+            RankingType::Monthly => 'm',
+            RankingType::Quarterly => 'q',
+        }
+    }
+
+    /// 最新のランキングの日付（日間は前日、週間は直近の火曜日、月間・四半期は今月1日）
+    fn latest_date(&self, today: NaiveDate) -> NaiveDate {
+        match self {
+            RankingType::Daily => today - Duration::days(1),
+            RankingType::Weekly => {
+                let days_since = (today.weekday().num_days_from_monday() + 7 - Weekday::Tue.num_days_from_monday()) % 7;
+                today - Duration::days(days_since as i64)
+            }
+            RankingType::Monthly | RankingType::Quarterly => today.with_day(1).unwrap_or(today),
+        }
+    }
+}
+
+/// ランキングAPIの`rtype`（`20240101-d`など）を組み立てる
+pub fn rtype(date: NaiveDate, ranking_type: RankingType) -> Result<String> {
+    if date < NaiveDate::from_ymd_opt(2013, 5, 1).unwrap_or_default() {
+        bail!("Rankings are available from 2013-05-01");
+    }
+    match ranking_type {
+        RankingType::Weekly if date.weekday() != Weekday::Tue => bail!("Weekly rankings are dated on Tuesdays"),
+        RankingType::Monthly | RankingType::Quarterly if date.day() != 1 => {
+            bail!("Monthly and quarterly rankings are dated on the 1st")
+        }
+        _ => Ok(format!("{}-{}", date.format("%Y%m%d"), ranking_type.suffix())),
+    }
+}
+
+/// `rtype`を日付と種別に分ける
+pub fn parse_rtype(rtype: &str) -> Result<(NaiveDate, RankingType)> {
+    let (date, suffix) = rtype.split_once('-').with_context(|| format!("Invalid rtype: {}", rtype))?;
+    let date = NaiveDate::parse_from_str(date, "%Y%m%d").with_context(|| format!("Invalid rtype: {}", rtype))?;
+    let ranking_type = RankingType::ALL
+        .into_iter()
+        .find(|t| suffix.len() == 1 && suffix.starts_with(t.suffix()))
+        .with_context(|| format!("Invalid rtype: {}", rtype))?;
+    Ok((date, ranking_type))
+}
+
+/// 殿堂入りの記録を種別ごとにまとめ、それぞれ日付順に並べる
+pub fn group_hall_of_fame(entries: &[HallOfFameEntry]) -> Result<Vec<(RankingType, NaiveDate, &HallOfFameEntry)>> {
+    let mut rows = entries
+        .iter()
+        .map(|entry| parse_rtype(&entry.rtype).map(|(date, ranking_type)| (ranking_type, date, entry)))
+        .collect::<Result<Vec<_>>>()?;
+    rows.sort_by_key(|(ranking_type, date, _)| {
+        (RankingType::ALL.iter().position(|t| t == ranking_type), *date)
+    });
+    Ok(rows)
+}
+
+fn write_output(report: &Report, global: &GlobalArgs, path: Option<&PathBuf>) -> Result<()> {
+    if let Some(path) = path {
+        std::fs::write(path, report.render(global.format)?)?;
+        eprintln!("💾 Saved to: {}", path.display());
+    }
+    Ok(())
+}
+
+pub async fn run(global: &GlobalArgs, args: RankingArgs) -> Result<Report> {
+    if global.nocturne() {
+        bail!("The ranking API only covers regular Narou novels");
+    }
+    let date = match &args.date {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y%m%d")?,
+        None => args.ranking_type.latest_date(Local::now().date_naive()),
+    };
+    let rtype = rtype(date, args.ranking_type)?;
+
+    let fetcher = global.fetcher()?;
+    let client = global.api_client(&fetcher);
+    let response = client.execute(&RankingApiClient, &RankingRequest::new(rtype.clone())).await?;
+
+    if let Some(path) = global.library(args.library.clone()) {
+        Library::open(&path)?.upsert_ranking(&rtype, &response.rankings)?;
+        eprintln!("🗄️  {} entries stored in: {}", response.rankings.len(), path.display());
+    }
+
+    // ランキングAPIはncodeと順位しか返さないので、小説APIで作品情報を補う
+    let mut infos = HashMap::new();
+    if !args.no_details {
+        let ncodes: Vec<String> = response.rankings.iter().map(|e| e.ncode.to_lowercase()).collect();
+        for info in fetch_narou_infos(&client, &ncodes).await? {
+            if let Some(ncode) = &info.ncode {
+                infos.insert(ncode.to_lowercase(), info);
+            }
+        }
+        eprintln!("🔍 Looked up {} of {} novels", infos.len(), ncodes.len());
+    }
+
+    let mut report = Report::new(
+        "ranking",
+        Table::new(&["rank", "ncode", "pt", "title", "writer", "genre", "length"]),
+    );
+    for entry in &response.rankings {
+        let ncode = entry.ncode.to_lowercase();
+        let info = infos.get(&ncode);
+        report.table.row(vec![
+            entry.rank.to_string(),
+            ncode,
+            entry.pt.to_string(),
+            opt(info.and_then(|i| i.title.as_deref())),
+            opt(info.and_then(|i| i.writer.as_deref())),
+            opt(info.and_then(|i| i.genre).and_then(genre_name)),
+            opt(info.and_then(|i| i.length)),
+        ]);
+        let mut item = serde_json::to_value(entry)?;
+        if let (Some(object), Some(info)) = (item.as_object_mut(), info) {
+            object.insert("novel".to_string(), serde_json::to_value(info)?);
+        }
+        report.item(item);
+    }
+    let report = report.extra("rtype", json!(rtype));
+    write_output(&report, global, args.output.as_ref())?;
+    Ok(report)
+}
+
+pub async fn hall_of_fame(global: &GlobalArgs, args: HallOfFameArgs) -> Result<Report> {
+    if global.nocturne() {
+        bail!("The hall of fame API only covers regular Narou novels");
+    }
+    let ncode = args.ncode.to_lowercase();
+    let fetcher = global.fetcher()?;
+    let client = global.api_client(&fetcher);
+    let response = client.execute(&HallOfFameApiClient, &HallOfFameRequest::new(ncode.clone())).await?;
+    let rows = group_hall_of_fame(&response.rankings)?;
+
+    let mut report = Report::new("hall-of-fame", Table::new(&["type", "date", "rank", "pt"]));
+    let mut best = serde_json::Map::new();
+    for (ranking_type, date, entry) in &rows {
+        report.table.row(vec![
+            ranking_type.name().to_string(),
+            date.format("%Y-%m-%d").to_string(),
+            entry.rank.to_string(),
+            entry.pt.to_string(),
+        ]);
+        report.item(json!({
+            "type": ranking_type.name(),
+            "date": date.format("%Y-%m-%d").to_string(),
+            "rtype": entry.rtype,
+            "rank": entry.rank,
+            "pt": entry.pt,
+        }));
+        let best_rank = best.entry(ranking_type.name()).or_insert(Value::from(entry.rank));
+        if best_rank.as_u64().is_some_and(|rank| u64::from(entry.rank) < rank) {
+            *best_rank = Value::from(entry.rank);
+        }
+    }
+    eprintln!("🏆 {} entered {} rankings", ncode, rows.len());
+
+    let report = report.extra("ncode", json!(ncode)).extra("best_rank", Value::Object(best));
+    write_output(&report, global, args.output.as_ref())?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtype() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(rtype(date(2024, 1, 1), RankingType::Daily).unwrap(), "20240101-d");
+        assert_eq!(rtype(date(2024, 1, 2), RankingType::Weekly).unwrap(), "20240102-w");
+        assert!(rtype(date(2024, 1, 3), RankingType::Weekly).is_err());
+        assert!(rtype(date(2024, 1, 2), RankingType::Monthly).is_err());
+        assert!(rtype(date(2013, 4, 30), RankingType::Daily).is_err());
+
+        // 2024-01-04は木曜日
+        assert_eq!(RankingType::Weekly.latest_date(date(2024, 1, 4)), date(2024, 1, 2));
+        assert_eq!(RankingType::Weekly.latest_date(date(2024, 1, 2)), date(2024, 1, 2));
+        assert_eq!(RankingType::Quarterly.latest_date(date(2024, 1, 4)), date(2024, 1, 1));
+    }
+
+    #[test]
+    fn test_group_hall_of_fame() {
+        let entry = |rtype: &str, rank| HallOfFameEntry { rtype: rtype.to_string(), pt: 100, rank };
+        let entries = vec![
+            entry("20240110-w", 5),
+            entry("20240102-d", 3),
+            entry("20240101-d", 1),
+            entry("20240201-m", 8),
+            entry("20240102-w", 2),
+        ];
+        let rows = group_hall_of_fame(&entries).unwrap();
+        let rtypes: Vec<&str> = rows.iter().map(|(_, _, e)| e.rtype.as_str()).collect();
+        assert_eq!(rtypes, ["20240101-d", "20240102-d", "20240102-w", "20240110-w", "20240201-m"]);
+        assert_eq!(rows[2].0, RankingType::Weekly);
+
+        assert!(parse_rtype("20240101-x").is_err());
+        assert!(parse_rtype("20240101").is_err());
+    }
+}
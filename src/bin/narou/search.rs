@@ -0,0 +1,358 @@
+use crate::info::{add_narou, add_nocturne, novel_table};
+use crate::output::Report;
+use crate::GlobalArgs;
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use serde_json::Value;
+use web_novel_scraper::api::{NarouApiClient, NarouRequest, NocturneApiClient, NocturneRequest};
+
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    /// Keywords (all must match)
+    pub words: Vec<String>,
+
+    /// Exclude novels containing this word (repeatable)
+    #[arg(long = "not")]
+    pub notword: Vec<String>,
+
+    /// Where keywords are searched (default: everywhere)
+    #[arg(long = "in", value_enum, value_delimiter = ',')]
+    pub targets: Vec<SearchTarget>,
+
+    /// Big genre codes, e.g. 1,2 (Narou only)
+    #[arg(long, value_delimiter = ',')]
+    pub biggenre: Vec<String>,
+
+    /// Exclude these big genre codes (Narou only)
+    #[arg(long, value_delimiter = ',')]
+    pub not_biggenre: Vec<String>,
+
+    /// Genre codes, e.g. 201,202 (with --nocturne: nocgenre codes)
+    #[arg(long, value_delimiter = ',')]
+    pub genre: Vec<String>,
+
+    /// Exclude these genre codes (with --nocturne: nocgenre codes)
+    #[arg(long, value_delimiter = ',')]
+    pub not_genre: Vec<String>,
+
+    /// Novels by this user ID (Narou only)
+    #[arg(long, value_delimiter = ',')]
+    pub userid: Vec<String>,
+
+    /// Novels by this XID (Nocturne only)
+    #[arg(long, value_delimiter = ',')]
+    pub xid: Vec<String>,
+
+    /// Only these novel codes
+    #[arg(long, value_delimiter = ',')]
+    pub ncode: Vec<String>,
+
+    /// Only novels with these tags
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub with: Vec<Tag>,
+
+    /// Exclude novels with these tags
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub without: Vec<Tag>,
+
+    /// Minimum length in characters
+    #[arg(long)]
+    pub min_length: Option<u32>,
+
+    /// Maximum length in characters
+    #[arg(long)]
+    pub max_length: Option<u32>,
+
+    /// Minimum reading time in minutes
+    #[arg(long)]
+    pub min_time: Option<u32>,
+
+    /// Maximum reading time in minutes
+    #[arg(long)]
+    pub max_time: Option<u32>,
+
+    /// Dialogue ratio range in percent, e.g. 30-60
+    #[arg(long)]
+    pub kaiwaritu: Option<String>,
+
+    /// Number of illustrations, e.g. 1- or 1-10
+    #[arg(long)]
+    pub sasie: Option<String>,
+
+    /// Novel type
+    #[arg(long = "type", value_enum)]
+    pub novel_type: Option<NovelTypeFilter>,
+
+    /// Writing style codes: 1 (no indent, many blank lines), 2 (no indent, few), 4 (indented, many), 6 (indented, few)
+    #[arg(long, value_delimiter = ',')]
+    pub buntai: Vec<String>,
+
+    /// How to treat novels on long hiatus
+    #[arg(long, value_enum)]
+    pub stopped: Option<Stopped>,
+
+    /// Last episode posted in: thisweek, lastweek, sevenday, thismonth, lastmonth or UNIXTIME-UNIXTIME
+    #[arg(long)]
+    pub lastup: Option<String>,
+
+    /// Last updated (including revisions) in the same periods as --lastup
+    #[arg(long)]
+    pub lastupdate: Option<String>,
+
+    /// Only novels marked as pickup
+    #[arg(long)]
+    pub pickup: bool,
+
+    /// Sort order
+    #[arg(long, value_enum)]
+    pub order: Option<Order>,
+
+    /// API output fields to request, e.g. t,n,w,gp (default: all)
+    #[arg(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+
+    /// Maximum number of results
+    #[arg(long, default_value_t = 20)]
+    pub limit: u32,
+
+    /// Page through every result (the API serves at most 2499)
+    #[arg(long, conflicts_with = "limit")]
+    pub all: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SearchTarget {
+    Title,
+    /// Synopsis
+    Story,
+    Keyword,
+    /// Author name
+    Writer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Tag {
+    /// R15 (Narou only)
+    R15,
+    /// Boys' love
+    Bl,
+    /// Girls' love
+    Gl,
+    /// Cruel depictions
+    Zankoku,
+    /// Reincarnation
+    Tensei,
+    /// Transported to another world
+    Tenni,
+    /// Reincarnation or transportation
+    TenseiOrTenni,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum NovelTypeFilter {
+    /// Short stories
+    Short,
+    /// Ongoing serials
+    Ongoing,
+    /// Completed serials
+    Completed,
+    /// All serials
+    Serial,
+    /// Short stories and completed serials
+    Finished,
+}
+
+impl NovelTypeFilter {
+    fn code(&self) -> &'static str {
+        match self {
+            NovelTypeFilter::Short => "t",
+            NovelTypeFilter::Ongoing => "r",
+            NovelTypeFilter::Completed => "er",
+            NovelTypeFilter::Serial => "re",
+            NovelTypeFilter::Finished => "ter",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Stopped {
+    /// Leave out novels on long hiatus
+    Exclude,
+    /// Only novels on long hiatus
+    Only,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Order {
+    New,
+    Favnovelcnt,
+    Reviewcnt,
+    Hyoka,
+    Hyokaasc,
+    Dailypoint,
+    Weeklypoint,
+    Monthlypoint,
+    Quarterpoint,
+    Yearlypoint,
+    Impressioncnt,
+    Hyokacnt,
+    Hyokacntasc,
+    Weekly,
+    Lengthdesc,
+    Lengthasc,
+    Generalfirstup,
+    Ncodedesc,
+    Old,
+}
+
+fn joined(values: &[String], sep: &str) -> Option<String> {
+    (!values.is_empty()).then(|| values.join(sep))
+}
+
+fn flag(set: bool) -> Option<u8> {
+    set.then_some(1)
+}
+
+/// なろうとノクターンで共通の検索条件を設定する
+macro_rules! common_filters {
+    ($request:ident, $args:ident) => {
+        $request.word = joined(&$args.words, " ");
+        $request.notword = joined(&$args.notword, " ");
+        $request.title = flag($args.targets.contains(&SearchTarget::Title));
+        $request.ex = flag($args.targets.contains(&SearchTarget::Story));
+        $request.keyword = flag($args.targets.contains(&SearchTarget::Keyword));
+        $request.wname = flag($args.targets.contains(&SearchTarget::Writer));
+        $request.ncode = joined(&$args.ncode, "-");
+
+        $request.isbl = flag($args.with.contains(&Tag::Bl));
+        $request.isgl = flag($args.with.contains(&Tag::Gl));
+        $request.iszankoku = flag($args.with.contains(&Tag::Zankoku));
+        $request.istensei = flag($args.with.contains(&Tag::Tensei));
+        $request.istenni = flag($args.with.contains(&Tag::Tenni));
+        $request.istt = flag($args.with.contains(&Tag::TenseiOrTenni));
+        $request.notbl = flag($args.without.contains(&Tag::Bl));
+        $request.notgl = flag($args.without.contains(&Tag::Gl));
+        $request.notzankoku = flag($args.without.contains(&Tag::Zankoku));
+        $request.nottensei = flag($args.without.iter().any(|t| matches!(t, Tag::Tensei | Tag::TenseiOrTenni)));
+        $request.nottenni = flag($args.without.iter().any(|t| matches!(t, Tag::Tenni | Tag::TenseiOrTenni)));
+
+        $request.minlen = $args.min_length;
+        $request.maxlen = $args.max_length;
+        $request.mintime = $args.min_time;
+        $request.maxtime = $args.max_time;
+        $request.kaiwaritu = $args.kaiwaritu.clone();
+        $request.sasie = $args.sasie.clone();
+        $request.r#type = $args.novel_type.map(|t| t.code().to_string());
+        $request.buntai = joined(&$args.buntai, "-");
+        $request.stop = $args.stopped.map(|s| match s {
+            Stopped::Exclude => 1,
+            Stopped::Only => 2,
+        });
+        $request.lastup = $args.lastup.clone();
+        $request.lastupdate = $args.lastupdate.clone();
+        $request.ispickup = flag($args.pickup);
+        $request.order = $args.order.and_then(|o| o.to_possible_value()).map(|v| v.get_name().to_string());
+        $request.of = joined(&$args.fields, "-");
+    };
+}
+
+pub fn narou_request(args: &SearchArgs) -> Result<NarouRequest> {
+    if !args.xid.is_empty() {
+        bail!("--xid is only available with --nocturne");
+    }
+    let mut request = NarouRequest::new();
+    common_filters!(request, args);
+    request.biggenre = joined(&args.biggenre, "-");
+    request.notbiggenre = joined(&args.not_biggenre, "-");
+    request.genre = joined(&args.genre, "-");
+    request.notgenre = joined(&args.not_genre, "-");
+    request.userid = joined(&args.userid, "-");
+    request.isr15 = flag(args.with.contains(&Tag::R15));
+    request.notr15 = flag(args.without.contains(&Tag::R15));
+    Ok(request)
+}
+
+pub fn nocturne_request(args: &SearchArgs) -> Result<NocturneRequest> {
+    if !args.biggenre.is_empty() || !args.not_biggenre.is_empty() {
+        bail!("--biggenre is not available with --nocturne (use --genre with nocgenre codes)");
+    }
+    if !args.userid.is_empty() {
+        bail!("--userid is not available with --nocturne (use --xid)");
+    }
+    if args.with.contains(&Tag::R15) || args.without.contains(&Tag::R15) {
+        bail!("The r15 tag is not available with --nocturne");
+    }
+    let mut request = NocturneRequest::new();
+    common_filters!(request, args);
+    request.nocgenre = joined(&args.genre, "-");
+    request.notnocgenre = joined(&args.not_genre, "-");
+    request.xid = joined(&args.xid, "-");
+    Ok(request)
+}
+
+pub async fn run(global: &GlobalArgs, args: SearchArgs) -> Result<Report> {
+    let fetcher = global.fetcher()?;
+    let client = global.api_client(&fetcher);
+    let max_results = (!args.all).then_some(args.limit);
+
+    let mut report = Report::new("search", novel_table());
+    let allcount = if global.nocturne() {
+        let results = client.execute_all(&NocturneApiClient, &nocturne_request(&args)?, max_results).await?;
+        for info in &results.items {
+            add_nocturne(&mut report, info)?;
+        }
+        results.allcount
+    } else {
+        let results = client.execute_all(&NarouApiClient, &narou_request(&args)?, max_results).await?;
+        for info in &results.items {
+            add_narou(&mut report, info)?;
+        }
+        results.allcount
+    };
+
+    eprintln!("🔍 {} of {} matching novels", report.items.len(), allcount.unwrap_or(0));
+    if args.all && allcount.is_some_and(|count| count as usize > report.items.len()) {
+        eprintln!("⚠️  The API only serves the first {} results; narrow the search to see the rest", report.items.len());
+    }
+    Ok(report.extra("allcount", Value::from(allcount)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        search: SearchArgs,
+    }
+
+    fn parse(args: &[&str]) -> SearchArgs {
+        Cli::parse_from(std::iter::once("search").chain(args.iter().copied())).search
+    }
+
+    #[test]
+    fn test_narou_request() {
+        let args = parse(&[
+            "異世界", "魔法", "--not", "ハーレム", "--in", "title,keyword", "--genre", "201,202", "--with", "tensei",
+            "--without", "r15,bl", "--min-length", "100000", "--type", "completed", "--order", "weeklypoint",
+            "--fields", "t,n,w",
+        ]);
+        let request = narou_request(&args).unwrap();
+        assert_eq!(request.word.as_deref(), Some("異世界 魔法"));
+        assert_eq!(request.notword.as_deref(), Some("ハーレム"));
+        assert_eq!((request.title, request.ex, request.keyword, request.wname), (Some(1), None, Some(1), None));
+        assert_eq!(request.genre.as_deref(), Some("201-202"));
+        assert_eq!((request.istensei, request.notr15, request.notbl), (Some(1), Some(1), Some(1)));
+        assert_eq!(request.minlen, Some(100000));
+        assert_eq!(request.r#type.as_deref(), Some("er"));
+        assert_eq!(request.order.as_deref(), Some("weeklypoint"));
+        assert_eq!(request.of.as_deref(), Some("t-n-w"));
+
+        assert!(nocturne_request(&args).is_err());
+        let nocturne = nocturne_request(&parse(&["--genre", "1", "--xid", "x9487b"])).unwrap();
+        assert_eq!(nocturne.nocgenre.as_deref(), Some("1"));
+        assert_eq!(nocturne.xid.as_deref(), Some("x9487b"));
+    }
+}
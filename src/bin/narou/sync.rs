@@ -0,0 +1,238 @@
+use crate::info::novel_type;
+use crate::output::{Report, Table};
+use crate::GlobalArgs;
+use anyhow::{bail, Result};
+use clap::Args;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use web_novel_scraper::tracker::{fetch_narou_infos, fetch_nocturne_infos};
+use web_novel_scraper::{Library, NarouNovelScraper, NovelType};
+
+#[derive(Args, Debug)]
+pub struct SyncArgs {
+    /// SQLite library to update (default: the profile's library)
+    #[arg(long)]
+    pub library: Option<PathBuf>,
+
+    /// Novels to sync (default: every novel in the library); new ones are added
+    pub ncodes: Vec<String>,
+}
+
+/// 同期の対象（ncodeとノクターンかどうか）
+struct Target {
+    ncode: String,
+    nocturne: bool,
+}
+
+pub async fn run(global: &GlobalArgs, args: SyncArgs) -> Result<Report> {
+    let Some(path) = global.library(args.library) else {
+        bail!("No library: pass --library or set library in the profile");
+    };
+    let library = Library::open(&path)?;
+    let targets: Vec<Target> = if args.ncodes.is_empty() {
+        library
+            .novels()?
+            .into_iter()
+            .map(|r| Target { ncode: r.ncode, nocturne: r.is_nocturne })
+            .collect()
+    } else {
+        let mut targets = Vec::new();
+        for ncode in &args.ncodes {
+            let ncode = ncode.to_lowercase();
+            let nocturne = library.novel(&ncode)?.map(|r| r.is_nocturne).unwrap_or(global.nocturne());
+            targets.push(Target { ncode, nocturne });
+        }
+        targets
+    };
+    if targets.is_empty() {
+        bail!("{} has no novels: pass ncodes to add them", path.display());
+    }
+
+    let fetcher = global.fetcher()?;
+    let client = global.api_client(&fetcher);
+    let mut report = Report::new("sync", Table::new(&["ncode", "title", "episodes", "stored", "new"]));
+
+    for nocturne in [false, true] {
+        let ncodes: Vec<String> = targets.iter().filter(|t| t.nocturne == nocturne).map(|t| t.ncode.clone()).collect();
+        if ncodes.is_empty() {
+            continue;
+        }
+
+        // APIの最新情報を保存して、話数と作品の種類を得る
+        let mut statuses = Vec::new();
+        if nocturne {
+            for info in fetch_nocturne_infos(&client, &ncodes).await? {
+                library.upsert_nocturne_novel(&info)?;
+                statuses.push((info.ncode.clone(), info.title.clone(), novel_type(info.novel_type, info.general_all_no)));
+            }
+        } else {
+            for info in fetch_narou_infos(&client, &ncodes).await? {
+                library.upsert_novel(&info)?;
+                statuses.push((info.ncode.clone(), info.title.clone(), novel_type(info.novel_type, info.general_all_no)));
+            }
+        }
+
+        let scraper = if nocturne {
+            NarouNovelScraper::new_nocturne(fetcher.clone())
+        } else {
+            NarouNovelScraper::new(fetcher.clone())
+        };
+        for (ncode, title, novel_type) in statuses {
+            let Some(ncode) = ncode.map(|n| n.to_lowercase()) else {
+                continue;
+            };
+            let synced = sync_episodes(&library, &scraper, &ncode, &novel_type).await?;
+
+            report.table.row(vec![
+                ncode.clone(),
+                title.clone().unwrap_or_default(),
+                synced.episode_count.to_string(),
+                (synced.stored + synced.added.len()).to_string(),
+                synced.added.len().to_string(),
+            ]);
+            report.item(json!({
+                "ncode": ncode,
+                "title": title,
+                "nocturne": nocturne,
+                "episode_count": synced.episode_count,
+                "new_episodes": synced.added,
+            }));
+        }
+    }
+
+    Ok(report)
+}
+
+/// 1作品分の同期結果
+struct SyncedEpisodes {
+    /// APIでの話数（短編は1）
+    episode_count: usize,
+    /// 同期前に保存済みだった話数
+    stored: usize,
+    /// 新しく保存した話（短編は0）
+    added: Vec<u32>,
+}
+
+/// ライブラリにない話を取得して保存する
+async fn sync_episodes(
+    library: &Library,
+    scraper: &NarouNovelScraper,
+    ncode: &str,
+    novel_type: &NovelType,
+) -> Result<SyncedEpisodes> {
+    let stored: BTreeSet<u32> = library.episodes_for(ncode)?.iter().map(|e| e.episode_number).collect();
+    let wanted: Vec<u32> = match novel_type {
+        NovelType::ShortStory => vec![0],
+        NovelType::Serial { total_episodes } => (1..=*total_episodes).collect(),
+    };
+    let missing: Vec<u32> = wanted.iter().copied().filter(|n| !stored.contains(n)).collect();
+    if !missing.is_empty() {
+        eprintln!("📥 {}: {} new episodes", ncode, missing.len());
+    }
+
+    // 新しい話があれば、exportの章見出しに使う目次も取り直す
+    if matches!(novel_type, NovelType::Serial { .. }) && !missing.is_empty() {
+        match scraper.fetch_index(ncode).await {
+            Ok(index) => library.upsert_index(ncode, &index)?,
+            Err(e) => eprintln!("⚠️  Failed to fetch the index of {}: {}", ncode, e),
+        }
+    }
+
+    let mut added = Vec::new();
+    for episode_number in missing {
+        let episode = scraper.fetch_episode(ncode, episode_number).await?;
+        library.upsert_episode(ncode, &episode)?;
+        added.push(episode_number);
+    }
+
+    Ok(SyncedEpisodes {
+        episode_count: wanted.len(),
+        stored: stored.len(),
+        added,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web_novel_scraper::{Episode, FetcherConfig, HtmlFetcher, RequestDelayConfig, UserAgentMode};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn scraper(server: &MockServer) -> NarouNovelScraper {
+        let fetcher = HtmlFetcher::from_config(
+            FetcherConfig::new()
+                .user_agent_mode(UserAgentMode::Fixed(Some("SyncBot/1.0".to_string())))
+                .delay(RequestDelayConfig::disabled()),
+        )
+        .unwrap();
+        NarouNovelScraper::new(fetcher).with_base_url(server.uri())
+    }
+
+    #[tokio::test]
+    async fn test_sync_short_story() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/n0001aa/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<h1 class="p-novel__title">短編</h1><div class="p-novel__body"><div class="js-novel-text p-novel__text"><p id="L1">本文</p></div></div>"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let scraper = scraper(&server);
+        let library = Library::open_in_memory().unwrap();
+
+        let synced = sync_episodes(&library, &scraper, "n0001aa", &NovelType::ShortStory).await.unwrap();
+        assert_eq!((synced.episode_count, synced.stored, synced.added), (1, 0, vec![0]));
+        assert_eq!(library.episodes_for("n0001aa").unwrap()[0].subtitle, "短編");
+
+        // 保存済みなら取り直さない
+        let synced = sync_episodes(&library, &scraper, "n0001aa", &NovelType::ShortStory).await.unwrap();
+        assert!(synced.added.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sync_serial_stores_index() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/n0002aa/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(concat!(
+                r#"<h1 class="p-novel__title">連載</h1><div class="p-eplist">"#,
+                r#"<div class="p-eplist__chapter-title">第一章</div>"#,
+                r#"<div class="p-eplist__sublist"><a href="/n0002aa/1/" class="p-eplist__subtitle">一</a><div class="p-eplist__update">2024/01/01 00:00</div></div>"#,
+                r#"<div class="p-eplist__sublist"><a href="/n0002aa/2/" class="p-eplist__subtitle">二</a><div class="p-eplist__update">2024/01/02 00:00</div></div>"#,
+                "</div>",
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/n0002aa/2/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<h1 class="p-novel__title">二</h1><div class="p-novel__body"><div class="js-novel-text p-novel__text"><p id="L1">本文</p></div></div>"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let library = Library::open_in_memory().unwrap();
+        let first = Episode {
+            episode_number: 1,
+            html: r#"<h1 class="p-novel__title">一</h1><div class="p-novel__body"><div class="js-novel-text p-novel__text"><p id="L1">本文</p></div></div>"#.to_string(),
+        };
+        library.upsert_episode("n0002aa", &first).unwrap();
+
+        let novel_type = NovelType::Serial { total_episodes: 2 };
+        let synced = sync_episodes(&library, &scraper(&server), "n0002aa", &novel_type).await.unwrap();
+        assert_eq!((synced.episode_count, synced.stored, synced.added), (2, 1, vec![2]));
+        let index = library.index("n0002aa").unwrap().unwrap();
+        assert_eq!(index.chapters[0].title.as_deref(), Some("第一章"));
+        assert_eq!(index.entries().count(), 2);
+
+        // 新しい話がなければ目次も取り直さない
+        sync_episodes(&library, &scraper(&server), "n0002aa", &novel_type).await.unwrap();
+    }
+}
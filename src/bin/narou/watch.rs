@@ -0,0 +1,78 @@
+use crate::GlobalArgs;
+use anyhow::{bail, Result};
+use clap::Args;
+use std::path::PathBuf;
+use std::time::Duration;
+use web_novel_scraper::tracker::read_watchlist;
+use web_novel_scraper::{Library, RssSink, StdoutSink, Watcher, WebhookSink};
+
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    /// Novel codes to follow (in addition to the watchlist)
+    pub ncodes: Vec<String>,
+
+    /// Watchlist file (one ncode per line, # starts a comment)
+    #[arg(short, long)]
+    pub watchlist: Option<PathBuf>,
+
+    /// File that remembers which episodes were already announced
+    #[arg(long, default_value = "./output/watch_state.json")]
+    pub state: PathBuf,
+
+    /// Seconds between polls
+    #[arg(long, default_value_t = 1800)]
+    pub interval: u64,
+
+    /// Poll once and exit
+    #[arg(long)]
+    pub once: bool,
+
+    /// POST each event as JSON to this URL (repeatable)
+    #[arg(long)]
+    pub webhook: Vec<String>,
+
+    /// Keep an RSS file of recent events
+    #[arg(long)]
+    pub rss: Option<PathBuf>,
+
+    /// Store fetched episodes in this SQLite library (default: the profile's library)
+    #[arg(long)]
+    pub library: Option<PathBuf>,
+}
+
+/// 新しい話を1行1イベントのJSONで標準出力に流し続ける（--formatは使わない）
+pub async fn run(global: &GlobalArgs, args: WatchArgs) -> Result<()> {
+    let mut ncodes = match &args.watchlist {
+        Some(path) => read_watchlist(path)?,
+        None => Vec::new(),
+    };
+    for ncode in &args.ncodes {
+        let ncode = ncode.to_lowercase();
+        if !ncodes.contains(&ncode) {
+            ncodes.push(ncode);
+        }
+    }
+    if ncodes.is_empty() {
+        bail!("No novels to watch: pass ncodes or --watchlist");
+    }
+
+    let mut watcher = Watcher::new(global.fetcher()?, global.nocturne(), &args.state)?.sink(Box::new(StdoutSink));
+    if let Some(path) = global.library(args.library.clone()) {
+        watcher = watcher.library(Library::open(&path)?);
+    }
+    for url in &args.webhook {
+        watcher = watcher.sink(Box::new(WebhookSink::new(url)));
+    }
+    if let Some(path) = &args.rss {
+        watcher = watcher.sink(Box::new(RssSink::open(path, "なろう 更新通知")?));
+    }
+
+    eprintln!("👀 Watching {} novels", ncodes.len());
+    if args.once {
+        let events = watcher.poll(&ncodes).await?;
+        eprintln!("✅ {} new episodes", events.len());
+    } else {
+        watcher.run(&ncodes, Duration::from_secs(args.interval)).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use anyhow::Result;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use clap::Parser;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use web_novel_scraper::export::EpubOptions;
+use web_novel_scraper::opds::build_epub;
+use web_novel_scraper::{Catalog, Library, OpdsFeed};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Serve the local library as an OPDS 1.2/2.0 catalog", long_about = None)]
+struct Args {
+    /// SQLite library to serve
+    #[arg(short, long, default_value = "./output/library.sqlite3")]
+    library: PathBuf,
+
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8081")]
+    addr: String,
+
+    /// Number of novels in the recently updated feed
+    #[arg(long, default_value_t = 50)]
+    recent: usize,
+
+    /// Generate EPUBs in vertical writing mode
+    #[arg(long)]
+    vertical: bool,
+}
+
+struct AppState {
+    library: Mutex<Library>,
+    recent: usize,
+    epub_options: EpubOptions,
+}
+
+type SharedState = Arc<AppState>;
+
+const OPDS1_BASE: &str = "/opds";
+const OPDS2_BASE: &str = "/opds2";
+const BOOKS_BASE: &str = "/books";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let library = Library::open(&args.library)?;
+    let catalog = Catalog::from_library(&library)?;
+    println!("📚 {} novels in {}", catalog.books().len(), args.library.display());
+
+    let state = Arc::new(AppState {
+        library: Mutex::new(library),
+        recent: args.recent,
+        epub_options: EpubOptions {
+            vertical: args.vertical,
+            ..Default::default()
+        },
+    });
+
+    let app = Router::new()
+        .route("/opds", get(|s| opds1(s, Vec::new())))
+        .route("/opds/:section", get(|s, Path(section): Path<String>| opds1(s, vec![section])))
+        .route(
+            "/opds/:section/:key",
+            get(|s, Path((section, key)): Path<(String, String)>| opds1(s, vec![section, key])),
+        )
+        .route("/opds2", get(|s| opds2(s, Vec::new())))
+        .route("/opds2/:section", get(|s, Path(section): Path<String>| opds2(s, vec![section])))
+        .route(
+            "/opds2/:section/:key",
+            get(|s, Path((section, key)): Path<(String, String)>| opds2(s, vec![section, key])),
+        )
+        .route("/books/:file", get(epub))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(&args.addr).await?;
+    let addr = listener.local_addr()?;
+    println!("📡 OPDS 1.2: http://{}{}", addr, OPDS1_BASE);
+    println!("📡 OPDS 2.0: http://{}{}", addr, OPDS2_BASE);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+/// パスに対応するフィードを作る（リクエストごとにライブラリを読み直す）
+fn feed_for(state: &AppState, segments: &[String]) -> Result<Option<OpdsFeed>> {
+    let library = state.library.lock().unwrap();
+    let catalog = Catalog::from_library(&library)?;
+
+    let feed = match segments.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [] => OpdsFeed::root(),
+        ["authors"] => OpdsFeed::authors(&catalog),
+        ["authors", writer] => OpdsFeed::author(&catalog, writer),
+        ["genres"] => OpdsFeed::genres(&catalog),
+        ["genres", key] => OpdsFeed::genre(&catalog, key),
+        ["recent"] => OpdsFeed::recent(&catalog, state.recent),
+        ["completed"] => OpdsFeed::completed(&catalog),
+        _ => return Ok(None),
+    };
+    Ok(Some(feed))
+}
+
+async fn opds1(State(state): State<SharedState>, segments: Vec<String>) -> Response {
+    match feed_for(&state, &segments) {
+        Ok(Some(feed)) => (
+            [(header::CONTENT_TYPE, feed.opds1_content_type())],
+            feed.to_opds1(OPDS1_BASE, BOOKS_BASE),
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn opds2(State(state): State<SharedState>, segments: Vec<String>) -> Response {
+    match feed_for(&state, &segments) {
+        Ok(Some(feed)) => (
+            [(header::CONTENT_TYPE, "application/opds+json")],
+            Json(feed.to_opds2(OPDS2_BASE, BOOKS_BASE)),
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 保存済みの本文からその場でEPUBを作って返す
+async fn epub(State(state): State<SharedState>, Path(file): Path<String>) -> Response {
+    let Some(ncode) = file.strip_suffix(".epub") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let library = state.library.lock().unwrap();
+    match build_epub(&library, ncode, state.epub_options.clone()) {
+        Ok(Some(bytes)) => (
+            [
+                (header::CONTENT_TYPE, "application/epub+zip".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.epub\"", ncode.to_lowercase())),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+fn internal_error(e: anyhow::Error) -> Response {
+    eprintln!("⚠️  {:#}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response()
+}
@@ -0,0 +1,238 @@
+use anyhow::Result;
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use clap::Parser;
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use web_novel_scraper::reader::{render_episode, render_index, render_shelf, ruby_mode_from_key, POSITIONS_FILE};
+use web_novel_scraper::{Bookshelf, ReadingPositions};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Read downloaded novels in the browser", long_about = None)]
+struct Args {
+    /// Output directory written by fetch_novel (raw HTML format)
+    #[arg(short, long, default_value = "./output")]
+    output: PathBuf,
+
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8082")]
+    addr: String,
+
+    /// File that stores each reader's position (default: {output}/reader_positions.json)
+    #[arg(long)]
+    positions: Option<PathBuf>,
+}
+
+struct AppState {
+    shelf: Mutex<Bookshelf>,
+    positions: Mutex<ReadingPositions>,
+    positions_path: PathBuf,
+}
+
+type SharedState = Arc<AppState>;
+
+/// 読者名を覚えるCookie
+const USER_COOKIE: &str = "reader_user";
+const DEFAULT_USER: &str = "default";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let positions_path = args.positions.clone().unwrap_or_else(|| args.output.join(POSITIONS_FILE));
+    let shelf = Bookshelf::open(&args.output);
+    println!("📚 {} novels in {}", shelf.novels()?.len(), args.output.display());
+
+    let state = Arc::new(AppState {
+        shelf: Mutex::new(shelf),
+        positions: Mutex::new(ReadingPositions::load(&positions_path)?),
+        positions_path,
+    });
+
+    let app = Router::new()
+        .route("/", get(shelf_page))
+        .route("/user", post(switch_user))
+        .route("/novels/:ncode/", get(|s, Path(ncode): Path<String>| index_page(s, ncode)))
+        .route("/novels/:ncode/:file", get(novel_file))
+        .route("/novels/:ncode/images/:file", get(image))
+        .route("/api/position", post(save_position))
+        .route("/api/position/:ncode", get(load_position))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(&args.addr).await?;
+    println!("📖 Reader at http://{}/", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+async fn shelf_page(State(state): State<SharedState>, headers: HeaderMap) -> Response {
+    let user = current_user(&headers);
+    let novels = match state.shelf.lock().unwrap().novels() {
+        Ok(novels) => novels,
+        Err(e) => return internal_error(e),
+    };
+    let positions = state.positions.lock().unwrap();
+    Html(render_shelf(&novels, &user, positions.for_user(&user))).into_response()
+}
+
+#[derive(Deserialize)]
+struct UserForm {
+    name: String,
+}
+
+async fn switch_user(Form(form): Form<UserForm>) -> Response {
+    let name: String = form.name.trim().chars().filter(|c| !c.is_control() && *c != ';').collect();
+    let name = if name.is_empty() { DEFAULT_USER.to_string() } else { name };
+    let cookie = format!("{}={}; Path=/; Max-Age=31536000", USER_COOKIE, encode_cookie_value(&name));
+    ([(header::SET_COOKIE, cookie)], Redirect::to("/")).into_response()
+}
+
+async fn index_page(State(state): State<SharedState>, ncode: String) -> Response {
+    let mut shelf = state.shelf.lock().unwrap();
+    let result = shelf.novel(&ncode).and_then(|novel| match novel {
+        Some(novel) => shelf.index(&novel).map(|index| Some(render_index(&novel, index))),
+        None => Ok(None),
+    });
+    html_or_status(result)
+}
+
+/// `index.html`・`0001.html`・短編の`{ncode}.html`
+async fn novel_file(State(state): State<SharedState>, headers: HeaderMap, Path((ncode, file)): Path<(String, String)>) -> Response {
+    if file == "index.html" {
+        return index_page(State(state), ncode).await;
+    }
+    let Some(stem) = file.strip_suffix(".html") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let episode_number = if stem.eq_ignore_ascii_case(&ncode) {
+        0
+    } else {
+        match stem.parse() {
+            Ok(n) => n,
+            Err(_) => return StatusCode::NOT_FOUND.into_response(),
+        }
+    };
+
+    let ruby = ruby_mode_from_key(cookie_value(&headers, "ruby").as_deref().unwrap_or("keep"));
+    let shelf = state.shelf.lock().unwrap();
+    let result = shelf.novel(&ncode).and_then(|novel| match novel {
+        Some(novel) => Ok(novel.episode(episode_number)?.map(|episode| render_episode(&novel, &episode, ruby))),
+        None => Ok(None),
+    });
+    html_or_status(result)
+}
+
+/// `fetch_novel --illustrations`で保存した挿絵
+async fn image(State(state): State<SharedState>, Path((ncode, file)): Path<(String, String)>) -> Response {
+    if file.contains("..") || file.contains('/') || file.contains('\\') {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let novel = match state.shelf.lock().unwrap().novel(&ncode) {
+        Ok(Some(novel)) => novel,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return internal_error(e),
+    };
+    let content_type = match file.rsplit('.').next().map(str::to_lowercase).as_deref() {
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "image/jpeg",
+    };
+    match fs::read(novel.dir.join("images").join(&file)) {
+        Ok(bytes) => ([(header::CONTENT_TYPE, content_type)], bytes).into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct PositionUpdate {
+    ncode: String,
+    episode_number: u32,
+    paragraph_id: Option<String>,
+}
+
+async fn save_position(State(state): State<SharedState>, headers: HeaderMap, Json(update): Json<PositionUpdate>) -> Response {
+    let user = current_user(&headers);
+    let mut positions = state.positions.lock().unwrap();
+    positions.set(&user, &update.ncode, update.episode_number, update.paragraph_id);
+    match positions.save(&state.positions_path) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+async fn load_position(State(state): State<SharedState>, headers: HeaderMap, Path(ncode): Path<String>) -> Response {
+    let user = current_user(&headers);
+    match state.positions.lock().unwrap().get(&user, &ncode) {
+        Some(position) => Json(position.clone()).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn current_user(headers: &HeaderMap) -> String {
+    cookie_value(headers, USER_COOKIE)
+        .map(|v| decode_cookie_value(&v))
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| DEFAULT_USER.to_string())
+}
+
+fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// 読者名（日本語を含む）をCookieに入れられる形にする
+fn encode_cookie_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn decode_cookie_value(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn html_or_status(result: Result<Option<String>>) -> Response {
+    match result {
+        Ok(Some(page)) => Html(page).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+fn internal_error(e: anyhow::Error) -> Response {
+    eprintln!("⚠️  {:#}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response()
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use clap::Parser;
+use std::path::PathBuf;
+use web_novel_scraper::{Library, SearchIndex};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Full-text search over downloaded episodes", long_about = None)]
+struct Args {
+    /// Search terms (space-separated terms must all appear in the same paragraph)
+    query: Vec<String>,
+
+    /// Search index file
+    #[arg(long, default_value = "./output/search.sqlite3")]
+    index: PathBuf,
+
+    /// fetch_novel output directory to index before searching
+    #[arg(long, default_value = "./output")]
+    output: PathBuf,
+
+    /// Also index the episodes stored in this SQLite library
+    #[arg(long)]
+    library: Option<PathBuf>,
+
+    /// Search the existing index without scanning for new episodes
+    #[arg(long)]
+    no_update: bool,
+
+    /// Only search this novel
+    #[arg(short, long)]
+    ncode: Option<String>,
+
+    /// Maximum number of results
+    #[arg(short, long, default_value_t = 50)]
+    limit: usize,
+
+    /// Output format (json or text)
+    #[arg(short, long, default_value = "text")]
+    format: String,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let mut index = SearchIndex::open(&args.index)?;
+
+    // 新しく取得した話だけ索引し直す
+    if !args.no_update {
+        let mut updated = 0;
+        if args.output.is_dir() {
+            updated += index.index_output_dir(&args.output)?;
+        }
+        if let Some(path) = &args.library {
+            updated += index.index_library(&Library::open(path)?)?;
+        }
+        if updated > 0 {
+            eprintln!("🔎 Indexed {} new or changed episodes", updated);
+        }
+    }
+
+    let query = args.query.join(" ");
+    if query.trim().is_empty() {
+        eprintln!("{} episodes indexed", index.document_count()?);
+        return Ok(());
+    }
+
+    let hits = index.search(&query, args.ncode.as_deref(), args.limit)?;
+
+    match args.format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&hits)?),
+        _ => {
+            for hit in &hits {
+                println!("{} #{} {} ({})", hit.ncode, hit.episode_number, hit.line_id, hit.subtitle);
+                println!("    {}", hit.snippet);
+            }
+            println!("\n{} results", hits.len());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use anyhow::{bail, Result};
+use chrono::Duration;
+use clap::Parser;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use web_novel_scraper::api::HttpClient;
+use web_novel_scraper::tracker::{fetch_snapshots, read_watchlist, write_metrics_csv};
+use web_novel_scraper::{Growth, Library, MetricSnapshot};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Record point and bookmark counts for a watchlist and report growth", long_about = None)]
+struct Args {
+    /// Watchlist file (one ncode per line, # starts a comment)
+    #[arg(short, long)]
+    watchlist: Option<PathBuf>,
+
+    /// Novel codes to track (in addition to the watchlist)
+    #[arg(short, long)]
+    ncode: Vec<String>,
+
+    /// SQLite library that stores the time series
+    #[arg(long, default_value = "./output/library.sqlite3")]
+    library: PathBuf,
+
+    /// Use the Nocturne API (R18) instead of the regular Narou API
+    #[arg(long)]
+    nocturne: bool,
+
+    /// Only report on stored data without calling the API
+    #[arg(long)]
+    no_fetch: bool,
+
+    /// Compare against the oldest record within this many days (default: all history)
+    #[arg(long)]
+    window_days: Option<i64>,
+
+    /// Output format (text, json, or csv)
+    #[arg(short, long, default_value = "text")]
+    format: String,
+
+    /// Write the export to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let mut library = Library::open(&args.library)?;
+
+    let mut ncodes = match &args.watchlist {
+        Some(path) => read_watchlist(path)?,
+        None => Vec::new(),
+    };
+    for ncode in &args.ncode {
+        let ncode = ncode.to_lowercase();
+        if !ncodes.contains(&ncode) {
+            ncodes.push(ncode);
+        }
+    }
+    if ncodes.is_empty() {
+        ncodes = library.tracked_ncodes()?;
+    }
+    if ncodes.is_empty() {
+        bail!("No ncodes to track: pass --watchlist or --ncode");
+    }
+
+    if !args.no_fetch {
+        let snapshots = fetch_snapshots(&HttpClient::default(), &ncodes, args.nocturne).await?;
+        library.record_metrics(&snapshots)?;
+        eprintln!("📊 Recorded {} of {} novels", snapshots.len(), ncodes.len());
+    }
+
+    let window = args.window_days.map(Duration::days);
+    let mut series: Vec<(String, Vec<MetricSnapshot>)> = Vec::new();
+    for ncode in &ncodes {
+        series.push((ncode.clone(), library.metrics_for(ncode)?));
+    }
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+
+    match args.format.as_str() {
+        "json" => {
+            let json_output: Vec<_> = series
+                .iter()
+                .map(|(ncode, snapshots)| {
+                    serde_json::json!({
+                        "ncode": ncode,
+                        "growth": Growth::from_series(snapshots, window),
+                        "snapshots": snapshots,
+                    })
+                })
+                .collect();
+            writeln!(out, "{}", serde_json::to_string_pretty(&json_output)?)?;
+        }
+        "csv" => {
+            let all: Vec<MetricSnapshot> = series.into_iter().flat_map(|(_, snapshots)| snapshots).collect();
+            write_metrics_csv(&all, out)?;
+        }
+        _ => {
+            let rate = |v: Option<f64>| v.map(|v| format!("{:+.1}", v)).unwrap_or_else(|| "-".to_string());
+            writeln!(out, "{:<10} {:>10} {:>8} {:>12} {:>14} {:>8}", "ncode", "points", "records", "points/day", "bookmarks/week", "days")?;
+            for (ncode, snapshots) in &series {
+                let latest = snapshots.last();
+                let growth = Growth::from_series(snapshots, window);
+                writeln!(
+                    out,
+                    "{:<10} {:>10} {:>8} {:>12} {:>14} {:>8}",
+                    ncode,
+                    latest.and_then(|s| s.global_point).map(|p| p.to_string()).unwrap_or_else(|| "-".to_string()),
+                    snapshots.len(),
+                    rate(growth.as_ref().and_then(|g| g.points_per_day)),
+                    rate(growth.as_ref().and_then(|g| g.bookmarks_per_week)),
+                    growth.as_ref().map(|g| format!("{:.1}", g.days)).unwrap_or_else(|| "-".to_string()),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
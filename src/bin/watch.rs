@@ -0,0 +1,105 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use std::path::PathBuf;
+use std::time::Duration;
+use web_novel_scraper::tracker::read_watchlist;
+use web_novel_scraper::{HtmlFetcher, Library, RequestDelayConfig, RssSink, StdoutSink, Watcher, WebhookSink};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Poll followed novels for new episodes and emit notifications", long_about = None)]
+struct Args {
+    /// Watchlist file (one ncode per line, # starts a comment)
+    #[arg(short, long)]
+    watchlist: Option<PathBuf>,
+
+    /// Novel codes to follow (in addition to the watchlist)
+    #[arg(short, long)]
+    ncode: Vec<String>,
+
+    /// File that remembers which episodes were already announced
+    #[arg(long, default_value = "./output/watch_state.json")]
+    state: PathBuf,
+
+    /// Seconds between polls
+    #[arg(long, default_value_t = 1800)]
+    interval: u64,
+
+    /// Poll once and exit
+    #[arg(long)]
+    once: bool,
+
+    /// Print events as JSON lines (default when no other sink is given)
+    #[arg(long)]
+    stdout: bool,
+
+    /// POST each event as JSON to this URL (repeatable)
+    #[arg(long)]
+    webhook: Vec<String>,
+
+    /// Keep an RSS file of recent events
+    #[arg(long)]
+    rss: Option<PathBuf>,
+
+    /// Store fetched episodes in this SQLite library
+    #[arg(long)]
+    library: Option<PathBuf>,
+
+    /// Use Nocturne site and API (R18) instead of regular Narou
+    #[arg(long)]
+    nocturne: bool,
+
+    /// Minimum delay between requests in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    min_delay: u64,
+
+    /// Maximum delay between requests in milliseconds
+    #[arg(long, default_value_t = 3000)]
+    max_delay: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let mut ncodes = match &args.watchlist {
+        Some(path) => read_watchlist(path)?,
+        None => Vec::new(),
+    };
+    for ncode in &args.ncode {
+        let ncode = ncode.to_lowercase();
+        if !ncodes.contains(&ncode) {
+            ncodes.push(ncode);
+        }
+    }
+    if ncodes.is_empty() {
+        bail!("No novels to watch: pass --watchlist or --ncode");
+    }
+
+    let fetcher = HtmlFetcher::default();
+    fetcher.set_delay_config(RequestDelayConfig::new(args.min_delay, args.max_delay));
+
+    let mut watcher = Watcher::new(fetcher, args.nocturne, &args.state)?;
+    if let Some(path) = &args.library {
+        watcher = watcher.library(Library::open(path)?);
+    }
+    for url in &args.webhook {
+        watcher = watcher.sink(Box::new(WebhookSink::new(url)));
+    }
+    if let Some(path) = &args.rss {
+        watcher = watcher.sink(Box::new(RssSink::open(path, "なろう 更新通知")?));
+    }
+    if args.stdout || (args.webhook.is_empty() && args.rss.is_none()) {
+        watcher = watcher.sink(Box::new(StdoutSink));
+    }
+
+    // 進捗は標準エラーへ（標準出力はイベント専用）
+    eprintln!("👀 Watching {} novels", ncodes.len());
+    if args.once {
+        let events = watcher.poll(&ncodes).await?;
+        eprintln!("✅ {} new episodes", events.len());
+    } else {
+        watcher.run(&ncodes, Duration::from_secs(args.interval)).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use crate::page_classifier::check_page;
+use anyhow::{Context, Result};
+use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
+
+/// ブックマーク一覧の1作品
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BookmarkEntry {
+    /// 小説のncode（小文字）
+    pub ncode: String,
+    pub title: String,
+    /// シリーズのコード（`s1765j`など、シリーズに属する場合）
+    pub series_code: Option<String>,
+    pub series_title: Option<String>,
+    /// 短編かどうか
+    pub is_short: bool,
+    /// 完結済みかどうか
+    pub completed: bool,
+    /// エピソード数（短編は1）
+    pub episode_count: u32,
+    /// ジャンル（`ハイファンタジー[ファンタジー]`、ノクターンは掲載サイト名）
+    pub genre: Option<String>,
+    /// 最終更新日（`2025年08月10日`）
+    pub last_updated: Option<String>,
+}
+
+/// ブックマークのカテゴリ
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BookmarkCategory {
+    pub number: u32,
+    pub name: String,
+    pub count: u32,
+}
+
+/// なろう・ノクターンのブックマーク一覧ページのスクレイパー
+pub struct NarouBookmarkScraper {
+    fetcher: crate::HtmlFetcher,
+    is_nocturne: bool,
+}
+
+impl NarouBookmarkScraper {
+    /// なろうのブックマーク（ユーザーIDで指定）
+    pub fn new(fetcher: crate::HtmlFetcher) -> Self {
+        Self {
+            fetcher,
+            is_nocturne: false,
+        }
+    }
+
+    /// ノクターン系のXブックマーク（XIDで指定）
+    pub fn new_nocturne(fetcher: crate::HtmlFetcher) -> Self {
+        Self {
+            fetcher,
+            is_nocturne: true,
+        }
+    }
+
+    /// ブックマーク一覧のURL（`user`はなろうならユーザーID、ノクターンならXID）
+    pub fn build_list_url(&self, user: &str, category: u32, page: u32) -> String {
+        if self.is_nocturne {
+            format!(
+                "https://xmypage.syosetu.com/mypagefavnovelmain18/list/xid/{}/?nowcategory={}&p={}",
+                user, category, page
+            )
+        } else {
+            format!(
+                "https://mypage.syosetu.com/mypagefavnovelmain/list/userid/{}/index.php?nowcategory={}&p={}",
+                user, category, page
+            )
+        }
+    }
+
+    /// カテゴリ一覧（1ページ目のサイドバーから取得）
+    pub async fn fetch_categories(&self, user: &str) -> Result<Vec<BookmarkCategory>> {
+        let url = self.build_list_url(user, 1, 1);
+        let html = self.fetcher.fetch(&url).await.with_context(|| format!("Failed to fetch {}", url))?;
+        check_page(&html, &url)?;
+        Ok(Self::parse_categories(&html))
+    }
+
+    /// 指定カテゴリのブックマークを全ページ取得
+    pub async fn fetch_category(&self, user: &str, category: u32) -> Result<Vec<BookmarkEntry>> {
+        let mut all_entries = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let url = self.build_list_url(user, category, page);
+            eprintln!("Fetching bookmarks page {}: {}", page, url);
+
+            let html = self.fetcher.fetch(&url).await
+                .with_context(|| format!("Failed to fetch bookmarks page {}", page))?;
+            check_page(&html, &url)?;
+
+            let entries = Self::parse_bookmark_page(&html);
+            if entries.is_empty() {
+                break;
+            }
+            all_entries.extend(entries);
+
+            if !Self::has_next_page(&html) {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(all_entries)
+    }
+
+    /// 全カテゴリのブックマーク（カテゴリ番号と組にして返す）
+    pub async fn fetch_all_bookmarks(&self, user: &str) -> Result<Vec<(u32, BookmarkEntry)>> {
+        let mut all_entries = Vec::new();
+        for category in self.fetch_categories(user).await? {
+            if category.count == 0 {
+                continue;
+            }
+            for entry in self.fetch_category(user, category.number).await? {
+                all_entries.push((category.number, entry));
+            }
+        }
+        Ok(all_entries)
+    }
+
+    /// 一覧ページからブックマークを抽出
+    pub fn parse_bookmark_page(html: &str) -> Vec<BookmarkEntry> {
+        let document = Html::parse_document(html);
+        let item_selector = Selector::parse(".c-novel-list__item").unwrap();
+        let title_selector = Selector::parse("a.c-novel-list__title").unwrap();
+        let series_selector = Selector::parse("a.c-novel-list__series").unwrap();
+        let short_selector = Selector::parse(".c-label--short").unwrap();
+        let complete_selector = Selector::parse(".c-novel-list__complete").unwrap();
+        let number_selector = Selector::parse(".c-novel-list__number").unwrap();
+        let genre_selector = Selector::parse(".c-novel-list__genre").unwrap();
+        let lastup_selector = Selector::parse(".c-novel-list__lastup").unwrap();
+
+        document
+            .select(&item_selector)
+            .filter_map(|item| {
+                let title_link = item.select(&title_selector).next()?;
+                let ncode = code_from_url(title_link.value().attr("href")?)?;
+                let series = item.select(&series_selector).next();
+                let is_short = item.select(&short_selector).next().is_some();
+                let episode_count = item
+                    .select(&number_selector)
+                    .next()
+                    .and_then(|e| e.text().next())
+                    .and_then(|t| t.trim().parse().ok())
+                    .unwrap_or(1);
+
+                Some(BookmarkEntry {
+                    ncode,
+                    title: element_text(title_link),
+                    series_code: series.and_then(|s| s.value().attr("href")).and_then(code_from_url),
+                    series_title: series.map(element_text),
+                    is_short,
+                    completed: is_short || item.select(&complete_selector).next().is_some(),
+                    episode_count,
+                    genre: item.select(&genre_selector).next().map(element_text).filter(|g| !g.is_empty()),
+                    last_updated: item
+                        .select(&lastup_selector)
+                        .next()
+                        .map(element_text)
+                        .map(|t| t.trim_start_matches("最終更新日：").to_string()),
+                })
+            })
+            .collect()
+    }
+
+    /// サイドバーからカテゴリを抽出
+    pub fn parse_categories(html: &str) -> Vec<BookmarkCategory> {
+        let document = Html::parse_document(html);
+        let link_selector = Selector::parse(".c-side-list__item a").unwrap();
+        let count_selector = Selector::parse(".u-text-subtext").unwrap();
+
+        document
+            .select(&link_selector)
+            .filter_map(|link| {
+                let href = link.value().attr("href")?;
+                let number = href.split("nowcategory=").nth(1)?.split('&').next()?.parse().ok()?;
+                let count = link
+                    .select(&count_selector)
+                    .next()
+                    .and_then(|c| element_text(c).parse().ok())
+                    .unwrap_or(0);
+                let name = link
+                    .text()
+                    .next()
+                    .map(|t| t.trim().to_string())
+                    .unwrap_or_default();
+                Some(BookmarkCategory { number, name, count })
+            })
+            .collect()
+    }
+
+    /// 次のページへのリンクがあるか確認
+    pub(crate) fn has_next_page(html: &str) -> bool {
+        let document = Html::parse_document(html);
+        let next_selector = Selector::parse(r#".c-pager a[title="次へ"]"#).unwrap();
+        document.select(&next_selector).next().is_some()
+    }
+}
+
+/// `https://ncode.syosetu.com/n8383kn/`などからコード部分を取り出す
+pub(crate) fn code_from_url(url: &str) -> Option<String> {
+    let code = url.trim_end_matches('/').rsplit('/').next()?;
+    (!code.is_empty()).then(|| code.to_lowercase())
+}
+
+pub(crate) fn element_text(element: ElementRef) -> String {
+    element.text().collect::<String>().trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_bookmark_page() {
+        let html = fs::read_to_string("target_pages/narou/bookmarks/59791-1-1.html").unwrap();
+        let entries = NarouBookmarkScraper::parse_bookmark_page(&html);
+        assert_eq!(entries.len(), 20);
+        assert_eq!(
+            entries[0],
+            BookmarkEntry {
+                ncode: "n8383kn".to_string(),
+                title: "魔法大全（ウルオール図書館蔵）".to_string(),
+                series_code: Some("s1765j".to_string()),
+                series_title: Some("ウルオール図書館の蔵書".to_string()),
+                is_short: false,
+                completed: false,
+                episode_count: 9,
+                genre: Some("ハイファンタジー[ファンタジー]".to_string()),
+                last_updated: Some("2025年08月10日".to_string()),
+            }
+        );
+        assert!(NarouBookmarkScraper::has_next_page(&html));
+        assert_eq!(
+            NarouBookmarkScraper::parse_categories(&html),
+            vec![BookmarkCategory { number: 1, name: "カテゴリ1".to_string(), count: 55 }]
+        );
+
+        let last = fs::read_to_string("target_pages/narou/bookmarks/59791-1-3.html").unwrap();
+        assert_eq!(NarouBookmarkScraper::parse_bookmark_page(&last).len(), 15);
+        assert!(!NarouBookmarkScraper::has_next_page(&last));
+    }
+
+    #[test]
+    fn test_parse_nocturne_bookmark_page() {
+        let html = fs::read_to_string("target_pages/nocturne/bookmarks/x9487b-1-1.html").unwrap();
+        let entries = NarouBookmarkScraper::parse_bookmark_page(&html);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].ncode, "n1925bl");
+        assert!(entries[0].completed);
+        assert_eq!(entries[0].episode_count, 37);
+        assert_eq!(entries[0].genre.as_deref(), Some("ノクターンノベルズ(男性向け)"));
+        assert_eq!(NarouBookmarkScraper::parse_categories(&html)[0].number, 10);
+    }
+}
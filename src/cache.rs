@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// レスポンスキャッシュの設定
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheConfig {
+    /// キャッシュの有効期間
+    pub ttl: Duration,
+    /// ディスクキャッシュの保存先（Noneならメモリのみ）
+    pub dir: Option<PathBuf>,
+}
+
+impl CacheConfig {
+    /// メモリのみのキャッシュ
+    pub fn in_memory(ttl: Duration) -> Self {
+        Self { ttl, dir: None }
+    }
+
+    /// ディスクにも保存するキャッシュ（実行をまたいで再利用される）
+    pub fn on_disk(ttl: Duration, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            ttl,
+            dir: Some(dir.into()),
+        }
+    }
+}
+
+/// キャッシュされたレスポンス
+#[derive(Clone, Debug)]
+pub struct CachedResponse {
+    pub body: Vec<u8>,
+    pub content_type: Option<String>,
+    pub fetched_at: SystemTime,
+}
+
+/// ディスクキャッシュのメタデータ
+#[derive(Serialize, Deserialize)]
+struct CacheMeta {
+    url: String,
+    content_type: Option<String>,
+    fetched_at: u64,
+}
+
+/// URL単位のGETレスポンスキャッシュ
+pub struct ResponseCache {
+    config: CacheConfig,
+    memory: Mutex<HashMap<String, CachedResponse>>,
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            memory: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 有効期限内のレスポンスを取得
+    pub fn get(&self, url: &str) -> Option<CachedResponse> {
+        if let Some(entry) = self.memory.lock().unwrap().get(url) {
+            if self.is_fresh(entry.fetched_at) {
+                return Some(entry.clone());
+            }
+        }
+
+        let entry = self.read_disk(url).ok().flatten()?;
+        if !self.is_fresh(entry.fetched_at) {
+            return None;
+        }
+        self.memory.lock().unwrap().insert(url.to_string(), entry.clone());
+        Some(entry)
+    }
+
+    /// レスポンスを保存
+    pub fn put(&self, url: &str, body: &[u8], content_type: Option<&str>) -> Result<()> {
+        let entry = CachedResponse {
+            body: body.to_vec(),
+            content_type: content_type.map(String::from),
+            fetched_at: SystemTime::now(),
+        };
+        self.write_disk(url, &entry)?;
+        self.memory.lock().unwrap().insert(url.to_string(), entry);
+        Ok(())
+    }
+
+    /// 指定したURLのレスポンスを破棄
+    pub fn remove(&self, url: &str) -> Result<()> {
+        self.memory.lock().unwrap().remove(url);
+        if let Some((meta_path, body_path)) = self.entry_paths(url) {
+            for path in [meta_path, body_path] {
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// キャッシュを全て破棄
+    pub fn clear(&self) -> Result<()> {
+        self.memory.lock().unwrap().clear();
+        if let Some(dir) = &self.config.dir {
+            if dir.exists() {
+                fs::remove_dir_all(dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_fresh(&self, fetched_at: SystemTime) -> bool {
+        fetched_at.elapsed().map(|age| age < self.config.ttl).unwrap_or(false)
+    }
+
+    fn entry_paths(&self, url: &str) -> Option<(PathBuf, PathBuf)> {
+        let dir = self.config.dir.as_ref()?;
+        let key: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Some((dir.join(format!("{}.json", key)), dir.join(format!("{}.body", key))))
+    }
+
+    fn read_disk(&self, url: &str) -> Result<Option<CachedResponse>> {
+        let Some((meta_path, body_path)) = self.entry_paths(url) else {
+            return Ok(None);
+        };
+        if !meta_path.exists() || !body_path.exists() {
+            return Ok(None);
+        }
+
+        let meta: CacheMeta = serde_json::from_str(&fs::read_to_string(&meta_path)?)?;
+        if meta.url != url {
+            return Ok(None);
+        }
+
+        Ok(Some(CachedResponse {
+            body: fs::read(&body_path)?,
+            content_type: meta.content_type,
+            fetched_at: UNIX_EPOCH + Duration::from_secs(meta.fetched_at),
+        }))
+    }
+
+    fn write_disk(&self, url: &str, entry: &CachedResponse) -> Result<()> {
+        let Some((meta_path, body_path)) = self.entry_paths(url) else {
+            return Ok(());
+        };
+        if let Some(dir) = meta_path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+        }
+
+        let meta = CacheMeta {
+            url: url.to_string(),
+            content_type: entry.content_type.clone(),
+            fetched_at: entry.fetched_at.duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+        fs::write(&body_path, &entry.body)?;
+        fs::write(&meta_path, serde_json::to_string(&meta)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_cache_ttl() {
+        let cache = ResponseCache::new(CacheConfig::in_memory(Duration::from_secs(60)));
+        cache.put("https://example.com/a", b"hello", Some("text/html")).unwrap();
+
+        let hit = cache.get("https://example.com/a").unwrap();
+        assert_eq!(hit.body, b"hello");
+        assert_eq!(hit.content_type.as_deref(), Some("text/html"));
+        assert!(cache.get("https://example.com/b").is_none());
+
+        let expired = ResponseCache::new(CacheConfig::in_memory(Duration::ZERO));
+        expired.put("https://example.com/a", b"hello", None).unwrap();
+        assert!(expired.get("https://example.com/a").is_none());
+    }
+
+    #[test]
+    fn test_disk_cache_survives_new_instance() {
+        let dir = std::env::temp_dir().join(format!("wns-cache-{}", std::process::id()));
+        let config = CacheConfig::on_disk(Duration::from_secs(60), &dir);
+
+        ResponseCache::new(config.clone())
+            .put("https://api.syosetu.com/novelapi/api/?ncode=n7775do", b"[]", None)
+            .unwrap();
+
+        let reopened = ResponseCache::new(config);
+        let hit = reopened.get("https://api.syosetu.com/novelapi/api/?ncode=n7775do").unwrap();
+        assert_eq!(hit.body, b"[]");
+
+        reopened.clear().unwrap();
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,310 @@
+use crate::cache::CacheConfig;
+use crate::fetcher::{FetcherConfig, HtmlFetcher, RequestDelayConfig, UserAgentMode};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 設定ファイルの既定の場所（`$XDG_CONFIG_HOME`、なければ`~/.config`の下）
+pub const CONFIG_FILE: &str = "web-novel-scraper/config.toml";
+
+/// プロファイルでリクエスト間隔を指定しない場合の値
+pub const DEFAULT_MIN_DELAY: u64 = 1000;
+pub const DEFAULT_MAX_DELAY: u64 = 3000;
+/// キャッシュの有効期間の既定値（秒）
+pub const DEFAULT_CACHE_TTL: u64 = 3600;
+
+/// User-Agentの選び方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserAgentSetting {
+    /// リクエストごとにランダム
+    Random,
+    /// 起動時にランダムに1つ選んで使い続ける
+    Fixed,
+    /// `user_agent`で指定したもの
+    Custom,
+}
+
+/// 名前付きのプロファイル
+///
+/// 指定のない項目は`[defaults]`、コマンドラインの順に上書きされる。
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub min_delay: Option<u64>,
+    pub max_delay: Option<u64>,
+    pub ua_mode: Option<UserAgentSetting>,
+    pub user_agent: Option<String>,
+    pub proxies: Option<Vec<String>>,
+    /// 起動時に読み込むCookieファイル（Netscape形式またはJSON）
+    pub cookie_file: Option<PathBuf>,
+    pub cache_dir: Option<PathBuf>,
+    /// キャッシュの有効期間（秒）
+    pub cache_ttl: Option<u64>,
+    /// ダウンロードの保存先
+    pub archive: Option<PathBuf>,
+    /// SQLiteのライブラリ
+    pub library: Option<PathBuf>,
+    /// 既定の保存形式（`html`・`epub`・`text`・`aozora`・`markdown`・`clean-html`）
+    pub export_formats: Option<Vec<String>>,
+    /// ノクターン系のサイトとAPIを使う
+    pub nocturne: Option<bool>,
+}
+
+impl Profile {
+    /// `over`で指定のある項目を上書きしたプロファイル
+    pub fn merge(&self, over: &Profile) -> Profile {
+        Profile {
+            min_delay: over.min_delay.or(self.min_delay),
+            max_delay: over.max_delay.or(self.max_delay),
+            ua_mode: over.ua_mode.or(self.ua_mode),
+            user_agent: over.user_agent.clone().or_else(|| self.user_agent.clone()),
+            proxies: over.proxies.clone().or_else(|| self.proxies.clone()),
+            cookie_file: over.cookie_file.clone().or_else(|| self.cookie_file.clone()),
+            cache_dir: over.cache_dir.clone().or_else(|| self.cache_dir.clone()),
+            cache_ttl: over.cache_ttl.or(self.cache_ttl),
+            archive: over.archive.clone().or_else(|| self.archive.clone()),
+            library: over.library.clone().or_else(|| self.library.clone()),
+            export_formats: over.export_formats.clone().or_else(|| self.export_formats.clone()),
+            nocturne: over.nocturne.or(self.nocturne),
+        }
+    }
+
+    pub fn nocturne(&self) -> bool {
+        self.nocturne.unwrap_or(false)
+    }
+
+    /// リクエスト間隔（ミリ秒）の最小と最大（最小が最大を超える組み合わせはエラー）
+    pub fn delay_range(&self) -> Result<(u64, u64)> {
+        let min = self.min_delay.unwrap_or(DEFAULT_MIN_DELAY);
+        let max = match self.max_delay {
+            Some(max) => max,
+            None => DEFAULT_MAX_DELAY.max(min),
+        };
+        if min > max {
+            bail!("min_delay ({}) must not exceed max_delay ({})", min, max);
+        }
+        Ok((min, max))
+    }
+
+    pub fn delay_config(&self) -> Result<RequestDelayConfig> {
+        let (min, max) = self.delay_range()?;
+        Ok(RequestDelayConfig::new(min, max))
+    }
+
+    /// プロファイルの設定でフェッチャーを作成（Cookieファイルがあれば読み込む）
+    pub fn fetcher(&self) -> Result<HtmlFetcher> {
+        self.fetcher_with(FetcherConfig::new())
+    }
+
+    /// タイムアウトなどを指定した`config`にプロファイルの設定を重ねてフェッチャーを作成
+    pub fn fetcher_with(&self, config: FetcherConfig) -> Result<HtmlFetcher> {
+        // ログインしないまま巡回してしまわないよう、指定したクッキーファイルがなければエラー
+        if let Some(path) = self.cookie_file.as_ref().filter(|path| !path.exists()) {
+            bail!("Cookie file {} does not exist", path.display());
+        }
+
+        let mut config = config.delay(self.delay_config()?);
+        if let Some(proxies) = &self.proxies {
+            config = config.proxies(proxies.iter().cloned());
+        }
+        if let Some(dir) = &self.cache_dir {
+            let ttl = Duration::from_secs(self.cache_ttl.unwrap_or(DEFAULT_CACHE_TTL));
+            config = config.cache(CacheConfig::on_disk(ttl, dir));
+        }
+
+        let fetcher = match self.ua_mode.unwrap_or(UserAgentSetting::Random) {
+            UserAgentSetting::Random => HtmlFetcher::from_config(config.user_agent_mode(UserAgentMode::RandomEveryRequest))?,
+            UserAgentSetting::Fixed => {
+                let fetcher = HtmlFetcher::from_config(config.user_agent_mode(UserAgentMode::Fixed(None)))?;
+                fetcher.set_user_agent_from_random();
+                fetcher
+            }
+            UserAgentSetting::Custom => {
+                let Some(user_agent) = self.user_agent.clone() else {
+                    bail!("ua_mode \"custom\" requires user_agent");
+                };
+                HtmlFetcher::from_config(config.user_agent_mode(UserAgentMode::Fixed(Some(user_agent))))?
+            }
+        };
+
+        if let Some(path) = &self.cookie_file {
+            fetcher.load_cookies(path)?;
+        }
+        Ok(fetcher)
+    }
+
+    /// `~/`で始まるパスをホームディレクトリに展開
+    fn expand_paths(&mut self) {
+        for path in [&mut self.cookie_file, &mut self.cache_dir, &mut self.archive, &mut self.library]
+            .into_iter()
+            .flatten()
+        {
+            *path = expand_home(path);
+        }
+    }
+}
+
+/// CLIの設定ファイル
+///
+/// ```toml
+/// default_profile = "polite-crawl"
+///
+/// [defaults]
+/// archive = "~/novels"
+///
+/// [profiles.polite-crawl]
+/// min_delay = 3000
+/// max_delay = 8000
+/// ua_mode = "fixed"
+///
+/// [profiles.nocturne-archive]
+/// nocturne = true
+/// library = "~/novels/nocturne.db"
+/// export_formats = ["epub", "text"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `--profile`を指定しない場合に使うプロファイル
+    pub default_profile: Option<String>,
+    /// すべてのプロファイルに共通の設定
+    pub defaults: Profile,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config: Config = toml::from_str(text)?;
+        config.defaults.expand_paths();
+        for profile in config.profiles.values_mut() {
+            profile.expand_paths();
+        }
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read config {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    /// 既定の場所の設定ファイル
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join(CONFIG_FILE))
+    }
+
+    /// `path`、なければ既定の場所から読む（既定の場所にファイルがなければ空の設定）
+    pub fn load_or_default(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None => match Self::default_path().filter(|p| p.exists()) {
+                Some(path) => Self::load(&path),
+                None => Ok(Self::default()),
+            },
+        }
+    }
+
+    /// `[defaults]`に指定のプロファイル（`None`なら`default_profile`）を重ねた設定
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        let Some(name) = name.or(self.default_profile.as_deref()) else {
+            return Ok(self.defaults.clone());
+        };
+        match self.profiles.get(name) {
+            Some(profile) => Ok(self.defaults.merge(profile)),
+            None => {
+                let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                bail!("Unknown profile \"{}\" (available: {})", name, known.join(", "))
+            }
+        }
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+default_profile = "polite-crawl"
+
+[defaults]
+archive = "/srv/novels"
+export_formats = ["html"]
+
+[profiles.polite-crawl]
+min_delay = 3000
+max_delay = 8000
+ua_mode = "fixed"
+
+[profiles.nocturne-archive]
+nocturne = true
+proxies = ["socks5://127.0.0.1:9050"]
+library = "/srv/novels/nocturne.db"
+export_formats = ["epub", "text"]
+"#;
+
+    #[test]
+    fn test_profiles() {
+        let config = Config::parse(CONFIG).unwrap();
+
+        let polite = config.profile(None).unwrap();
+        assert_eq!(polite.min_delay, Some(3000));
+        assert_eq!(polite.ua_mode, Some(UserAgentSetting::Fixed));
+        assert_eq!(polite.archive, Some(PathBuf::from("/srv/novels")));
+        assert!(!polite.nocturne());
+
+        let archive = config.profile(Some("nocturne-archive")).unwrap();
+        assert!(archive.nocturne());
+        assert_eq!(archive.export_formats, Some(vec!["epub".to_string(), "text".to_string()]));
+        assert_eq!(archive.archive, Some(PathBuf::from("/srv/novels")));
+        assert_eq!(archive.min_delay, None);
+
+        assert!(config.profile(Some("missing")).is_err());
+        assert!(Config::parse("[profiles.x]\nmin_dealy = 1").is_err());
+    }
+
+    #[test]
+    fn test_command_line_overrides() {
+        let config = Config::parse(CONFIG).unwrap();
+        let cli = Profile {
+            max_delay: Some(5000),
+            nocturne: Some(true),
+            ..Profile::default()
+        };
+        let profile = config.profile(None).unwrap().merge(&cli);
+        assert_eq!((profile.min_delay, profile.max_delay), (Some(3000), Some(5000)));
+        assert!(profile.nocturne());
+
+        assert_eq!(profile.delay_range().unwrap(), (3000, 5000));
+        // 最小だけ既定値より大きくした場合は最大も合わせる
+        assert_eq!(Profile { min_delay: Some(5000), ..Profile::default() }.delay_range().unwrap(), (5000, 5000));
+        assert!(Profile { min_delay: Some(5000), max_delay: Some(1000), ..Profile::default() }.delay_range().is_err());
+    }
+
+    #[test]
+    fn test_missing_cookie_file() {
+        let profile = Profile {
+            ua_mode: Some(UserAgentSetting::Custom),
+            user_agent: Some("TestBot/1.0".to_string()),
+            cookie_file: Some(PathBuf::from("/nonexistent/cookies.txt")),
+            ..Profile::default()
+        };
+        let Err(err) = profile.fetcher() else {
+            panic!("a missing cookie file should be an error");
+        };
+        assert!(err.to_string().contains("/nonexistent/cookies.txt"));
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use cookie_store::{CookieDomain, CookieExpiration};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Netscape形式のファイルでHttpOnlyクッキーに付くプレフィックス
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// ファイルへの保存・読み込みに使うクッキー表現
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredCookie {
+    /// ドメイン（先頭の`.`は付けない）
+    pub domain: String,
+    /// サブドメインにも送信するか（Domain属性付きのクッキー）
+    pub include_subdomains: bool,
+    pub path: String,
+    pub secure: bool,
+    pub http_only: bool,
+    /// 有効期限（UNIX秒）。セッションクッキーの場合はNone
+    pub expires: Option<i64>,
+    pub name: String,
+    pub value: String,
+}
+
+/// クッキーファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieFileFormat {
+    /// curl/wget互換のcookies.txt
+    Netscape,
+    /// `StoredCookie`の配列をJSONで保存
+    Json,
+}
+
+impl CookieFileFormat {
+    /// 拡張子から形式を判定（`.json`以外はNetscape形式）
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Netscape,
+        }
+    }
+}
+
+impl StoredCookie {
+    /// クッキーストア内のクッキーから変換
+    pub(crate) fn from_store(cookie: &cookie_store::Cookie<'_>) -> Option<Self> {
+        let (domain, include_subdomains) = match &cookie.domain {
+            CookieDomain::HostOnly(host) => (host.clone(), false),
+            CookieDomain::Suffix(suffix) => (suffix.clone(), true),
+            CookieDomain::NotPresent | CookieDomain::Empty => return None,
+        };
+        let expires = match cookie.expires {
+            CookieExpiration::AtUtc(at) => Some(at.unix_timestamp()),
+            CookieExpiration::SessionEnd => None,
+        };
+
+        Some(Self {
+            domain,
+            include_subdomains,
+            path: String::from(&cookie.path),
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            expires,
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+        })
+    }
+
+    /// ストアへ登録する際の送信元URL
+    pub(crate) fn origin_url(&self) -> Result<Url> {
+        let url = format!("https://{}{}", self.domain, self.path);
+        url.parse::<Url>()
+            .with_context(|| format!("Invalid cookie domain/path: {}", url))
+    }
+
+    /// Set-Cookieヘッダー相当の文字列に変換
+    pub(crate) fn to_set_cookie_string(&self) -> String {
+        let mut s = format!("{}={}; Path={}", self.name, self.value, self.path);
+        if self.include_subdomains {
+            s.push_str(&format!("; Domain={}", self.domain));
+        }
+        if let Some(expires) = self.expires.and_then(|ts| DateTime::<Utc>::from_timestamp(ts, 0)) {
+            s.push_str(&format!("; Expires={}", expires.format("%a, %d %b %Y %H:%M:%S GMT")));
+        }
+        if self.secure {
+            s.push_str("; Secure");
+        }
+        if self.http_only {
+            s.push_str("; HttpOnly");
+        }
+        s
+    }
+}
+
+/// Netscape形式（cookies.txt）のテキストを解析
+pub fn parse_netscape(text: &str) -> Result<Vec<StoredCookie>> {
+    let mut cookies = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            anyhow::bail!("Invalid cookies.txt line {}: expected 7 tab-separated fields", i + 1);
+        }
+
+        let expires = fields[4].parse::<i64>()
+            .with_context(|| format!("Invalid expiry on cookies.txt line {}", i + 1))?;
+
+        cookies.push(StoredCookie {
+            domain: fields[0].trim_start_matches('.').to_string(),
+            include_subdomains: fields[1].eq_ignore_ascii_case("TRUE"),
+            path: fields[2].to_string(),
+            secure: fields[3].eq_ignore_ascii_case("TRUE"),
+            http_only,
+            expires: if expires == 0 { None } else { Some(expires) },
+            name: fields[5].to_string(),
+            value: fields[6].to_string(),
+        });
+    }
+
+    Ok(cookies)
+}
+
+/// Netscape形式（cookies.txt）のテキストに変換
+pub fn to_netscape(cookies: &[StoredCookie]) -> String {
+    let mut out = String::from("# Netscape HTTP Cookie File\n");
+
+    for cookie in cookies {
+        let bool_str = |b: bool| if b { "TRUE" } else { "FALSE" };
+        let domain = if cookie.include_subdomains {
+            format!(".{}", cookie.domain)
+        } else {
+            cookie.domain.clone()
+        };
+        out.push_str(&format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            if cookie.http_only { HTTP_ONLY_PREFIX } else { "" },
+            domain,
+            bool_str(cookie.include_subdomains),
+            cookie.path,
+            bool_str(cookie.secure),
+            cookie.expires.unwrap_or(0),
+            cookie.name,
+            cookie.value,
+        ));
+    }
+
+    out
+}
+
+/// クッキーファイルを読み込む
+pub fn load_cookie_file(path: &Path, format: CookieFileFormat) -> Result<Vec<StoredCookie>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read cookie file {}", path.display()))?;
+
+    match format {
+        CookieFileFormat::Netscape => parse_netscape(&text),
+        CookieFileFormat::Json => serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse cookie file {}", path.display())),
+    }
+}
+
+/// クッキーファイルへ書き出す
+pub fn save_cookie_file(path: &Path, format: CookieFileFormat, cookies: &[StoredCookie]) -> Result<()> {
+    let text = match format {
+        CookieFileFormat::Netscape => to_netscape(cookies),
+        CookieFileFormat::Json => serde_json::to_string_pretty(cookies)?,
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, text)
+        .with_context(|| format!("Failed to write cookie file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# Netscape HTTP Cookie File\n\
+        .syosetu.com\tTRUE\t/\tFALSE\t0\tover18\tyes\n\
+        #HttpOnly_ssl.syosetu.com\tFALSE\t/login/\tTRUE\t1893456000\tuserl\tabc123\n";
+
+    #[test]
+    fn test_parse_netscape() {
+        let cookies = parse_netscape(SAMPLE).unwrap();
+        assert_eq!(cookies.len(), 2);
+
+        assert_eq!(cookies[0].domain, "syosetu.com");
+        assert!(cookies[0].include_subdomains);
+        assert_eq!(cookies[0].expires, None);
+        assert_eq!(cookies[0].name, "over18");
+
+        assert_eq!(cookies[1].domain, "ssl.syosetu.com");
+        assert!(cookies[1].http_only);
+        assert!(cookies[1].secure);
+        assert_eq!(cookies[1].path, "/login/");
+        assert_eq!(cookies[1].expires, Some(1893456000));
+    }
+
+    #[test]
+    fn test_netscape_round_trip() {
+        let cookies = parse_netscape(SAMPLE).unwrap();
+        let reparsed = parse_netscape(&to_netscape(&cookies)).unwrap();
+        assert_eq!(cookies, reparsed);
+    }
+
+    #[test]
+    fn test_parse_netscape_invalid_line() {
+        assert!(parse_netscape("syosetu.com\tTRUE\t/\n").is_err());
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(CookieFileFormat::from_path(Path::new("cookies.json")), CookieFileFormat::Json);
+        assert_eq!(CookieFileFormat::from_path(Path::new("cookies.txt")), CookieFileFormat::Netscape);
+    }
+}
@@ -0,0 +1,539 @@
+use super::{episode_title, escape_xml, paragraphs_xhtml};
+use crate::api::endpoints::narou::NarouNovelInfo;
+use crate::api::endpoints::nocturne::NocturneNovelInfo;
+use crate::library::NovelRecord;
+use crate::novel_parser::{NovelIndex, ParsedEpisode};
+use crate::novel_scraper::{Illustration, NovelContent};
+use crate::ruby::RubyMode;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// EPUBの書誌情報
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpubMetadata {
+    /// dc:identifier（既定は`urn:narou:{ncode}`）
+    pub identifier: String,
+    pub title: String,
+    pub author: String,
+    /// あらすじ
+    pub synopsis: String,
+    pub keywords: Vec<String>,
+    /// 作品ページのURL
+    pub source_url: Option<String>,
+    pub language: String,
+}
+
+impl EpubMetadata {
+    pub fn new(ncode: &str, title: impl Into<String>) -> Self {
+        Self {
+            identifier: format!("urn:narou:{}", ncode.to_lowercase()),
+            title: title.into(),
+            author: String::new(),
+            synopsis: String::new(),
+            keywords: Vec::new(),
+            source_url: None,
+            language: "ja".to_string(),
+        }
+    }
+
+    /// 目次ページの情報から作成（APIが使えない場合の代替）
+    pub fn from_index(ncode: &str, index: &NovelIndex) -> Self {
+        Self {
+            author: index.author.clone(),
+            synopsis: index.summary.clone(),
+            ..Self::new(ncode, index.title.clone())
+        }
+    }
+
+    /// なろう小説APIの情報から作成
+    pub fn from_narou_info(ncode: &str, info: &NarouNovelInfo) -> Self {
+        Self {
+            author: info.writer.clone().unwrap_or_default(),
+            synopsis: info.story.clone().unwrap_or_default(),
+            keywords: split_keywords(info.keyword.as_deref()),
+            source_url: Some(format!("https://ncode.syosetu.com/{}/", ncode.to_lowercase())),
+            ..Self::new(ncode, info.title.clone().unwrap_or_default())
+        }
+    }
+
+    /// なろう小説API（R18）の情報から作成
+    pub fn from_nocturne_info(ncode: &str, info: &NocturneNovelInfo) -> Self {
+        Self {
+            author: info.writer.clone().unwrap_or_default(),
+            synopsis: info.story.clone().unwrap_or_default(),
+            keywords: split_keywords(info.keyword.as_deref()),
+            source_url: Some(format!("https://novel18.syosetu.com/{}/", ncode.to_lowercase())),
+            ..Self::new(ncode, info.title.clone().unwrap_or_default())
+        }
+    }
+
+    /// ライブラリに保存したAPIの情報から作成
+    pub fn from_novel_record(record: &NovelRecord) -> Self {
+        let ncode = &record.ncode;
+        let metadata = if record.is_nocturne {
+            serde_json::from_value::<NocturneNovelInfo>(record.info.clone())
+                .map(|info| Self::from_nocturne_info(ncode, &info))
+        } else {
+            serde_json::from_value::<NarouNovelInfo>(record.info.clone())
+                .map(|info| Self::from_narou_info(ncode, &info))
+        };
+        metadata.unwrap_or_else(|_| Self::new(ncode, record.title.clone().unwrap_or_else(|| ncode.clone())))
+    }
+}
+
+/// EPUBの出力オプション
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpubOptions {
+    /// 縦書き（`writing-mode: vertical-rl`・右から左へのページ送り）
+    pub vertical: bool,
+    /// 前書きを含める
+    pub include_preface: bool,
+    /// 後書きを含める
+    pub include_afterword: bool,
+    /// ルビの出力方法
+    pub ruby: RubyMode,
+}
+
+impl Default for EpubOptions {
+    fn default() -> Self {
+        Self {
+            vertical: false,
+            include_preface: true,
+            include_afterword: true,
+            ruby: RubyMode::Keep,
+        }
+    }
+}
+
+/// 1話分のXHTMLファイル
+struct EpubChapter {
+    file_name: String,
+    title: String,
+    /// この話から始まる章のタイトル
+    section: Option<String>,
+}
+
+/// `NovelContent`からEPUB 3を作成
+pub struct EpubExporter {
+    metadata: EpubMetadata,
+    options: EpubOptions,
+    index: Option<NovelIndex>,
+    illustrations: Vec<Illustration>,
+}
+
+impl EpubExporter {
+    pub fn new(metadata: EpubMetadata) -> Self {
+        Self {
+            metadata,
+            options: EpubOptions::default(),
+            index: None,
+            illustrations: Vec::new(),
+        }
+    }
+
+    pub fn options(mut self, options: EpubOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// 目次を設定（章立てとサブタイトルの補完に使う）
+    pub fn index(mut self, index: NovelIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// 埋め込む挿絵を設定（本文の`<img>`のURLと照合される）
+    pub fn illustrations(mut self, illustrations: Vec<Illustration>) -> Self {
+        self.illustrations = illustrations;
+        self
+    }
+
+    /// EPUBファイルのバイト列を作成
+    pub fn build(&self, novel: &NovelContent) -> Result<Vec<u8>> {
+        let image_paths: HashMap<&str, String> = self
+            .illustrations
+            .iter()
+            .enumerate()
+            .map(|(i, ill)| (ill.url.as_str(), format!("images/img{:03}.{}", i + 1, ill.extension())))
+            .collect();
+        let resolve_image = |url: &str| image_paths.get(url).map(|p| format!("../{}", p));
+
+        let mut chapters = Vec::new();
+        let mut pages = Vec::new();
+        for episode in &novel.episodes {
+            let parsed = ParsedEpisode::parse(episode);
+            let chapter = EpubChapter {
+                file_name: format!("text/ep{:04}.xhtml", episode.episode_number),
+                title: episode_title(&parsed, self.index.as_ref()),
+                section: self.section_starting_at(episode.episode_number),
+            };
+            pages.push(self.episode_xhtml(&parsed, &chapter, &resolve_image));
+            chapters.push(chapter);
+        }
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        // mimetypeは先頭に無圧縮で置く必要がある
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(b"application/epub+zip")?;
+
+        let mut add = |name: &str, data: &[u8]| -> Result<()> {
+            zip.start_file(name, deflated)?;
+            zip.write_all(data)?;
+            Ok(())
+        };
+        add("META-INF/container.xml", CONTAINER_XML.as_bytes())?;
+        add("OEBPS/content.opf", self.content_opf(&chapters, &image_paths).as_bytes())?;
+        add("OEBPS/nav.xhtml", self.nav_xhtml(&chapters).as_bytes())?;
+        add("OEBPS/style.css", self.stylesheet().as_bytes())?;
+        add("OEBPS/title.xhtml", self.title_xhtml().as_bytes())?;
+        for (chapter, page) in chapters.iter().zip(&pages) {
+            add(&format!("OEBPS/{}", chapter.file_name), page.as_bytes())?;
+        }
+        for illustration in &self.illustrations {
+            add(&format!("OEBPS/{}", image_paths[illustration.url.as_str()]), &illustration.data)?;
+        }
+
+        Ok(zip.finish()?.into_inner())
+    }
+
+    /// EPUBファイルを書き出す
+    pub fn write_to(&self, novel: &NovelContent, path: &Path) -> Result<()> {
+        let data = self.build(novel)?;
+        fs::write(path, data).with_context(|| format!("Failed to write EPUB {}", path.display()))
+    }
+
+    fn section_starting_at(&self, episode_number: u32) -> Option<String> {
+        self.index.as_ref()?.chapter_starting_at(episode_number).map(String::from)
+    }
+
+    fn writing_mode_class(&self) -> &'static str {
+        if self.options.vertical { "vrtl" } else { "hltr" }
+    }
+
+    fn episode_xhtml(
+        &self,
+        parsed: &ParsedEpisode,
+        chapter: &EpubChapter,
+        resolve_image: &dyn Fn(&str) -> Option<String>,
+    ) -> String {
+        let mut body = String::new();
+        if let Some(section) = &chapter.section {
+            body.push_str(&format!("<h2 class=\"chapter\">{}</h2>\n", escape_xml(section)));
+        }
+        body.push_str(&format!("<h3 class=\"subtitle\">{}</h3>\n", escape_xml(&chapter.title)));
+
+        let sections = [
+            ("preface", &parsed.preface, self.options.include_preface),
+            ("honbun", &parsed.body, true),
+            ("afterword", &parsed.afterword, self.options.include_afterword),
+        ];
+        for (class, paragraphs, included) in sections {
+            if included && !paragraphs.is_empty() {
+                body.push_str(&format!("<div class=\"{}\">\n", class));
+                body.push_str(&paragraphs_xhtml(paragraphs, self.options.ruby, resolve_image));
+                body.push_str("</div>\n");
+            }
+        }
+
+        xhtml_document(&chapter.title, &self.metadata.language, self.writing_mode_class(), "../style.css", &body)
+    }
+
+    fn title_xhtml(&self) -> String {
+        let mut body = format!("<h1 class=\"title\">{}</h1>\n", escape_xml(&self.metadata.title));
+        if !self.metadata.author.is_empty() {
+            body.push_str(&format!("<p class=\"author\">{}</p>\n", escape_xml(&self.metadata.author)));
+        }
+        if !self.metadata.synopsis.is_empty() {
+            body.push_str("<div class=\"synopsis\">\n");
+            for line in self.metadata.synopsis.lines() {
+                if line.trim().is_empty() {
+                    body.push_str("<p><br/></p>\n");
+                } else {
+                    body.push_str(&format!("<p>{}</p>\n", escape_xml(line)));
+                }
+            }
+            body.push_str("</div>\n");
+        }
+        xhtml_document(&self.metadata.title, &self.metadata.language, self.writing_mode_class(), "style.css", &body)
+    }
+
+    fn nav_xhtml(&self, chapters: &[EpubChapter]) -> String {
+        let mut list = String::from("<ol>\n<li><a href=\"title.xhtml\">表紙</a></li>\n");
+        let mut in_section = false;
+
+        for chapter in chapters {
+            if let Some(section) = &chapter.section {
+                if in_section {
+                    list.push_str("</ol></li>\n");
+                }
+                list.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a><ol>\n",
+                    chapter.file_name,
+                    escape_xml(section)
+                ));
+                in_section = true;
+            }
+            list.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                chapter.file_name,
+                escape_xml(&chapter.title)
+            ));
+        }
+        if in_section {
+            list.push_str("</ol></li>\n");
+        }
+        list.push_str("</ol>\n");
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{lang}" lang="{lang}">
+<head>
+<meta charset="UTF-8"/>
+<title>目次</title>
+</head>
+<body>
+<nav epub:type="toc" id="toc">
+<h1>目次</h1>
+{list}</nav>
+</body>
+</html>
+"#,
+            lang = escape_xml(&self.metadata.language),
+            list = list,
+        )
+    }
+
+    fn content_opf(&self, chapters: &[EpubChapter], image_paths: &HashMap<&str, String>) -> String {
+        let meta = &self.metadata;
+        let mut metadata = format!(
+            "<dc:identifier id=\"bookid\">{}</dc:identifier>\n<dc:title>{}</dc:title>\n<dc:language>{}</dc:language>\n",
+            escape_xml(&meta.identifier),
+            escape_xml(&meta.title),
+            escape_xml(&meta.language),
+        );
+        if !meta.author.is_empty() {
+            metadata.push_str(&format!("<dc:creator>{}</dc:creator>\n", escape_xml(&meta.author)));
+        }
+        if !meta.synopsis.is_empty() {
+            metadata.push_str(&format!("<dc:description>{}</dc:description>\n", escape_xml(&meta.synopsis)));
+        }
+        for keyword in &meta.keywords {
+            metadata.push_str(&format!("<dc:subject>{}</dc:subject>\n", escape_xml(keyword)));
+        }
+        if let Some(url) = &meta.source_url {
+            metadata.push_str(&format!("<dc:source>{}</dc:source>\n", escape_xml(url)));
+        }
+        metadata.push_str(&format!(
+            "<meta property=\"dcterms:modified\">{}</meta>\n",
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+        ));
+
+        let mut manifest = String::from(
+            "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+             <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n\
+             <item id=\"title\" href=\"title.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+        );
+        let mut spine = String::from("<itemref idref=\"title\"/>\n");
+        for (i, chapter) in chapters.iter().enumerate() {
+            manifest.push_str(&format!(
+                "<item id=\"ep{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+                i + 1,
+                chapter.file_name
+            ));
+            spine.push_str(&format!("<itemref idref=\"ep{}\"/>\n", i + 1));
+        }
+        for (i, illustration) in self.illustrations.iter().enumerate() {
+            manifest.push_str(&format!(
+                "<item id=\"img{}\" href=\"{}\" media-type=\"{}\"/>\n",
+                i + 1,
+                image_paths[illustration.url.as_str()],
+                illustration.media_type
+            ));
+        }
+
+        let direction = if self.options.vertical { "rtl" } else { "ltr" };
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="bookid" xml:lang="{lang}">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+{metadata}</metadata>
+<manifest>
+{manifest}</manifest>
+<spine page-progression-direction="{direction}">
+{spine}</spine>
+</package>
+"#,
+            lang = escape_xml(&meta.language),
+            metadata = metadata,
+            manifest = manifest,
+            direction = direction,
+            spine = spine,
+        )
+    }
+
+    fn stylesheet(&self) -> String {
+        let mut css = String::from(BASE_CSS);
+        if self.options.vertical {
+            css.push_str(VERTICAL_CSS);
+        }
+        css
+    }
+}
+
+fn xhtml_document(title: &str, lang: &str, class: &str, css_href: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xml:lang="{lang}" lang="{lang}" class="{class}">
+<head>
+<meta charset="UTF-8"/>
+<title>{title}</title>
+<link rel="stylesheet" type="text/css" href="{css}"/>
+</head>
+<body>
+{body}</body>
+</html>
+"#,
+        lang = escape_xml(lang),
+        class = class,
+        title = escape_xml(title),
+        css = css_href,
+        body = body,
+    )
+}
+
+/// キーワードはAPIでは空白区切り
+fn split_keywords(keyword: Option<&str>) -> Vec<String> {
+    keyword
+        .map(|k| k.split_whitespace().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles>
+<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+</rootfiles>
+</container>
+"#;
+
+const BASE_CSS: &str = "body { margin: 0; line-height: 1.8; }
+p { margin: 0; }
+h2.chapter { font-size: 1.3em; margin: 1em 0; }
+h3.subtitle { font-size: 1.1em; margin: 1em 0; }
+div.preface, div.afterword { font-size: 0.9em; margin: 2em 0; }
+img.illustration { max-width: 100%; max-height: 100%; }
+rt { font-size: 0.5em; }
+";
+
+const VERTICAL_CSS: &str = "html.vrtl {
+  writing-mode: vertical-rl;
+  -epub-writing-mode: vertical-rl;
+  -webkit-writing-mode: vertical-rl;
+}
+html.vrtl div.preface, html.vrtl div.afterword { margin: 0 2em; }
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel_scraper::{Episode, NovelType};
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    fn fixture(path: &str) -> String {
+        fs::read_to_string(format!("target_pages/{}", path)).unwrap()
+    }
+
+    fn novel() -> NovelContent {
+        NovelContent {
+            ncode: "n7775do".to_string(),
+            novel_type: NovelType::Serial { total_episodes: 2 },
+            episodes: vec![
+                Episode { episode_number: 1, html: fixture("narou/novel/n7775do-2.html") },
+                Episode { episode_number: 2, html: fixture("narou/novel/n7775do-2.html") },
+            ],
+        }
+    }
+
+    fn read_entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+        let mut text = String::new();
+        archive.by_name(name).unwrap().read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_build_epub() {
+        let index = NovelIndex::parse(&fixture("narou/novel_list/n7775do.html"));
+        let metadata = EpubMetadata::from_index("n7775do", &index);
+        let data = EpubExporter::new(metadata)
+            .index(index)
+            .options(EpubOptions { vertical: true, ..Default::default() })
+            .build(&novel())
+            .unwrap();
+
+        let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+        {
+            let first = archive.by_index(0).unwrap();
+            assert_eq!(first.name(), "mimetype");
+            assert_eq!(first.compression(), CompressionMethod::Stored);
+        }
+
+        let opf = read_entry(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>お前が神を殺したいなら、とあなたは言った</dc:title>"));
+        assert!(opf.contains("<dc:creator>ふじやま</dc:creator>"));
+        assert!(opf.contains(r#"page-progression-direction="rtl""#));
+
+        let nav = read_entry(&mut archive, "OEBPS/nav.xhtml");
+        assert!(nav.contains(r#"<li><a href="text/ep0001.xhtml">はじめに</a><ol>"#));
+        assert!(nav.contains(r#"<li><a href="text/ep0002.xhtml">終わりの始まりはいつもそこにあり、なべて世はこともなし</a><ol>"#));
+
+        let page = read_entry(&mut archive, "OEBPS/text/ep0002.xhtml");
+        assert!(page.contains(r#"<div class="preface">"#));
+        assert!(page.contains(r#"<p id="L78">「ナオキ<ruby>前<rp>(</rp><rt>・</rt><rp>)</rp></ruby>教祖様のご遺志、しかと承りました。</p>"#));
+        assert!(page.contains(r#"<p id="L2"><br/></p>"#));
+
+        let css = read_entry(&mut archive, "OEBPS/style.css");
+        assert!(css.contains("writing-mode: vertical-rl"));
+    }
+
+    #[test]
+    fn test_embedded_illustration() {
+        let html = r#"<h1 class="p-novel__title">挿絵回</h1><div class="p-novel__body"><div class="js-novel-text p-novel__text">
+<p id="L1"><a href="//1.mitemin.net/i2/"><img src="//1.mitemin.net/i2.png" alt="挿絵(By みてみん)" /></a></p></div></div>"#;
+        let novel = NovelContent {
+            ncode: "n0001a".to_string(),
+            novel_type: NovelType::ShortStory,
+            episodes: vec![Episode { episode_number: 0, html: html.to_string() }],
+        };
+        let png = b"\x89PNG\r\n\x1a\nrest".to_vec();
+        let metadata = EpubMetadata { language: "en".to_string(), ..EpubMetadata::new("n0001a", "短編") };
+        let data = EpubExporter::new(metadata)
+            .options(EpubOptions { include_preface: false, ..Default::default() })
+            .illustrations(vec![Illustration::new("https://1.mitemin.net/i2.png", png.clone())])
+            .build(&novel)
+            .unwrap();
+
+        let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+        let page = read_entry(&mut archive, "OEBPS/text/ep0000.xhtml");
+        assert!(page.contains(r#"src="../images/img001.png""#));
+        assert!(page.contains(r#"xml:lang="en" lang="en""#));
+
+        let opf = read_entry(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains(r#"href="images/img001.png" media-type="image/png""#));
+        assert!(opf.contains(r#"page-progression-direction="ltr""#));
+
+        let mut image = Vec::new();
+        archive.by_name("OEBPS/images/img001.png").unwrap().read_to_end(&mut image).unwrap();
+        assert_eq!(image, png);
+    }
+}
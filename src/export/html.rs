@@ -0,0 +1,455 @@
+use super::{episode_title, escape_xml, paragraphs_xhtml};
+use crate::novel_parser::{NovelIndex, ParsedEpisode};
+use crate::novel_scraper::{NovelContent, NovelType};
+use crate::ruby::RubyMode;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 既定のHTMLテンプレート
+const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>
+body { max-width: 40em; margin: 2em auto; padding: 0 1em; line-height: 1.8; font-family: serif; }
+p { margin: 0; }
+.preface, .afterword { margin: 2em 0; padding: 1em; background: #f6f6f6; font-size: 0.9em; }
+.episode-nav { display: flex; justify-content: space-between; margin: 2em 0; }
+hr.chapter-divider { margin: 3em 0; }
+img.illustration { max-width: 100%; }
+</style>
+</head>
+<body>
+{{nav}}
+{{content}}
+{{nav}}
+</body>
+</html>
+"#;
+
+/// HTML出力のテンプレート
+///
+/// `{{title}}`・`{{novel_title}}`・`{{author}}`・`{{nav}}`・`{{content}}`が置き換えられる。
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlTemplate {
+    source: String,
+}
+
+impl Default for HtmlTemplate {
+    fn default() -> Self {
+        Self {
+            source: DEFAULT_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl HtmlTemplate {
+    /// テンプレート文字列から作成（`{{content}}`は必須）
+    pub fn new(source: impl Into<String>) -> Result<Self> {
+        let source = source.into();
+        if !source.contains("{{content}}") {
+            anyhow::bail!("HTML template must contain {{{{content}}}}");
+        }
+        Ok(Self { source })
+    }
+
+    /// テンプレートファイルを読み込む
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read HTML template {}", path.display()))?;
+        Self::new(source)
+    }
+
+    /// プレースホルダーを置き換える（値はHTMLとしてそのまま埋め込まれる）
+    ///
+    /// テンプレートを1回だけ走査するので、値に含まれる`{{content}}`などは置き換えられない。
+    pub fn render(&self, values: &[(&str, &str)]) -> String {
+        let mut out = String::with_capacity(self.source.len());
+        let mut rest = self.source.as_str();
+        while let Some(start) = rest.find("{{") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let value = after.find("}}").and_then(|end| {
+                values.iter().find(|(key, _)| *key == &after[..end]).map(|(_, value)| (end, *value))
+            });
+            match value {
+                Some((end, value)) => {
+                    out.push_str(value);
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    // 知らないプレースホルダーはそのまま残す
+                    out.push_str("{{");
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// HTML出力のオプション
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlOptions {
+    /// 前書きを含める
+    pub include_preface: bool,
+    /// 後書きを含める
+    pub include_afterword: bool,
+    /// ルビの出力方法
+    pub ruby: RubyMode,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            include_preface: true,
+            include_afterword: true,
+            ruby: RubyMode::Keep,
+        }
+    }
+}
+
+/// サイトの部品を取り除いた読書用HTMLを作成
+pub struct HtmlExporter {
+    options: HtmlOptions,
+    template: HtmlTemplate,
+    title: String,
+    author: String,
+    index: Option<NovelIndex>,
+    /// 挿絵URL→保存先のパス（未設定の挿絵は元のURLを参照する）
+    illustration_paths: HashMap<String, String>,
+}
+
+impl HtmlExporter {
+    pub fn new(options: HtmlOptions) -> Self {
+        Self {
+            options,
+            template: HtmlTemplate::default(),
+            title: String::new(),
+            author: String::new(),
+            index: None,
+            illustration_paths: HashMap::new(),
+        }
+    }
+
+    pub fn template(mut self, template: HtmlTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// 目次ページに書く作品名・作者名
+    pub fn header(mut self, title: impl Into<String>, author: impl Into<String>) -> Self {
+        self.title = title.into();
+        self.author = author.into();
+        self
+    }
+
+    /// 目次を設定（章見出し・あらすじに使う）
+    pub fn index(mut self, index: NovelIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// 挿絵の保存先を設定
+    pub fn illustration_path(mut self, url: impl Into<String>, path: impl Into<String>) -> Self {
+        self.illustration_paths.insert(url.into(), path.into());
+        self
+    }
+
+    /// 1話分のページ（`prev`・`next`は前後の話、`index`は目次ページのファイル名。目次がなければNone）
+    pub fn render_episode(
+        &self,
+        parsed: &ParsedEpisode,
+        prev: Option<&str>,
+        next: Option<&str>,
+        index: Option<&str>,
+    ) -> String {
+        let title = episode_title(parsed, self.index.as_ref());
+        let nav = episode_nav(prev, next, index);
+        self.template.render(&[
+            ("title", &escape_xml(&title)),
+            ("novel_title", &escape_xml(&self.title)),
+            ("author", &escape_xml(&self.author)),
+            ("nav", &nav),
+            ("content", &self.episode_content(parsed, false)),
+        ])
+    }
+
+    /// 目次ページ（`files`は話数とファイル名の組）
+    pub fn render_index_page(&self, files: &[(u32, String)]) -> String {
+        let mut content = self.title_block();
+        content.push_str(&self.toc(files));
+        self.template.render(&[
+            ("title", &escape_xml(&self.title)),
+            ("novel_title", &escape_xml(&self.title)),
+            ("author", &escape_xml(&self.author)),
+            ("nav", ""),
+            ("content", &content),
+        ])
+    }
+
+    /// 全話を1ページに
+    pub fn render_combined(&self, novel: &NovelContent) -> String {
+        let parsed: Vec<ParsedEpisode> = novel.episodes.iter().map(ParsedEpisode::parse).collect();
+        let anchors: Vec<(u32, String)> = parsed.iter()
+            .map(|p| (p.episode_number, format!("#ep{:04}", p.episode_number)))
+            .collect();
+
+        let mut content = self.title_block();
+        content.push_str(&self.toc(&anchors));
+        for episode in &parsed {
+            content.push_str(&self.episode_content(episode, true));
+        }
+
+        self.template.render(&[
+            ("title", &escape_xml(&self.title)),
+            ("novel_title", &escape_xml(&self.title)),
+            ("author", &escape_xml(&self.author)),
+            ("nav", ""),
+            ("content", &content),
+        ])
+    }
+
+    /// 1ファイルにまとめて書き出す
+    pub fn write_combined(&self, novel: &NovelContent, path: &Path) -> Result<()> {
+        fs::write(path, self.render_combined(novel))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// 1話ずつ`0001.html`に書き出し、`index.html`を生成する（短編は`{ncode}.html`のみ）
+    pub fn write_separate(&self, novel: &NovelContent, dir: &Path) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+
+        let files: Vec<(u32, String)> = novel.episodes.iter()
+            .map(|e| {
+                let name = match novel.novel_type {
+                    NovelType::ShortStory => format!("{}.html", novel.ncode),
+                    NovelType::Serial { .. } => format!("{:04}.html", e.episode_number),
+                };
+                (e.episode_number, name)
+            })
+            .collect();
+
+        // 目次ページを作るのは連載だけ
+        let index = match novel.novel_type {
+            NovelType::ShortStory => None,
+            NovelType::Serial { .. } => Some("index.html"),
+        };
+
+        let mut paths = Vec::new();
+        for (i, episode) in novel.episodes.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|p| files[p].1.as_str());
+            let next = files.get(i + 1).map(|(_, name)| name.as_str());
+            let path = dir.join(&files[i].1);
+            fs::write(&path, self.render_episode(&ParsedEpisode::parse(episode), prev, next, index))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path);
+        }
+
+        if let Some(index) = index {
+            let path = dir.join(index);
+            fs::write(&path, self.render_index_page(&files))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    fn episode_content(&self, parsed: &ParsedEpisode, combined: bool) -> String {
+        let resolve_image = |url: &str| {
+            Some(self.illustration_paths.get(url).cloned().unwrap_or_else(|| url.to_string()))
+        };
+        let mut out = String::new();
+
+        if let Some(chapter) = self.index.as_ref().and_then(|i| i.chapter_starting_at(parsed.episode_number)) {
+            if combined {
+                out.push_str("<hr class=\"chapter-divider\">\n");
+            }
+            out.push_str(&format!("<h2 class=\"chapter\">{}</h2>\n", escape_xml(chapter)));
+        }
+
+        if combined {
+            out.push_str(&format!("<section class=\"episode\" id=\"ep{:04}\">\n", parsed.episode_number));
+        } else {
+            out.push_str("<section class=\"episode\">\n");
+        }
+        out.push_str(&format!(
+            "<h3 class=\"subtitle\">{}</h3>\n",
+            escape_xml(&episode_title(parsed, self.index.as_ref()))
+        ));
+
+        let sections = [
+            ("preface", &parsed.preface, self.options.include_preface),
+            ("honbun", &parsed.body, true),
+            ("afterword", &parsed.afterword, self.options.include_afterword),
+        ];
+        for (class, paragraphs, included) in sections {
+            if included && !paragraphs.is_empty() {
+                out.push_str(&format!("<div class=\"{}\">\n", class));
+                out.push_str(&paragraphs_xhtml(paragraphs, self.options.ruby, &resolve_image));
+                out.push_str("</div>\n");
+            }
+        }
+        out.push_str("</section>\n");
+        out
+    }
+
+    fn title_block(&self) -> String {
+        let mut out = format!("<h1 class=\"title\">{}</h1>\n", escape_xml(&self.title));
+        if !self.author.is_empty() {
+            out.push_str(&format!("<p class=\"author\">{}</p>\n", escape_xml(&self.author)));
+        }
+        if let Some(summary) = self.index.as_ref().map(|i| &i.summary).filter(|s| !s.is_empty()) {
+            out.push_str("<div class=\"synopsis\">\n");
+            for line in summary.lines() {
+                out.push_str(&format!("<p>{}</p>\n", escape_xml(line)));
+            }
+            out.push_str("</div>\n");
+        }
+        out
+    }
+
+    /// 章ごとにまとめた目次（`targets`にない話は載せない）
+    fn toc(&self, targets: &[(u32, String)]) -> String {
+        let link = |number: u32, subtitle: &str| {
+            targets.iter().find(|(n, _)| *n == number).map(|(_, href)| {
+                format!("<li><a href=\"{}\">{}</a></li>\n", escape_xml(href), escape_xml(subtitle))
+            })
+        };
+
+        let mut out = String::from("<nav class=\"toc\">\n");
+        match &self.index {
+            Some(index) => {
+                for chapter in &index.chapters {
+                    let items: String = chapter.episodes.iter()
+                        .filter_map(|e| link(e.episode_number, &e.subtitle))
+                        .collect();
+                    if items.is_empty() {
+                        continue;
+                    }
+                    if let Some(title) = &chapter.title {
+                        out.push_str(&format!("<h2 class=\"chapter\">{}</h2>\n", escape_xml(title)));
+                    }
+                    out.push_str(&format!("<ol>\n{}</ol>\n", items));
+                }
+            }
+            None => {
+                let items: String = targets.iter()
+                    .filter_map(|(n, _)| link(*n, &format!("第{}話", n)))
+                    .collect();
+                out.push_str(&format!("<ol>\n{}</ol>\n", items));
+            }
+        }
+        out.push_str("</nav>\n");
+        out
+    }
+}
+
+fn episode_nav(prev: Option<&str>, next: Option<&str>, index: Option<&str>) -> String {
+    let link = |href: Option<&str>, label: &str| match href {
+        Some(href) => format!("<a href=\"{}\">{}</a>", escape_xml(href), label),
+        None => format!("<span>{}</span>", label),
+    };
+    let index = index
+        .map(|href| format!("<a href=\"{}\">目次</a>", escape_xml(href)))
+        .unwrap_or_default();
+    format!(
+        "<nav class=\"episode-nav\">{}{}{}</nav>",
+        link(prev, "前へ"),
+        index,
+        link(next, "次へ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel_scraper::Episode;
+
+    fn novel() -> NovelContent {
+        let html = fs::read_to_string("target_pages/narou/novel/n7775do-2.html").unwrap();
+        NovelContent {
+            ncode: "n7775do".to_string(),
+            novel_type: NovelType::Serial { total_episodes: 2 },
+            episodes: vec![
+                Episode { episode_number: 1, html: html.clone() },
+                Episode { episode_number: 2, html },
+            ],
+        }
+    }
+
+    fn exporter() -> HtmlExporter {
+        let index = NovelIndex::parse(&fs::read_to_string("target_pages/narou/novel_list/n7775do.html").unwrap());
+        HtmlExporter::new(HtmlOptions::default())
+            .header(index.title.clone(), index.author.clone())
+            .index(index)
+    }
+
+    #[test]
+    fn test_clean_episode_page() {
+        let novel = novel();
+        let page = exporter().render_episode(
+            &ParsedEpisode::parse(&novel.episodes[1]),
+            Some("0001.html"),
+            None,
+            Some("index.html"),
+        );
+
+        assert!(page.contains("<title>天真歴19年　9月14日</title>"));
+        assert!(page.contains(r#"<h2 class="chapter">終わりの始まりはいつもそこにあり、なべて世はこともなし</h2>"#));
+        assert!(page.contains("<ruby>前<rp>(</rp><rt>・</rt><rp>)</rp></ruby>"));
+        assert!(page.contains(r#"<a href="0001.html">前へ</a>"#));
+        for chrome in ["c-ad", "p-adjust-layout", "p-reaction", "p-bookmark-modal", "c-sns__button"] {
+            assert!(!page.contains(chrome), "{}", chrome);
+        }
+    }
+
+    #[test]
+    fn test_combined_with_template() {
+        let template = HtmlTemplate::new("<main data-novel=\"{{novel_title}}\">{{content}}</main>").unwrap();
+        let page = exporter().template(template).render_combined(&novel());
+
+        assert!(page.starts_with("<main data-novel=\"お前が神を殺したいなら、とあなたは言った\">"));
+        assert!(page.contains(r##"<li><a href="#ep0001">登場人物/教会派閥紹介</a></li>"##));
+        assert!(page.contains(r#"<section class="episode" id="ep0002">"#));
+        assert_eq!(page.matches("chapter-divider").count(), 2);
+
+        assert!(HtmlTemplate::new("<body></body>").is_err());
+
+        // 値に含まれるプレースホルダーは展開しない
+        let template = HtmlTemplate::new("<title>{{title}}</title>{{unknown}}{{content}}").unwrap();
+        assert_eq!(
+            template.render(&[("title", "{{content}}"), ("content", "本文")]),
+            "<title>{{content}}</title>{{unknown}}本文"
+        );
+    }
+
+    #[test]
+    fn test_write_separate_with_index_page() {
+        let dir = std::env::temp_dir().join(format!("wns-html-{}", std::process::id()));
+        let paths = exporter().write_separate(&novel(), &dir).unwrap();
+
+        assert_eq!(paths.len(), 3);
+        let index = fs::read_to_string(dir.join("index.html")).unwrap();
+        assert!(index.contains(r#"<a href="0002.html">天真歴19年　9月14日</a>"#));
+        assert!(index.contains(r#"<h2 class="chapter">はじめに</h2>"#));
+        let page = fs::read_to_string(dir.join("0001.html")).unwrap();
+        assert!(page.contains(r#"<a href="index.html">目次</a>"#));
+        fs::remove_dir_all(&dir).unwrap();
+
+        // 短編には目次ページがないのでリンクしない
+        let mut short = novel();
+        short.novel_type = NovelType::ShortStory;
+        short.episodes.truncate(1);
+        let paths = exporter().write_separate(&short, &dir).unwrap();
+        assert_eq!(paths, vec![dir.join("n7775do.html")]);
+        assert!(!fs::read_to_string(&paths[0]).unwrap().contains("目次"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+use super::{episode_title, paragraph_to_xhtml};
+use crate::novel_parser::{NovelIndex, Paragraph, ParsedEpisode};
+use crate::novel_scraper::{NovelContent, NovelType};
+use crate::ruby::RubyMode;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Markdown出力のオプション
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownOptions {
+    /// 前書きを含める
+    pub include_preface: bool,
+    /// 後書きを含める
+    pub include_afterword: bool,
+    /// ルビの出力方法
+    pub ruby: RubyMode,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            include_preface: true,
+            include_afterword: true,
+            ruby: RubyMode::Keep,
+        }
+    }
+}
+
+/// 本文をMarkdownに変換
+///
+/// 連続する行は強制改行でつなぎ、原文の空行で段落を分ける。ルビは既定では`<ruby>`のまま残す。
+pub struct MarkdownExporter {
+    options: MarkdownOptions,
+    title: String,
+    author: String,
+    index: Option<NovelIndex>,
+    /// 挿絵URL→保存先のパス（未設定の挿絵は元のURLを参照する）
+    illustration_paths: HashMap<String, String>,
+}
+
+impl MarkdownExporter {
+    pub fn new(options: MarkdownOptions) -> Self {
+        Self {
+            options,
+            title: String::new(),
+            author: String::new(),
+            index: None,
+            illustration_paths: HashMap::new(),
+        }
+    }
+
+    /// 目次ページに書く作品名・作者名
+    pub fn header(mut self, title: impl Into<String>, author: impl Into<String>) -> Self {
+        self.title = title.into();
+        self.author = author.into();
+        self
+    }
+
+    /// 目次を設定（章見出し・あらすじに使う）
+    pub fn index(mut self, index: NovelIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// 挿絵の保存先を設定
+    pub fn illustration_path(mut self, url: impl Into<String>, path: impl Into<String>) -> Self {
+        self.illustration_paths.insert(url.into(), path.into());
+        self
+    }
+
+    /// 1話分のMarkdown
+    pub fn render_episode(&self, parsed: &ParsedEpisode) -> String {
+        let mut blocks = Vec::new();
+
+        if let Some(chapter) = self.index.as_ref().and_then(|i| i.chapter_starting_at(parsed.episode_number)) {
+            blocks.push(format!("## {}", escape_markdown(chapter)));
+        }
+        blocks.push(format!("### {}", escape_markdown(&episode_title(parsed, self.index.as_ref()))));
+
+        if self.options.include_preface && !parsed.preface.is_empty() {
+            blocks.extend(self.paragraph_blocks(&parsed.preface));
+            blocks.push("***".to_string());
+        }
+        blocks.extend(self.paragraph_blocks(&parsed.body));
+        if self.options.include_afterword && !parsed.afterword.is_empty() {
+            blocks.push("***".to_string());
+            blocks.extend(self.paragraph_blocks(&parsed.afterword));
+        }
+
+        let mut out = blocks.join("\n\n");
+        out.push('\n');
+        out
+    }
+
+    /// 目次ページ（`files`は話数とリンク先の組）
+    pub fn render_index_page(&self, files: &[(u32, String)]) -> String {
+        let mut out = self.title_block();
+        let link = |number: u32, subtitle: &str| {
+            files.iter().find(|(n, _)| *n == number)
+                .map(|(_, href)| format!("1. [{}]({})\n", escape_markdown(subtitle), href))
+        };
+
+        match &self.index {
+            Some(index) => {
+                for chapter in &index.chapters {
+                    let items: String = chapter.episodes.iter()
+                        .filter_map(|e| link(e.episode_number, &e.subtitle))
+                        .collect();
+                    if items.is_empty() {
+                        continue;
+                    }
+                    if let Some(title) = &chapter.title {
+                        out.push_str(&format!("## {}\n\n", escape_markdown(title)));
+                    }
+                    out.push_str(&items);
+                    out.push('\n');
+                }
+            }
+            None => {
+                for (number, _) in files {
+                    out.push_str(&link(*number, &format!("第{}話", number)).unwrap_or_default());
+                }
+            }
+        }
+
+        out
+    }
+
+    /// 全話を1つのMarkdownに（章の区切りは水平線）
+    pub fn render_combined(&self, novel: &NovelContent) -> String {
+        let mut out = self.title_block();
+        let episodes: Vec<String> = novel.episodes.iter()
+            .map(|e| self.render_episode(&ParsedEpisode::parse(e)))
+            .collect();
+        out.push_str(&episodes.join("\n---\n\n"));
+        out
+    }
+
+    /// 1ファイルにまとめて書き出す
+    pub fn write_combined(&self, novel: &NovelContent, path: &Path) -> Result<()> {
+        fs::write(path, self.render_combined(novel))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// 1話ずつ`0001.md`に書き出し、`index.md`を生成する（短編は`{ncode}.md`のみ）
+    pub fn write_separate(&self, novel: &NovelContent, dir: &Path) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        let mut files = Vec::new();
+        let mut paths = Vec::new();
+
+        for episode in &novel.episodes {
+            let name = match novel.novel_type {
+                NovelType::ShortStory => format!("{}.md", novel.ncode),
+                NovelType::Serial { .. } => format!("{:04}.md", episode.episode_number),
+            };
+            let path = dir.join(&name);
+            fs::write(&path, self.render_episode(&ParsedEpisode::parse(episode)))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path);
+            files.push((episode.episode_number, name));
+        }
+
+        if let NovelType::Serial { .. } = novel.novel_type {
+            let path = dir.join("index.md");
+            fs::write(&path, self.render_index_page(&files))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    fn title_block(&self) -> String {
+        let mut out = format!("# {}\n\n", escape_markdown(&self.title));
+        if !self.author.is_empty() {
+            out.push_str(&format!("作者：{}\n\n", escape_markdown(&self.author)));
+        }
+        if let Some(summary) = self.index.as_ref().map(|i| &i.summary).filter(|s| !s.is_empty()) {
+            for line in summary.lines() {
+                out.push_str(&format!("> {}\n", escape_markdown(line)));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// 空行で区切られた行のまとまりを1ブロックにする
+    fn paragraph_blocks(&self, paragraphs: &[Paragraph]) -> Vec<String> {
+        let resolve_image = |url: &str| {
+            Some(self.illustration_paths.get(url).cloned().unwrap_or_else(|| url.to_string()))
+        };
+        let mut blocks = Vec::new();
+        let mut lines: Vec<String> = Vec::new();
+
+        for paragraph in paragraphs {
+            if paragraph.is_blank() {
+                if !lines.is_empty() {
+                    blocks.push(lines.join("  \n"));
+                    lines.clear();
+                }
+                continue;
+            }
+            lines.push(escape_markdown(&paragraph_to_xhtml(&paragraph.html, self.options.ruby, &resolve_image)));
+        }
+        if !lines.is_empty() {
+            blocks.push(lines.join("  \n"));
+        }
+
+        blocks
+    }
+}
+
+/// Markdownの記号をエスケープ（タグの中は変えない）
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            '\\' | '*' | '_' | '`' | '[' | ']' if !in_tag => out.push('\\'),
+            '#' | '-' | '+' if i == 0 => out.push('\\'),
+            _ => {}
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel_scraper::Episode;
+
+    #[test]
+    fn test_render_episode() {
+        let html = r#"<h1 class="p-novel__title">第一話</h1><div class="p-novel__body">
+<div class="js-novel-text p-novel__text p-novel__text--preface"><p id="Lp1">前書き</p></div>
+<div class="js-novel-text p-novel__text"><p id="L1">一行目*</p><p id="L2"><ruby>漢字<rp>(</rp><rt>かんじ</rt><rp>)</rp></ruby></p>
+<p id="L3"><br /></p><p id="L4">-三行目</p></div>
+<div class="js-novel-text p-novel__text p-novel__text--afterword"><p id="La1">後書き</p></div></div>"#;
+        let parsed = ParsedEpisode::parse(&Episode { episode_number: 1, html: html.to_string() });
+
+        let markdown = MarkdownExporter::new(MarkdownOptions { include_afterword: false, ..Default::default() })
+            .render_episode(&parsed);
+        assert_eq!(
+            markdown,
+            "### 第一話\n\n前書き\n\n***\n\n一行目\\*  \n<ruby>漢字<rp>(</rp><rt>かんじ</rt><rp>)</rp></ruby>\n\n\\-三行目\n"
+        );
+    }
+
+    #[test]
+    fn test_index_page() {
+        let index = NovelIndex::parse(&fs::read_to_string("target_pages/narou/novel_list/n7775do.html").unwrap());
+        let exporter = MarkdownExporter::new(MarkdownOptions::default())
+            .header(index.title.clone(), index.author.clone())
+            .index(index);
+
+        let page = exporter.render_index_page(&[(1, "0001.md".to_string()), (2, "0002.md".to_string())]);
+        assert!(page.starts_with("# お前が神を殺したいなら、とあなたは言った\n\n作者：ふじやま\n\n> 「ナオキ"));
+        assert!(page.contains("## はじめに\n\n1. [登場人物/教会派閥紹介](0001.md)\n"));
+        assert!(page.contains("1. [天真歴19年　9月14日](0002.md)\n"));
+        assert!(!page.contains("0003.md"));
+    }
+}
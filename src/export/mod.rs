@@ -0,0 +1,176 @@
+pub mod epub;
+pub mod html;
+pub mod markdown;
+pub mod text;
+
+pub use epub::{EpubExporter, EpubMetadata, EpubOptions};
+pub use html::{HtmlExporter, HtmlOptions, HtmlTemplate};
+pub use markdown::{MarkdownExporter, MarkdownOptions};
+pub use text::{BlankLines, TextExporter, TextOptions, TextStyle};
+
+use crate::novel_parser::{absolute_url, NovelIndex, Paragraph, ParsedEpisode};
+use crate::ruby::{Ruby, RubyMode};
+use scraper::{ElementRef, Html, Node};
+
+/// 本文中でそのまま残すタグ
+const KEPT_TAGS: &[&str] = &["em", "strong", "b", "i", "s", "sub", "sup", "span"];
+
+/// エピソードの見出し（本文にサブタイトルがなければ目次から補う）
+pub(crate) fn episode_title(parsed: &ParsedEpisode, index: Option<&NovelIndex>) -> String {
+    if !parsed.subtitle.is_empty() {
+        return parsed.subtitle.clone();
+    }
+    index
+        .and_then(|i| i.subtitle_of(parsed.episode_number))
+        .map(String::from)
+        .unwrap_or_else(|| format!("第{}話", parsed.episode_number))
+}
+
+/// XMLの特殊文字をエスケープ
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// 段落の中身のHTMLを整形式のXHTMLに変換
+///
+/// 強調などのタグは残し、リンクなどそれ以外のタグは中身だけにする。
+/// `<ruby>`要素は`ruby`に従って出力する（なろう記法の｜《》は文字のまま残す）。
+/// 挿絵は`resolve_image`が返すパスに差し替え、取得できていない挿絵は代替テキストにする。
+pub(crate) fn paragraph_to_xhtml(
+    html: &str,
+    ruby: RubyMode,
+    resolve_image: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    let fragment = Html::parse_fragment(html);
+    let renderer = XhtmlRenderer { ruby, resolve_image };
+    let mut out = String::new();
+    renderer.render_children(fragment.root_element(), &mut out);
+    out
+}
+
+/// 段落を`<p id="L1">…</p>`の並びに変換
+pub(crate) fn paragraphs_xhtml(
+    paragraphs: &[Paragraph],
+    ruby: RubyMode,
+    resolve_image: &dyn Fn(&str) -> Option<String>,
+) -> String {
+    let mut out = String::new();
+    for paragraph in paragraphs {
+        let content = if paragraph.is_blank() {
+            "<br/>".to_string()
+        } else {
+            paragraph_to_xhtml(&paragraph.html, ruby, resolve_image)
+        };
+        if paragraph.id.is_empty() {
+            out.push_str(&format!("<p>{}</p>\n", content));
+        } else {
+            out.push_str(&format!("<p id=\"{}\">{}</p>\n", escape_xml(&paragraph.id), content));
+        }
+    }
+    out
+}
+
+struct XhtmlRenderer<'a> {
+    ruby: RubyMode,
+    resolve_image: &'a dyn Fn(&str) -> Option<String>,
+}
+
+impl XhtmlRenderer<'_> {
+    fn render_children(&self, element: ElementRef, out: &mut String) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(&escape_xml(text)),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.render_element(child, out);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn render_element(&self, element: ElementRef, out: &mut String) {
+        let name = element.value().name();
+        match name {
+            "br" => out.push_str("<br/>"),
+            "ruby" => self.render_ruby(&Ruby::from_element(element), out),
+            "rp" | "rt" => {}
+            "img" => {
+                let alt = element.value().attr("alt").unwrap_or("挿絵");
+                let local = element
+                    .value()
+                    .attr("src")
+                    .and_then(|src| (self.resolve_image)(&absolute_url(src)));
+                match local {
+                    Some(path) => out.push_str(&format!(
+                        r#"<img class="illustration" src="{}" alt="{}"/>"#,
+                        escape_xml(&path),
+                        escape_xml(alt)
+                    )),
+                    None => out.push_str(&format!("［{}］", escape_xml(alt))),
+                }
+            }
+            _ if KEPT_TAGS.contains(&name) => {
+                out.push_str(&format!("<{}>", name));
+                self.render_children(element, out);
+                out.push_str(&format!("</{}>", name));
+            }
+            _ => self.render_children(element, out),
+        }
+    }
+
+    fn render_ruby(&self, ruby: &Ruby, out: &mut String) {
+        match self.ruby {
+            RubyMode::Keep => out.push_str(&ruby.to_html()),
+            mode => out.push_str(&escape_xml(&ruby.render(mode))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paragraph_to_xhtml() {
+        let no_images = |_: &str| None;
+        assert_eq!(
+            paragraph_to_xhtml("A&amp;B<ruby>前<rp>(</rp><rt>・</rt><rp>)</rp></ruby><br>", RubyMode::Keep, &no_images),
+            "A&amp;B<ruby>前<rp>(</rp><rt>・</rt><rp>)</rp></ruby><br/>"
+        );
+        assert_eq!(
+            paragraph_to_xhtml("<ruby>魔法<rt>まほう</rt></ruby>と<ruby>剣<rt>けん</rt></ruby>", RubyMode::Keep, &no_images),
+            "<ruby>魔法<rp>(</rp><rt>まほう</rt><rp>)</rp></ruby>と<ruby>剣<rp>(</rp><rt>けん</rt><rp>)</rp></ruby>"
+        );
+        assert_eq!(
+            paragraph_to_xhtml("<ruby>魔法<rt>まほう</rt></ruby>と<ruby>剣<rt>けん</rt></ruby>", RubyMode::Parenthesize, &no_images),
+            "魔法（まほう）と剣（けん）"
+        );
+        // テキストに残った`｜`や`《》`は記法ではなく本文
+        assert_eq!(
+            paragraph_to_xhtml("A|B、東京《とうきょう》と<ruby>剣<rt>けん</rt></ruby>", RubyMode::Parenthesize, &no_images),
+            "A|B、東京《とうきょう》と剣（けん）"
+        );
+
+        let html = r#"<a href="//1.mitemin.net/i2/"><img src="//1.mitemin.net/i2.jpg" alt="挿絵(By みてみん)" /></a>"#;
+        assert_eq!(paragraph_to_xhtml(html, RubyMode::Keep, &no_images), "［挿絵(By みてみん)］");
+
+        let local = |url: &str| (url == "https://1.mitemin.net/i2.jpg").then(|| "../images/img001.jpg".to_string());
+        assert_eq!(
+            paragraph_to_xhtml(html, RubyMode::Keep, &local),
+            r#"<img class="illustration" src="../images/img001.jpg" alt="挿絵(By みてみん)"/>"#
+        );
+    }
+}
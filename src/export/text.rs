@@ -0,0 +1,353 @@
+use super::episode_title;
+use crate::novel_parser::{absolute_url, NovelIndex, Paragraph, ParsedEpisode};
+use crate::novel_scraper::{NovelContent, NovelType};
+use crate::ruby::{Ruby, RubyMode};
+use anyhow::{Context, Result};
+use scraper::{ElementRef, Html, Node};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 前書き・後書きと本文の区切り線（なろうのテキストダウンロードと同じ）
+const SECTION_SEPARATOR: &str = "********************************************";
+
+/// テキストの記法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextStyle {
+    /// 注記なしのプレーンテキスト
+    Plain,
+    /// 青空文庫形式（`｜漢字《かんじ》`・`［＃改ページ］`など）
+    Aozora,
+}
+
+/// 空行（`<p><br /></p>`）の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlankLines {
+    /// 原文どおり残す
+    Keep,
+    /// 連続する空行を1行にまとめる
+    Collapse,
+    /// 空行を全て取り除く
+    Remove,
+}
+
+/// テキスト出力のオプション
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextOptions {
+    pub style: TextStyle,
+    pub blank_lines: BlankLines,
+    /// ルビの出力方法（`Keep`は`｜漢字《かな》`、青空文庫形式では傍点も注記にする）
+    pub ruby: RubyMode,
+    /// 前書きを含める
+    pub include_preface: bool,
+    /// 後書きを含める
+    pub include_afterword: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            style: TextStyle::Plain,
+            blank_lines: BlankLines::Keep,
+            ruby: RubyMode::Parenthesize,
+            include_preface: true,
+            include_afterword: true,
+        }
+    }
+}
+
+/// `NovelContent`をUTF-8のテキストに変換
+pub struct TextExporter {
+    options: TextOptions,
+    title: Option<String>,
+    author: Option<String>,
+    index: Option<NovelIndex>,
+    /// 挿絵URL→保存先のパス
+    illustration_paths: HashMap<String, String>,
+}
+
+impl TextExporter {
+    pub fn new(options: TextOptions) -> Self {
+        Self {
+            options,
+            title: None,
+            author: None,
+            index: None,
+            illustration_paths: HashMap::new(),
+        }
+    }
+
+    /// 1ファイルにまとめる際の冒頭に書く作品名・作者名
+    pub fn header(mut self, title: impl Into<String>, author: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self.author = Some(author.into());
+        self
+    }
+
+    /// 目次を設定（章見出しとサブタイトルの補完に使う）
+    pub fn index(mut self, index: NovelIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// 挿絵の保存先を設定（青空文庫形式の挿絵注記に使う）
+    pub fn illustration_path(mut self, url: impl Into<String>, path: impl Into<String>) -> Self {
+        self.illustration_paths.insert(url.into(), path.into());
+        self
+    }
+
+    /// 1話分のテキスト
+    pub fn render_episode(&self, parsed: &ParsedEpisode) -> String {
+        let mut lines = Vec::new();
+
+        if let Some(chapter) = self.index.as_ref().and_then(|i| i.chapter_starting_at(parsed.episode_number)) {
+            lines.push(self.heading(chapter, "大見出し"));
+            lines.push(String::new());
+        }
+        lines.push(self.heading(&episode_title(parsed, self.index.as_ref()), "中見出し"));
+        lines.push(String::new());
+
+        if self.options.include_preface && !parsed.preface.is_empty() {
+            lines.extend(self.paragraph_lines(&parsed.preface));
+            lines.push(SECTION_SEPARATOR.to_string());
+        }
+        lines.extend(self.paragraph_lines(&parsed.body));
+        if self.options.include_afterword && !parsed.afterword.is_empty() {
+            lines.push(SECTION_SEPARATOR.to_string());
+            lines.extend(self.paragraph_lines(&parsed.afterword));
+        }
+
+        let mut text = lines.join("\n");
+        text.truncate(text.trim_end().len());
+        text.push('\n');
+        text
+    }
+
+    /// 全話を1つのテキストに
+    pub fn render_combined(&self, novel: &NovelContent) -> String {
+        let mut out = String::new();
+
+        if let Some(title) = &self.title {
+            out.push_str(title);
+            out.push('\n');
+            if let Some(author) = self.author.as_ref().filter(|a| !a.is_empty()) {
+                out.push_str(author);
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+
+        let episode_break = match self.options.style {
+            TextStyle::Plain => "\n\n",
+            TextStyle::Aozora => "\n［＃改ページ］\n\n",
+        };
+        let episodes: Vec<String> = novel.episodes.iter()
+            .map(|e| self.render_episode(&ParsedEpisode::parse(e)))
+            .collect();
+        out.push_str(&episodes.join(episode_break));
+        out
+    }
+
+    /// 1ファイルにまとめて書き出す
+    pub fn write_combined(&self, novel: &NovelContent, path: &Path) -> Result<()> {
+        fs::write(path, self.render_combined(novel))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// 1話ずつ`0001.txt`のように書き出す（短編は`{ncode}.txt`）
+    pub fn write_separate(&self, novel: &NovelContent, dir: &Path) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        let mut paths = Vec::new();
+
+        for episode in &novel.episodes {
+            let file_name = match novel.novel_type {
+                NovelType::ShortStory => format!("{}.txt", novel.ncode),
+                NovelType::Serial { .. } => format!("{:04}.txt", episode.episode_number),
+            };
+            let path = dir.join(file_name);
+            fs::write(&path, self.render_episode(&ParsedEpisode::parse(episode)))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    fn heading(&self, text: &str, level: &str) -> String {
+        match self.options.style {
+            TextStyle::Plain => text.to_string(),
+            TextStyle::Aozora => format!("［＃{level}］{}［＃{level}終わり］", escape_aozora(text), level = level),
+        }
+    }
+
+    fn paragraph_lines(&self, paragraphs: &[Paragraph]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut previous_blank = false;
+
+        for paragraph in paragraphs {
+            let blank = paragraph.is_blank();
+            let skip = match self.options.blank_lines {
+                BlankLines::Keep => false,
+                BlankLines::Collapse => blank && previous_blank,
+                BlankLines::Remove => blank,
+            };
+            previous_blank = blank;
+            if skip {
+                continue;
+            }
+            lines.push(if blank { String::new() } else { self.paragraph_text(&paragraph.html) });
+        }
+
+        lines
+    }
+
+    fn paragraph_text(&self, html: &str) -> String {
+        let fragment = Html::parse_fragment(html);
+        let mut out = String::new();
+        self.render_children(fragment.root_element(), &mut out);
+        out
+    }
+
+    fn render_children(&self, element: ElementRef, out: &mut String) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(&self.escape(text)),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.render_element(child, out);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn render_element(&self, element: ElementRef, out: &mut String) {
+        match element.value().name() {
+            "br" | "rp" => {}
+            "ruby" => self.render_ruby(&Ruby::from_element(element), out),
+            "img" => {
+                let alt = element.value().attr("alt").unwrap_or("挿絵");
+                let src = element.value().attr("src").map(absolute_url);
+                match self.options.style {
+                    TextStyle::Plain => out.push_str(&format!("［{}］", alt)),
+                    TextStyle::Aozora => {
+                        let target = src
+                            .map(|url| self.illustration_paths.get(&url).cloned().unwrap_or(url))
+                            .unwrap_or_default();
+                        out.push_str(&format!("［＃挿絵（{}）入る］", target));
+                    }
+                }
+            }
+            _ => self.render_children(element, out),
+        }
+    }
+
+    fn render_ruby(&self, ruby: &Ruby, out: &mut String) {
+        match (self.options.style, self.options.ruby) {
+            (TextStyle::Aozora, RubyMode::Keep) if ruby.reading.is_empty() => out.push_str(&escape_aozora(&ruby.base)),
+            (TextStyle::Aozora, RubyMode::Keep) if ruby.is_emphasis => {
+                let base = escape_aozora(&ruby.base);
+                out.push_str(&format!("{}［＃「{}」に傍点］", base, base));
+            }
+            (TextStyle::Aozora, RubyMode::Keep) => {
+                out.push_str(&format!("｜{}《{}》", escape_aozora(&ruby.base), escape_aozora(&ruby.reading)));
+            }
+            (_, mode) => out.push_str(&self.escape(&ruby.render(mode))),
+        }
+    }
+
+    fn escape(&self, text: &str) -> String {
+        match self.options.style {
+            TextStyle::Plain => text.to_string(),
+            TextStyle::Aozora => escape_aozora(text),
+        }
+    }
+}
+
+/// 本文中の注記記号をエスケープ（青空文庫の外字注記で置き換える）
+fn escape_aozora(text: &str) -> String {
+    text.replace('《', "※［＃始め二重山括弧、1-1-52］")
+        .replace('》', "※［＃終わり二重山括弧、1-1-53］")
+        .replace('｜', "※［＃縦線、1-1-35］")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel_scraper::Episode;
+
+    fn parsed(body: &str) -> ParsedEpisode {
+        let html = format!(
+            r#"<h1 class="p-novel__title">第一話</h1><div class="p-novel__body">
+<div class="js-novel-text p-novel__text p-novel__text--preface"><p id="Lp1">前書き</p></div>
+<div class="js-novel-text p-novel__text">{}</div></div>"#,
+            body
+        );
+        ParsedEpisode::parse(&Episode { episode_number: 1, html })
+    }
+
+    #[test]
+    fn test_plain_text() {
+        let episode = parsed(r#"<p id="L1">「<ruby>漢字<rp>(</rp><rt>かんじ</rt><rp>)</rp></ruby>と<ruby>前<rp>(</rp><rt>・</rt><rp>)</rp></ruby>」</p><p id="L2"><br /></p><p id="L3"><br /></p><p id="L4">終わり</p>"#);
+        let exporter = TextExporter::new(TextOptions::default());
+        assert_eq!(
+            exporter.render_episode(&episode),
+            format!("第一話\n\n前書き\n{}\n「漢字（かんじ）と前」\n\n\n終わり\n", SECTION_SEPARATOR)
+        );
+
+        let collapsed = TextExporter::new(TextOptions {
+            blank_lines: BlankLines::Collapse,
+            include_preface: false,
+            ..Default::default()
+        });
+        assert_eq!(collapsed.render_episode(&episode), "第一話\n\n「漢字（かんじ）と前」\n\n終わり\n");
+
+        let removed = TextExporter::new(TextOptions {
+            blank_lines: BlankLines::Remove,
+            include_preface: false,
+            ..Default::default()
+        });
+        assert_eq!(removed.render_episode(&episode), "第一話\n\n「漢字（かんじ）と前」\n終わり\n");
+    }
+
+    #[test]
+    fn test_aozora_notation() {
+        let episode = parsed(r#"<p id="L1"><ruby>漢字<rp>(</rp><rt>かんじ</rt><rp>)</rp></ruby>と<ruby>前<rp>(</rp><rt>・</rt><rp>)</rp></ruby></p><p id="L2"><a href="//1.mitemin.net/i2/"><img src="//1.mitemin.net/i2.jpg" alt="挿絵(By みてみん)" /></a></p>"#);
+        let exporter = TextExporter::new(TextOptions {
+            style: TextStyle::Aozora,
+            ruby: RubyMode::Keep,
+            include_preface: false,
+            ..Default::default()
+        })
+        .illustration_path("https://1.mitemin.net/i2.jpg", "images/i2.jpg");
+
+        assert_eq!(
+            exporter.render_episode(&episode),
+            "［＃中見出し］第一話［＃中見出し終わり］\n\n｜漢字《かんじ》と前［＃「前」に傍点］\n［＃挿絵（images/i2.jpg）入る］\n"
+        );
+    }
+
+    #[test]
+    fn test_combined_aozora_with_chapters() {
+        let index = NovelIndex::parse(&fs::read_to_string("target_pages/narou/novel_list/n7775do.html").unwrap());
+        let html = fs::read_to_string("target_pages/narou/novel/n7775do-2.html").unwrap();
+        let novel = NovelContent {
+            ncode: "n7775do".to_string(),
+            novel_type: NovelType::Serial { total_episodes: 2 },
+            episodes: vec![
+                Episode { episode_number: 1, html: html.clone() },
+                Episode { episode_number: 2, html },
+            ],
+        };
+
+        let text = TextExporter::new(TextOptions { style: TextStyle::Aozora, ruby: RubyMode::Keep, ..Default::default() })
+            .header(index.title.clone(), index.author.clone())
+            .index(index)
+            .render_combined(&novel);
+
+        assert!(text.starts_with("お前が神を殺したいなら、とあなたは言った\nふじやま\n\n［＃大見出し］はじめに［＃大見出し終わり］\n"));
+        assert_eq!(text.matches("［＃改ページ］").count(), 1);
+        assert!(text.contains("「ナオキ前［＃「前」に傍点］教祖様のご遺志、しかと承りました。"));
+    }
+}
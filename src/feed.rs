@@ -0,0 +1,282 @@
+use crate::api::HttpClient;
+use crate::export::escape_xml;
+use crate::library::Library;
+use crate::novel_parser::ParsedEpisode;
+use crate::novel_scraper::{Episode, NarouNovelScraper, NovelType};
+use crate::tracker::{fetch_narou_infos, fetch_nocturne_infos};
+use crate::watch::{excerpt, WatchEvent, EXCERPT_CHARS};
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+
+/// フィードの1項目（1話）
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedEntry {
+    pub ncode: String,
+    pub novel_title: String,
+    pub writer: String,
+    pub episode_number: u32,
+    pub subtitle: String,
+    pub url: String,
+    pub excerpt: String,
+    /// 掲載（改稿）日時
+    pub updated: DateTime<FixedOffset>,
+}
+
+impl From<&WatchEvent> for FeedEntry {
+    fn from(event: &WatchEvent) -> Self {
+        Self {
+            ncode: event.ncode.clone(),
+            novel_title: event.title.clone(),
+            writer: event.writer.clone(),
+            episode_number: event.episode_number,
+            subtitle: event.subtitle.clone(),
+            url: event.url.clone(),
+            excerpt: event.excerpt.clone(),
+            updated: DateTime::parse_from_rfc3339(&event.detected_at).unwrap_or_else(|_| Utc::now().fixed_offset()),
+        }
+    }
+}
+
+/// フォローしている作品の新着話のAtomフィード
+#[derive(Debug, Clone)]
+pub struct AtomFeed {
+    pub title: String,
+    /// フィードのID（`urn:narou:feed:…`など変わらない値）
+    pub id: String,
+    /// フィード自身のURL（配信する場合）
+    pub self_url: Option<String>,
+    /// フィード全体の著者（作者名のない項目はこれを使う）
+    pub author: String,
+    entries: Vec<FeedEntry>,
+}
+
+impl AtomFeed {
+    pub fn new(title: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            id: id.into(),
+            self_url: None,
+            author: "web-novel-scraper".to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// 項目を追加（同じ話は新しい方で置き換える）
+    pub fn add(&mut self, entry: FeedEntry) {
+        self.entries
+            .retain(|e| !(e.ncode == entry.ncode && e.episode_number == entry.episode_number));
+        self.entries.push(entry);
+    }
+
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = FeedEntry>) {
+        for entry in entries {
+            self.add(entry);
+        }
+    }
+
+    /// 新しい順の項目
+    pub fn entries(&self) -> Vec<&FeedEntry> {
+        let mut entries: Vec<&FeedEntry> = self.entries.iter().collect();
+        entries.sort_by(|a, b| b.updated.cmp(&a.updated).then(b.episode_number.cmp(&a.episode_number)));
+        entries
+    }
+
+    /// Atom 1.0として出力（`limit`件まで）
+    pub fn render(&self, limit: usize) -> String {
+        let entries = self.entries();
+        let updated = entries
+            .first()
+            .map(|e| e.updated.to_rfc3339())
+            .unwrap_or_else(|| Utc::now().to_rfc3339());
+
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        out.push_str(&format!("<id>{}</id>\n", escape_xml(&self.id)));
+        out.push_str(&format!("<title>{}</title>\n", escape_xml(&self.title)));
+        out.push_str(&format!("<updated>{}</updated>\n", updated));
+        if let Some(url) = &self.self_url {
+            out.push_str(&format!("<link rel=\"self\" href=\"{}\"/>\n", escape_xml(url)));
+        }
+        out.push_str(&format!("<author><name>{}</name></author>\n", escape_xml(&self.author)));
+        out.push_str("<generator>web-novel-scraper</generator>\n");
+
+        for entry in entries.into_iter().take(limit) {
+            out.push_str("<entry>\n");
+            out.push_str(&format!(
+                "<id>urn:narou:{}:{}</id>\n",
+                escape_xml(&entry.ncode),
+                entry.episode_number
+            ));
+            out.push_str(&format!(
+                "<title>{}</title>\n",
+                escape_xml(&format!("{} {}", entry.novel_title, entry.subtitle))
+            ));
+            out.push_str(&format!("<updated>{}</updated>\n", entry.updated.to_rfc3339()));
+            out.push_str(&format!("<link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>\n", escape_xml(&entry.url)));
+            if !entry.writer.is_empty() {
+                out.push_str(&format!("<author><name>{}</name></author>\n", escape_xml(&entry.writer)));
+            }
+            out.push_str(&format!("<summary>{}</summary>\n", escape_xml(&entry.excerpt)));
+            out.push_str("</entry>\n");
+        }
+
+        out.push_str("</feed>\n");
+        out
+    }
+}
+
+/// 目次と本文を取得して、作品の最新`latest`話の項目を作る
+pub async fn fetch_feed_entries(scraper: &NarouNovelScraper, ncode: &str, latest: usize) -> Result<Vec<FeedEntry>> {
+    let index = scraper.fetch_index(ncode).await?;
+
+    // 目次がない作品は短編
+    if index.episode_count() == 0 {
+        let novel = scraper.fetch_all_episodes(ncode, NovelType::ShortStory).await?;
+        let Some(episode) = novel.episodes.first() else {
+            return Ok(Vec::new());
+        };
+        let parsed = ParsedEpisode::parse(episode);
+        // 短編のページには日時がないので、作者と更新日時はAPIから取る
+        let client = HttpClient::from_fetcher(scraper.fetcher().clone());
+        let ncodes = [ncode.to_lowercase()];
+        let info = if scraper.is_nocturne() {
+            fetch_nocturne_infos(&client, &ncodes)
+                .await?
+                .into_iter()
+                .next()
+                .map(|i| (i.writer, i.novelupdated_at.or(i.general_lastup)))
+        } else {
+            fetch_narou_infos(&client, &ncodes)
+                .await?
+                .into_iter()
+                .next()
+                .map(|i| (i.writer, i.novelupdated_at.or(i.general_lastup)))
+        };
+        let (writer, date) = info.with_context(|| format!("{} was not found in the novel API", ncode))?;
+        let updated = date
+            .as_deref()
+            .and_then(parse_api_date)
+            .with_context(|| format!("The novel API has no update date for {}", ncode))?;
+        return Ok(vec![FeedEntry {
+            ncode: ncode.to_string(),
+            novel_title: parsed.subtitle.clone(),
+            writer: writer.filter(|w| !w.is_empty()).unwrap_or(index.author),
+            episode_number: 0,
+            subtitle: String::new(),
+            url: scraper.build_novel_url(ncode, None),
+            excerpt: excerpt(&parsed, EXCERPT_CHARS),
+            updated,
+        }]);
+    }
+
+    let entries: Vec<_> = index.entries().collect();
+    let mut feed_entries = Vec::new();
+    for entry in entries.iter().rev().take(latest) {
+        let episode = scraper.fetch_episode(ncode, entry.episode_number).await?;
+        let parsed = ParsedEpisode::parse(&episode);
+        let date = entry.revised_at.as_deref().unwrap_or(&entry.published_at);
+        feed_entries.push(FeedEntry {
+            ncode: ncode.to_string(),
+            novel_title: index.title.clone(),
+            writer: index.author.clone(),
+            episode_number: entry.episode_number,
+            subtitle: entry.subtitle.clone(),
+            url: scraper.build_novel_url(ncode, Some(entry.episode_number)),
+            excerpt: excerpt(&parsed, EXCERPT_CHARS),
+            updated: parse_narou_date(date).unwrap_or_else(|| Utc::now().fixed_offset()),
+        });
+    }
+
+    Ok(feed_entries)
+}
+
+/// ライブラリに保存済みの最新`latest`話から項目を作る（ネットワークを使わない）
+pub fn library_feed_entries(library: &Library, ncode: &str, latest: usize, nocturne: bool) -> Result<Vec<FeedEntry>> {
+    let novel = library.novel(ncode)?;
+    let episodes = library.episodes_for(ncode)?;
+    let domain = if nocturne || novel.as_ref().is_some_and(|n| n.is_nocturne) {
+        "https://novel18.syosetu.com"
+    } else {
+        "https://ncode.syosetu.com"
+    };
+
+    Ok(episodes
+        .iter()
+        .rev()
+        .take(latest)
+        .map(|record| {
+            let parsed = ParsedEpisode::parse(&Episode {
+                episode_number: record.episode_number,
+                html: record.html.clone(),
+            });
+            FeedEntry {
+                ncode: record.ncode.clone(),
+                novel_title: novel.as_ref().and_then(|n| n.title.clone()).unwrap_or_else(|| record.ncode.clone()),
+                writer: novel.as_ref().and_then(|n| n.writer.clone()).unwrap_or_default(),
+                episode_number: record.episode_number,
+                subtitle: record.subtitle.clone(),
+                url: match record.episode_number {
+                    0 => format!("{}/{}/", domain, record.ncode),
+                    n => format!("{}/{}/{}/", domain, record.ncode, n),
+                },
+                excerpt: excerpt(&parsed, EXCERPT_CHARS),
+                // 再同期のたびに新着扱いにならないよう、最初に保存した日時を使う
+                updated: DateTime::parse_from_rfc3339(&record.first_seen_at).unwrap_or_else(|_| Utc::now().fixed_offset()),
+            }
+        })
+        .collect())
+}
+
+/// 目次の日時（`2016/10/12 13:09`、日本時間）
+fn parse_narou_date(date: &str) -> Option<DateTime<FixedOffset>> {
+    let naive = NaiveDateTime::parse_from_str(date.trim(), "%Y/%m/%d %H:%M").ok()?;
+    FixedOffset::east_opt(9 * 3600)?.from_local_datetime(&naive).single()
+}
+
+/// APIの日時（`2024-01-02 03:04:05`、日本時間）
+fn parse_api_date(date: &str) -> Option<DateTime<FixedOffset>> {
+    let naive = NaiveDateTime::parse_from_str(date.trim(), "%Y-%m-%d %H:%M:%S").ok()?;
+    FixedOffset::east_opt(9 * 3600)?.from_local_datetime(&naive).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ncode: &str, episode_number: u32, updated: &str) -> FeedEntry {
+        FeedEntry {
+            ncode: ncode.to_string(),
+            novel_title: "作品".to_string(),
+            writer: "作者".to_string(),
+            episode_number,
+            subtitle: format!("第{}話 <前編>", episode_number),
+            url: format!("https://ncode.syosetu.com/{}/{}/", ncode, episode_number),
+            excerpt: "本文".to_string(),
+            updated: parse_narou_date(updated).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_render_atom() {
+        let mut feed = AtomFeed::new("新着", "urn:narou:feed:test");
+        feed.self_url = Some("http://localhost:8080/feed.atom".to_string());
+        feed.extend([
+            entry("n0001aa", 1, "2024/01/01 12:00"),
+            entry("n0002bb", 5, "2024/01/03 12:00"),
+            entry("n0001aa", 1, "2024/01/02 12:00"),
+        ]);
+
+        let atom = feed.render(10);
+        assert!(atom.contains("<updated>2024-01-03T12:00:00+09:00</updated>\n<link rel=\"self\""));
+        assert_eq!(atom.matches("<entry>").count(), 2);
+        assert!(atom.find("urn:narou:n0002bb:5").unwrap() < atom.find("urn:narou:n0001aa:1").unwrap());
+        assert!(atom.contains("<title>作品 第5話 &lt;前編&gt;</title>"));
+        assert!(atom.contains("<updated>2024-01-02T12:00:00+09:00</updated>"));
+        assert_eq!(feed.render(1).matches("<entry>").count(), 1);
+        assert!(atom.contains("<author><name>web-novel-scraper</name></author>\n<generator>"));
+
+        assert_eq!(
+            parse_api_date("2024-01-02 03:04:05").unwrap().to_rfc3339(),
+            "2024-01-02T03:04:05+09:00"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use crate::novel_parser::absolute_url;
+use crate::novel_scraper::Illustration;
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 取得元URLと保存ファイル名の対応を記録するファイル
+const MANIFEST_FILE: &str = "manifest.json";
+
+static IMG_SRC_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(<img\b[^>]*?\ssrc=")([^"]+)(")"#).unwrap());
+static LINKED_IMG_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<a\b([^>]*?)\bhref="[^"]*"([^>]*)>(\s*<img\b[^>]*?\ssrc="([^"]+)"[^>]*>\s*)</a>"#).unwrap()
+});
+
+/// 挿絵画像の保存先（内容のハッシュをファイル名にする）
+///
+/// 同じ画像が複数の話や複数のURLから参照されていても1ファイルだけ保存される。
+pub struct IllustrationStore {
+    dir: PathBuf,
+    /// 取得元URL→ファイル名
+    manifest: BTreeMap<String, String>,
+}
+
+impl IllustrationStore {
+    /// 保存先ディレクトリを開く（既存の対応表があれば読み込む）
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest = if manifest_path.exists() {
+            let text = fs::read_to_string(&manifest_path)
+                .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+            serde_json::from_str(&text)
+                .with_context(|| format!("Failed to parse {}", manifest_path.display()))?
+        } else {
+            BTreeMap::new()
+        };
+
+        Ok(Self { dir, manifest })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 保存済みの画像のファイル名（ファイルが消えていればNone）
+    pub fn file_name(&self, url: &str) -> Option<&str> {
+        self.manifest
+            .get(url)
+            .filter(|name| self.dir.join(name).exists())
+            .map(String::as_str)
+    }
+
+    /// まだ保存していないURLだけを返す
+    pub fn missing<'a>(&self, urls: &'a [String]) -> Vec<&'a String> {
+        urls.iter().filter(|url| self.file_name(url).is_none()).collect()
+    }
+
+    /// 画像を保存してファイル名（`{sha256}.{拡張子}`）を返す
+    pub fn store(&mut self, illustration: &Illustration) -> Result<String> {
+        let hash: String = Sha256::digest(&illustration.data)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let name = format!("{}.{}", hash, illustration.extension());
+        let path = self.dir.join(&name);
+
+        if !path.exists() {
+            fs::create_dir_all(&self.dir)
+                .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+            fs::write(&path, &illustration.data)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+
+        self.manifest.insert(illustration.url.clone(), name.clone());
+        self.save_manifest()?;
+        Ok(name)
+    }
+
+    /// 取得元URL→`prefix`付きのローカルパスの対応（エクスポーターの挿絵設定に使う）
+    pub fn local_paths(&self, prefix: &str) -> BTreeMap<String, String> {
+        self.manifest
+            .keys()
+            .filter_map(|url| self.file_name(url).map(|name| (url.clone(), format!("{}{}", prefix, name))))
+            .collect()
+    }
+
+    /// 保存済みの画像を読み込む
+    pub fn load(&self, url: &str) -> Result<Option<Illustration>> {
+        let Some(name) = self.file_name(url) else {
+            return Ok(None);
+        };
+        let data = fs::read(self.dir.join(name))?;
+        Ok(Some(Illustration::new(url, data)))
+    }
+
+    /// 保存済みの画像をURL順に読み込む（保存されていないものは飛ばす）
+    pub fn load_all(&self, urls: &[String]) -> Result<Vec<Illustration>> {
+        let mut illustrations = Vec::new();
+        for url in urls {
+            if let Some(illustration) = self.load(url)? {
+                illustrations.push(illustration);
+            }
+        }
+        Ok(illustrations)
+    }
+
+    fn save_manifest(&self) -> Result<()> {
+        let path = self.dir.join(MANIFEST_FILE);
+        fs::write(&path, serde_json::to_string_pretty(&self.manifest)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// HTML中の`<img src>`と挿絵へのリンクをローカルのパスに書き換える
+///
+/// `paths`は取得元URL（スキーム補完済み）→ローカルパス。対応がない画像はそのまま残す。
+pub fn relink_illustrations(html: &str, paths: &BTreeMap<String, String>) -> String {
+    let relinked = IMG_SRC_RE.replace_all(html, |caps: &Captures| {
+        match paths.get(&absolute_url(&caps[2])) {
+            Some(local) => format!("{}{}{}", &caps[1], local, &caps[3]),
+            None => caps[0].to_string(),
+        }
+    });
+
+    // 挿絵を包むみてみんへのリンクも、オフラインで開けるよう画像そのものに向ける
+    LINKED_IMG_RE
+        .replace_all(&relinked, |caps: &Captures| {
+            if paths.values().any(|local| local == &caps[4]) {
+                format!(r#"<a{}href="{}"{}>{}</a>"#, &caps[1], &caps[4], &caps[2], &caps[3])
+            } else {
+                caps[0].to_string()
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nimage";
+
+    #[test]
+    fn test_store_is_content_addressed() {
+        let dir = std::env::temp_dir().join(format!("wns-illust-{}", std::process::id()));
+        let mut store = IllustrationStore::open(&dir).unwrap();
+
+        let a = store.store(&Illustration::new("https://1.mitemin.net/a.png", PNG.to_vec())).unwrap();
+        let b = store.store(&Illustration::new("https://2.mitemin.net/b.png", PNG.to_vec())).unwrap();
+        assert_eq!(a, b);
+        assert!(a.ends_with(".png"));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2); // 画像1つ + manifest.json
+
+        let reopened = IllustrationStore::open(&dir).unwrap();
+        let urls = vec!["https://1.mitemin.net/a.png".to_string(), "https://3.mitemin.net/c.png".to_string()];
+        assert_eq!(reopened.missing(&urls), vec![&urls[1]]);
+        assert_eq!(reopened.load(&urls[0]).unwrap().unwrap().data, PNG);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_relink_illustrations() {
+        let html = r#"<p id="L1"><a href="//1.mitemin.net/i2/" target="_blank"><img src="//1.mitemin.net/i2.jpg" alt="挿絵" /></a></p><p><img src="https://other.example/x.png"></p>"#;
+        let lazy = r#"<img data-src="//1.mitemin.net/i2.jpg" src="//1.mitemin.net/i2.jpg">"#;
+        let paths = BTreeMap::from([("https://1.mitemin.net/i2.jpg".to_string(), "images/abc.jpg".to_string())]);
+
+        assert_eq!(
+            relink_illustrations(html, &paths),
+            r#"<p id="L1"><a href="images/abc.jpg" target="_blank"><img src="images/abc.jpg" alt="挿絵" /></a></p><p><img src="https://other.example/x.png"></p>"#
+        );
+        // `data-src`は書き換えない
+        assert_eq!(
+            relink_illustrations(lazy, &paths),
+            r#"<img data-src="//1.mitemin.net/i2.jpg" src="images/abc.jpg">"#
+        );
+    }
+}
@@ -1,8 +1,58 @@
+pub mod archive;
+pub mod cache;
+pub mod config;
+pub mod cookies;
 pub mod fetcher;
 pub mod rating_scraper;
+pub mod bookmark_scraper;
+pub mod author;
+pub mod mypage_scraper;
 pub mod novel_scraper;
+pub mod novel_parser;
+pub mod export;
+pub mod feed;
+pub mod illustrations;
+pub mod library;
+pub mod ruby;
+pub mod reactions;
+pub mod reader;
+pub mod revisions;
+pub mod search;
+pub mod tracker;
+pub mod watch;
+pub mod opds;
+pub mod page_classifier;
+pub mod session;
 pub mod api;
 
-pub use fetcher::{FetchOptions, HtmlFetcher, UserAgentMode, RequestDelayConfig};
+pub use archive::{ExportFormat, NovelWriter, SaveOptions};
+pub use cache::CacheConfig;
+pub use config::{Config, Profile, UserAgentSetting};
+pub use cookies::{CookieFileFormat, StoredCookie};
+pub use fetcher::{
+    FetchMetrics, FetchOptions, FetcherConfig, HtmlFetcher, HttpStatusError, RedirectPolicy, RetryPolicy,
+    UserAgentMode, RequestDelayConfig,
+};
 pub use rating_scraper::{NarouRatingScraper, RatingEntry};
-pub use novel_scraper::{NarouNovelScraper, NovelContent, NovelType, Episode};
\ No newline at end of file
+pub use bookmark_scraper::{BookmarkCategory, BookmarkEntry, NarouBookmarkScraper};
+pub use author::{ActivityReport, AuthorProfile, AuthorProfileFetcher};
+pub use mypage_scraper::{AuthorIdentity, AuthorWorks, MypageScraper, WorkSeries};
+pub use feed::{AtomFeed, FeedEntry};
+pub use illustrations::{relink_illustrations, IllustrationStore};
+pub use library::{BookmarkRecord, EpisodeRecord, Library, NovelRecord};
+pub use opds::{Catalog, CatalogBook, OpdsFeed};
+pub use page_classifier::{PageError, PageKind};
+pub use novel_scraper::{NarouNovelScraper, NovelContent, NovelType, Episode, Illustration};
+pub use novel_parser::{IndexChapter, IndexEntry, NovelIndex, Paragraph, ParsedEpisode, strip_site_chrome};
+pub use export::{
+    BlankLines, EpubExporter, EpubMetadata, EpubOptions, HtmlExporter, HtmlOptions, HtmlTemplate, MarkdownExporter,
+    MarkdownOptions, TextExporter, TextOptions, TextStyle,
+};
+pub use reactions::{EpisodeReactions, ReactionSeries};
+pub use reader::{Bookshelf, ReadingPosition, ReadingPositions, SavedNovel};
+pub use revisions::{diff_paragraphs, ParagraphChange, Revision, RevisionStore};
+pub use ruby::{Ruby, RubyMode, RubySegment};
+pub use search::{SearchHit, SearchIndex};
+pub use tracker::{Growth, MetricSnapshot};
+pub use watch::{EventSink, RssSink, StdoutSink, WatchEvent, WatchState, Watcher, WebhookSink};
+pub use session::{LoginCredentials, NarouSession, SessionError};
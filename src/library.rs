@@ -0,0 +1,748 @@
+use crate::api::endpoints::{narou::NarouNovelInfo, nocturne::NocturneNovelInfo, ranking::RankingEntry};
+use crate::novel_parser::{NovelIndex, ParsedEpisode};
+use crate::novel_scraper::{Episode, NovelContent, NovelType};
+use crate::rating_scraper::RatingEntry;
+use crate::revisions::content_hash;
+use crate::tracker::MetricSnapshot;
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// スキーマの変更履歴（`PRAGMA user_version`が適用済みの数）
+///
+/// 既存の手順は書き換えず、変更は末尾に追加する。
+const MIGRATIONS: &[&str] = &[
+    // 1: 初期スキーマ
+    "CREATE TABLE novels (
+        ncode TEXT PRIMARY KEY,
+        title TEXT,
+        writer TEXT,
+        userid INTEGER,
+        is_nocturne INTEGER NOT NULL DEFAULT 0,
+        novel_type INTEGER,
+        end INTEGER,
+        general_all_no INTEGER,
+        general_lastup TEXT,
+        novelupdated_at TEXT,
+        info_json TEXT NOT NULL,
+        recorded_at TEXT NOT NULL
+    );
+    CREATE TABLE novel_snapshots (
+        ncode TEXT NOT NULL,
+        recorded_at TEXT NOT NULL,
+        info_json TEXT NOT NULL,
+        PRIMARY KEY (ncode, recorded_at)
+    );
+    CREATE TABLE episodes (
+        ncode TEXT NOT NULL,
+        episode_number INTEGER NOT NULL,
+        subtitle TEXT NOT NULL,
+        html TEXT NOT NULL,
+        content_hash TEXT NOT NULL,
+        fetched_at TEXT NOT NULL,
+        PRIMARY KEY (ncode, episode_number)
+    );
+    CREATE TABLE ratings (
+        user_id INTEGER NOT NULL,
+        ncode TEXT NOT NULL,
+        rating_point REAL NOT NULL,
+        first_rating_date TEXT NOT NULL,
+        last_rating_date TEXT,
+        recorded_at TEXT NOT NULL,
+        PRIMARY KEY (user_id, ncode)
+    );
+    CREATE TABLE bookmarks (
+        user_id INTEGER NOT NULL,
+        category INTEGER NOT NULL,
+        ncode TEXT NOT NULL,
+        title TEXT,
+        recorded_at TEXT NOT NULL,
+        PRIMARY KEY (user_id, category, ncode)
+    );
+    CREATE TABLE rankings (
+        rtype TEXT NOT NULL,
+        ncode TEXT NOT NULL,
+        rank INTEGER NOT NULL,
+        pt INTEGER NOT NULL,
+        recorded_at TEXT NOT NULL,
+        PRIMARY KEY (rtype, ncode)
+    );
+    CREATE INDEX rankings_by_ncode ON rankings (ncode);",
+    // 2: 指標の時系列
+    "CREATE TABLE metrics (
+        ncode TEXT NOT NULL,
+        recorded_at TEXT NOT NULL,
+        global_point INTEGER,
+        daily_point INTEGER,
+        weekly_point INTEGER,
+        all_point INTEGER,
+        all_hyoka_cnt INTEGER,
+        fav_novel_cnt INTEGER,
+        impression_cnt INTEGER,
+        review_cnt INTEGER,
+        weekly_unique INTEGER,
+        general_all_no INTEGER,
+        PRIMARY KEY (ncode, recorded_at)
+    );",
+    // 3: エピソードを最初に保存した日時（再取得しても変わらない）
+    "ALTER TABLE episodes ADD COLUMN first_seen_at TEXT;
+    UPDATE episodes SET first_seen_at = fetched_at;",
+    // 4: 連載の目次（章立て・サブタイトル・改稿日時）
+    "CREATE TABLE novel_indexes (
+        ncode TEXT PRIMARY KEY,
+        index_json TEXT NOT NULL,
+        recorded_at TEXT NOT NULL
+    );",
+];
+
+/// 保存済みの作品（APIのメタデータの最新版）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NovelRecord {
+    pub ncode: String,
+    pub title: Option<String>,
+    pub writer: Option<String>,
+    pub userid: Option<u32>,
+    pub is_nocturne: bool,
+    pub novel_type: Option<u8>,
+    pub end: Option<u8>,
+    pub general_all_no: Option<u32>,
+    pub general_lastup: Option<String>,
+    pub novelupdated_at: Option<String>,
+    /// APIの応答そのまま
+    pub info: serde_json::Value,
+    pub recorded_at: String,
+}
+
+/// 保存済みのエピソード
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EpisodeRecord {
+    pub ncode: String,
+    pub episode_number: u32,
+    pub subtitle: String,
+    pub html: String,
+    /// サブタイトルと本文の段落から求めたハッシュ（`revisions::content_hash`、16桁の16進数）
+    pub content_hash: String,
+    /// 最後に取得した日時
+    pub fetched_at: String,
+    /// 最初に保存した日時（内容が変わっても更新しない）
+    pub first_seen_at: String,
+}
+
+impl EpisodeRecord {
+    pub fn to_episode(&self) -> Episode {
+        Episode {
+            episode_number: self.episode_number,
+            html: self.html.clone(),
+        }
+    }
+}
+
+/// ブックマーク1件
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BookmarkRecord {
+    pub user_id: u32,
+    /// ブックマークのカテゴリ番号
+    pub category: u32,
+    pub ncode: String,
+    pub title: Option<String>,
+}
+
+/// 取得した作品・本文・評価・ブックマーク・ランキングを保存するSQLiteのライブラリ
+pub struct Library {
+    conn: Connection,
+}
+
+impl Library {
+    /// データベースを開き、未適用のマイグレーションを実行する
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let conn = Connection::open(path).with_context(|| format!("Failed to open library {}", path.display()))?;
+        Self::from_connection(conn)
+    }
+
+    /// メモリ上のデータベース（テスト用）
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        let mut library = Self { conn };
+        library.migrate()?;
+        Ok(library)
+    }
+
+    /// 適用済みのスキーマのバージョン
+    pub fn schema_version(&self) -> Result<u32> {
+        Ok(self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
+    fn migrate(&mut self) -> Result<()> {
+        let current = self.schema_version()? as usize;
+        for (version, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(sql)
+                .with_context(|| format!("Failed to apply library migration {}", version + 1))?;
+            tx.pragma_update(None, "user_version", version as u32 + 1)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    /// なろうAPIのメタデータを保存（履歴として`novel_snapshots`にも残す）
+    pub fn upsert_novel(&self, info: &NarouNovelInfo) -> Result<()> {
+        let ncode = info.ncode.clone().context("Novel info has no ncode")?;
+        self.upsert_novel_row(NovelRecord {
+            ncode: ncode.to_lowercase(),
+            title: info.title.clone(),
+            writer: info.writer.clone(),
+            userid: info.userid,
+            is_nocturne: false,
+            novel_type: info.novel_type,
+            end: info.end,
+            general_all_no: info.general_all_no,
+            general_lastup: info.general_lastup.clone(),
+            novelupdated_at: info.novelupdated_at.clone(),
+            info: serde_json::to_value(info)?,
+            recorded_at: now(),
+        })
+    }
+
+    /// ノクターンAPIのメタデータを保存
+    pub fn upsert_nocturne_novel(&self, info: &NocturneNovelInfo) -> Result<()> {
+        let ncode = info.ncode.clone().context("Novel info has no ncode")?;
+        self.upsert_novel_row(NovelRecord {
+            ncode: ncode.to_lowercase(),
+            title: info.title.clone(),
+            writer: info.writer.clone(),
+            userid: None,
+            is_nocturne: true,
+            novel_type: info.novel_type,
+            end: info.end,
+            general_all_no: info.general_all_no,
+            general_lastup: info.general_lastup.clone(),
+            novelupdated_at: info.novelupdated_at.clone(),
+            info: serde_json::to_value(info)?,
+            recorded_at: now(),
+        })
+    }
+
+    fn upsert_novel_row(&self, novel: NovelRecord) -> Result<()> {
+        let info_json = novel.info.to_string();
+        self.conn.execute(
+            "INSERT INTO novels (ncode, title, writer, userid, is_nocturne, novel_type, end, general_all_no,
+                general_lastup, novelupdated_at, info_json, recorded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT (ncode) DO UPDATE SET
+                title = excluded.title, writer = excluded.writer, userid = excluded.userid,
+                is_nocturne = excluded.is_nocturne, novel_type = excluded.novel_type, end = excluded.end,
+                general_all_no = excluded.general_all_no, general_lastup = excluded.general_lastup,
+                novelupdated_at = excluded.novelupdated_at, info_json = excluded.info_json,
+                recorded_at = excluded.recorded_at",
+            params![
+                novel.ncode,
+                novel.title,
+                novel.writer,
+                novel.userid,
+                novel.is_nocturne,
+                novel.novel_type,
+                novel.end,
+                novel.general_all_no,
+                novel.general_lastup,
+                novel.novelupdated_at,
+                info_json,
+                novel.recorded_at,
+            ],
+        )?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO novel_snapshots (ncode, recorded_at, info_json) VALUES (?1, ?2, ?3)",
+            params![novel.ncode, novel.recorded_at, info_json],
+        )?;
+        Ok(())
+    }
+
+    /// 保存済みの作品
+    pub fn novel(&self, ncode: &str) -> Result<Option<NovelRecord>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT ncode, title, writer, userid, is_nocturne, novel_type, end, general_all_no,
+                    general_lastup, novelupdated_at, info_json, recorded_at
+                 FROM novels WHERE ncode = ?1",
+                [ncode.to_lowercase()],
+                novel_from_row,
+            )
+            .optional()?)
+    }
+
+    /// 保存済みの全作品（ncode順）
+    pub fn novels(&self) -> Result<Vec<NovelRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ncode, title, writer, userid, is_nocturne, novel_type, end, general_all_no,
+                general_lastup, novelupdated_at, info_json, recorded_at
+             FROM novels ORDER BY ncode",
+        )?;
+        let rows = stmt.query_map([], novel_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// 作品のメタデータの履歴（古い順、`(記録日時, APIの応答)`）
+    pub fn novel_snapshots(&self, ncode: &str) -> Result<Vec<(String, serde_json::Value)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT recorded_at, info_json FROM novel_snapshots WHERE ncode = ?1 ORDER BY recorded_at",
+        )?;
+        let rows = stmt.query_map([ncode.to_lowercase()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.map(|row| {
+            let (recorded_at, json) = row?;
+            Ok((recorded_at, serde_json::from_str(&json)?))
+        })
+        .collect()
+    }
+
+    /// エピソードを保存（本文が変わっていなければ取得日時だけ更新し、falseを返す）
+    pub fn upsert_episode(&self, ncode: &str, episode: &Episode) -> Result<bool> {
+        upsert_episode(&self.conn, ncode, episode)
+    }
+
+    /// 取得した全話を保存し、新規・変更のあった話数を返す
+    pub fn upsert_novel_content(&mut self, novel: &NovelContent) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut changed = 0;
+        for episode in &novel.episodes {
+            if upsert_episode(&tx, &novel.ncode, episode)? {
+                changed += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    /// 作品ごとの保存済みの話数
+    pub fn episode_counts(&self) -> Result<BTreeMap<String, usize>> {
+        let mut stmt = self.conn.prepare("SELECT ncode, COUNT(*) FROM episodes GROUP BY ncode")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// エピソードを保存済みの作品のncode
+    pub fn episode_ncodes(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT DISTINCT ncode FROM episodes ORDER BY ncode")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// 保存済みのエピソード（話数順）
+    pub fn episodes_for(&self, ncode: &str) -> Result<Vec<EpisodeRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ncode, episode_number, subtitle, html, content_hash, fetched_at, first_seen_at
+             FROM episodes WHERE ncode = ?1 ORDER BY episode_number",
+        )?;
+        let rows = stmt.query_map([ncode.to_lowercase()], |row| {
+            Ok(EpisodeRecord {
+                ncode: row.get(0)?,
+                episode_number: row.get(1)?,
+                subtitle: row.get(2)?,
+                html: row.get(3)?,
+                content_hash: row.get(4)?,
+                fetched_at: row.get(5)?,
+                first_seen_at: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// 保存済みの全話を作品として読み出す（1話もなければNone）
+    pub fn novel_content(&self, ncode: &str) -> Result<Option<NovelContent>> {
+        let episodes: Vec<Episode> = self.episodes_for(ncode)?.iter().map(EpisodeRecord::to_episode).collect();
+        if episodes.is_empty() {
+            return Ok(None);
+        }
+        let novel_type = match episodes.as_slice() {
+            [only] if only.episode_number == 0 => NovelType::ShortStory,
+            _ => NovelType::Serial { total_episodes: episodes.len() as u32 },
+        };
+        Ok(Some(NovelContent {
+            ncode: ncode.to_lowercase(),
+            novel_type,
+            episodes,
+        }))
+    }
+
+    /// 連載の目次を保存（前回の目次は置き換える）
+    pub fn upsert_index(&self, ncode: &str, index: &NovelIndex) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO novel_indexes (ncode, index_json, recorded_at) VALUES (?1, ?2, ?3)",
+            params![ncode.to_lowercase(), serde_json::to_string(index)?, now()],
+        )?;
+        Ok(())
+    }
+
+    /// 保存済みの目次
+    pub fn index(&self, ncode: &str) -> Result<Option<NovelIndex>> {
+        let json: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT index_json FROM novel_indexes WHERE ncode = ?1",
+                [ncode.to_lowercase()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        json.map(|json| Ok(serde_json::from_str(&json)?)).transpose()
+    }
+
+    /// ユーザーの評価一覧を保存
+    pub fn upsert_ratings(&mut self, user_id: u32, ratings: &[RatingEntry]) -> Result<()> {
+        let recorded_at = now();
+        let tx = self.conn.transaction()?;
+        for rating in ratings {
+            tx.execute(
+                "INSERT OR REPLACE INTO ratings (user_id, ncode, rating_point, first_rating_date, last_rating_date, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    user_id,
+                    rating.ncode.to_lowercase(),
+                    rating.rating_point,
+                    rating.first_rating_date,
+                    rating.last_rating_date,
+                    recorded_at,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// ユーザーの評価一覧
+    pub fn ratings_for(&self, user_id: u32) -> Result<Vec<RatingEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ncode, rating_point, first_rating_date, last_rating_date
+             FROM ratings WHERE user_id = ?1 ORDER BY first_rating_date DESC, ncode",
+        )?;
+        let rows = stmt.query_map([user_id], |row| {
+            Ok(RatingEntry {
+                ncode: row.get(0)?,
+                rating_point: row.get(1)?,
+                first_rating_date: row.get(2)?,
+                last_rating_date: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// ブックマークを保存
+    pub fn upsert_bookmarks(&mut self, bookmarks: &[BookmarkRecord]) -> Result<()> {
+        let recorded_at = now();
+        let tx = self.conn.transaction()?;
+        for bookmark in bookmarks {
+            tx.execute(
+                "INSERT OR REPLACE INTO bookmarks (user_id, category, ncode, title, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    bookmark.user_id,
+                    bookmark.category,
+                    bookmark.ncode.to_lowercase(),
+                    bookmark.title,
+                    recorded_at,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// ユーザーのブックマーク（カテゴリ・ncode順）
+    pub fn bookmarks_for(&self, user_id: u32) -> Result<Vec<BookmarkRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT user_id, category, ncode, title FROM bookmarks WHERE user_id = ?1 ORDER BY category, ncode",
+        )?;
+        let rows = stmt.query_map([user_id], |row| {
+            Ok(BookmarkRecord {
+                user_id: row.get(0)?,
+                category: row.get(1)?,
+                ncode: row.get(2)?,
+                title: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// ランキング（`rtype`は`20240101-d`など）を保存
+    pub fn upsert_ranking(&mut self, rtype: &str, entries: &[RankingEntry]) -> Result<()> {
+        let recorded_at = now();
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM rankings WHERE rtype = ?1", [rtype])?;
+        for entry in entries {
+            tx.execute(
+                "INSERT INTO rankings (rtype, ncode, rank, pt, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![rtype, entry.ncode.to_lowercase(), entry.rank, entry.pt, recorded_at],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 保存済みのランキング（順位順）
+    pub fn ranking(&self, rtype: &str) -> Result<Vec<RankingEntry>> {
+        let mut stmt = self.conn.prepare("SELECT ncode, pt, rank FROM rankings WHERE rtype = ?1 ORDER BY rank")?;
+        let rows = stmt.query_map([rtype], |row| {
+            Ok(RankingEntry {
+                ncode: row.get(0)?,
+                pt: row.get(1)?,
+                rank: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// 指標の記録を追加
+    pub fn record_metrics(&mut self, snapshots: &[MetricSnapshot]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for s in snapshots {
+            tx.execute(
+                "INSERT OR REPLACE INTO metrics (ncode, recorded_at, global_point, daily_point, weekly_point, all_point,
+                    all_hyoka_cnt, fav_novel_cnt, impression_cnt, review_cnt, weekly_unique, general_all_no)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    s.ncode.to_lowercase(),
+                    s.recorded_at,
+                    s.global_point,
+                    s.daily_point,
+                    s.weekly_point,
+                    s.all_point,
+                    s.all_hyoka_cnt,
+                    s.fav_novel_cnt,
+                    s.impression_cnt,
+                    s.review_cnt,
+                    s.weekly_unique,
+                    s.general_all_no,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 作品の指標の時系列（古い順）
+    pub fn metrics_for(&self, ncode: &str) -> Result<Vec<MetricSnapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ncode, recorded_at, global_point, daily_point, weekly_point, all_point, all_hyoka_cnt,
+                fav_novel_cnt, impression_cnt, review_cnt, weekly_unique, general_all_no
+             FROM metrics WHERE ncode = ?1 ORDER BY recorded_at",
+        )?;
+        let rows = stmt.query_map([ncode.to_lowercase()], |row| {
+            Ok(MetricSnapshot {
+                ncode: row.get(0)?,
+                recorded_at: row.get(1)?,
+                global_point: row.get(2)?,
+                daily_point: row.get(3)?,
+                weekly_point: row.get(4)?,
+                all_point: row.get(5)?,
+                all_hyoka_cnt: row.get(6)?,
+                fav_novel_cnt: row.get(7)?,
+                impression_cnt: row.get(8)?,
+                review_cnt: row.get(9)?,
+                weekly_unique: row.get(10)?,
+                general_all_no: row.get(11)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// 指標を記録したことのある作品のncode
+    pub fn tracked_ncodes(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT DISTINCT ncode FROM metrics ORDER BY ncode")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+fn upsert_episode(conn: &Connection, ncode: &str, episode: &Episode) -> Result<bool> {
+    let ncode = ncode.to_lowercase();
+    // 生のHTMLはリアクション欄の`data-token`などが毎回変わるので、本文だけで比べる
+    let parsed = ParsedEpisode::parse(episode);
+    let hash = content_hash(&parsed);
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT content_hash FROM episodes WHERE ncode = ?1 AND episode_number = ?2",
+            params![ncode, episode.episode_number],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if existing.as_deref() == Some(hash.as_str()) {
+        conn.execute(
+            "UPDATE episodes SET fetched_at = ?3 WHERE ncode = ?1 AND episode_number = ?2",
+            params![ncode, episode.episode_number, now()],
+        )?;
+        return Ok(false);
+    }
+
+    let subtitle = parsed.subtitle;
+    conn.execute(
+        "INSERT INTO episodes (ncode, episode_number, subtitle, html, content_hash, fetched_at, first_seen_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+         ON CONFLICT (ncode, episode_number) DO UPDATE SET
+             subtitle = excluded.subtitle, html = excluded.html,
+             content_hash = excluded.content_hash, fetched_at = excluded.fetched_at",
+        params![ncode, episode.episode_number, subtitle, episode.html, hash, now()],
+    )?;
+    Ok(true)
+}
+
+fn novel_from_row(row: &Row) -> rusqlite::Result<NovelRecord> {
+    let info_json: String = row.get(10)?;
+    Ok(NovelRecord {
+        ncode: row.get(0)?,
+        title: row.get(1)?,
+        writer: row.get(2)?,
+        userid: row.get(3)?,
+        is_nocturne: row.get(4)?,
+        novel_type: row.get(5)?,
+        end: row.get(6)?,
+        general_all_no: row.get(7)?,
+        general_lastup: row.get(8)?,
+        novelupdated_at: row.get(9)?,
+        info: serde_json::from_str(&info_json).unwrap_or(serde_json::Value::Null),
+        recorded_at: row.get(11)?,
+    })
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel_scraper::NovelType;
+
+    #[test]
+    fn test_migrations_are_idempotent() {
+        let path = std::env::temp_dir().join(format!("wns-library-{}.sqlite3", std::process::id()));
+        let library = Library::open(&path).unwrap();
+        assert_eq!(library.schema_version().unwrap(), MIGRATIONS.len() as u32);
+        drop(library);
+
+        let reopened = Library::open(&path).unwrap();
+        assert_eq!(reopened.schema_version().unwrap(), MIGRATIONS.len() as u32);
+        drop(reopened);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_novels_and_episodes() {
+        let mut library = Library::open_in_memory().unwrap();
+        let info = NarouNovelInfo {
+            ncode: Some("N7775DO".to_string()),
+            title: Some("お前が神を殺したいなら、とあなたは言った".to_string()),
+            general_all_no: Some(2),
+            ..Default::default()
+        };
+        library.upsert_novel(&info).unwrap();
+        library.upsert_novel(&NarouNovelInfo { general_all_no: Some(3), ..info }).unwrap();
+
+        let novel = library.novel("n7775do").unwrap().unwrap();
+        assert_eq!(novel.general_all_no, Some(3));
+        assert_eq!(novel.info["title"], "お前が神を殺したいなら、とあなたは言った");
+        assert!(!library.novel_snapshots("n7775do").unwrap().is_empty());
+
+        let episode = |n: u32, text: &str| Episode {
+            episode_number: n,
+            html: format!(
+                r#"<h1 class="p-novel__title">第{}話</h1><div class="p-novel__body"><div class="js-novel-text p-novel__text"><p id="L1">{}</p></div></div>"#,
+                n, text
+            ),
+        };
+        let content = NovelContent {
+            ncode: "n7775do".to_string(),
+            novel_type: NovelType::Serial { total_episodes: 2 },
+            episodes: vec![episode(2, "二"), episode(1, "一")],
+        };
+        assert_eq!(library.upsert_novel_content(&content).unwrap(), 2);
+        let first_seen = library.episodes_for("n7775do").unwrap()[1].first_seen_at.clone();
+        assert_eq!(library.upsert_novel_content(&content).unwrap(), 0);
+        assert!(library.upsert_episode("n7775do", &episode(2, "改稿")).unwrap());
+
+        // リアクション欄のトークンだけが違う再取得は変更なし
+        let with_token = |token: &str| Episode {
+            episode_number: 2,
+            html: format!(
+                r#"{}<div class="p-reaction__emoticon" data-token="{}"></div>"#,
+                episode(2, "改稿").html,
+                token
+            ),
+        };
+        library.upsert_episode("n7775do", &with_token("e1aff641")).unwrap();
+        assert!(!library.upsert_episode("n7775do", &with_token("9c0b2d77")).unwrap());
+
+        let episodes = library.episodes_for("n7775do").unwrap();
+        assert_eq!(episodes.iter().map(|e| e.episode_number).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(episodes[0].subtitle, "第1話");
+        assert!(episodes[1].html.contains("改稿"));
+        // 再取得・改稿しても最初に保存した日時は変わらない
+        assert_eq!(episodes[1].first_seen_at, first_seen);
+        assert_ne!(episodes[1].fetched_at, first_seen);
+
+        assert_eq!(library.index("n7775do").unwrap(), None);
+        let index = NovelIndex::parse(&std::fs::read_to_string("target_pages/narou/novel_list/n7775do.html").unwrap());
+        library.upsert_index("N7775DO", &index).unwrap();
+        assert_eq!(library.index("n7775do").unwrap(), Some(index));
+    }
+
+    #[test]
+    fn test_ratings_bookmarks_rankings() {
+        let mut library = Library::open_in_memory().unwrap();
+        library
+            .upsert_ratings(
+                1,
+                &[RatingEntry {
+                    ncode: "n0001aa".to_string(),
+                    rating_point: 4.5,
+                    first_rating_date: "2024/01/01".to_string(),
+                    last_rating_date: None,
+                }],
+            )
+            .unwrap();
+        assert_eq!(library.ratings_for(1).unwrap()[0].rating_point, 4.5);
+
+        let bookmark = BookmarkRecord { user_id: 1, category: 2, ncode: "n0001aa".to_string(), title: None };
+        library.upsert_bookmarks(&[bookmark.clone(), bookmark.clone()]).unwrap();
+        assert_eq!(library.bookmarks_for(1).unwrap(), vec![bookmark]);
+
+        let entry = |ncode: &str, rank| RankingEntry { ncode: ncode.to_string(), pt: 100, rank };
+        library.upsert_ranking("20240101-d", &[entry("n0002aa", 2), entry("n0001aa", 1)]).unwrap();
+        library.upsert_ranking("20240101-d", &[entry("n0003aa", 1)]).unwrap();
+        assert_eq!(library.ranking("20240101-d").unwrap().iter().map(|e| e.ncode.as_str()).collect::<Vec<_>>(), vec!["n0003aa"]);
+    }
+
+    #[test]
+    fn test_metrics_series() {
+        let mut library = Library::open_in_memory().unwrap();
+        let snapshot = |recorded_at: &str, global_point| MetricSnapshot {
+            ncode: "n0001aa".to_string(),
+            recorded_at: recorded_at.to_string(),
+            global_point: Some(global_point),
+            daily_point: None,
+            weekly_point: None,
+            all_point: None,
+            all_hyoka_cnt: None,
+            fav_novel_cnt: Some(3),
+            impression_cnt: None,
+            review_cnt: None,
+            weekly_unique: None,
+            general_all_no: Some(10),
+        };
+        let later = snapshot("2024-01-02T00:00:00+00:00", 200);
+        let earlier = snapshot("2024-01-01T00:00:00+00:00", 100);
+        library.record_metrics(&[later.clone(), earlier.clone()]).unwrap();
+
+        assert_eq!(library.metrics_for("N0001AA").unwrap(), vec![earlier, later]);
+        assert_eq!(library.tracked_ncodes().unwrap(), vec!["n0001aa"]);
+    }
+}
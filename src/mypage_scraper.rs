@@ -0,0 +1,233 @@
+use crate::bookmark_scraper::element_text;
+use crate::page_classifier::check_page;
+use crate::{BookmarkEntry, NarouBookmarkScraper};
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::Serialize;
+
+static USERID_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"//mypage\.syosetu\.com/(?:[^?#]*/userid/)?(\d+)/").unwrap());
+static XID_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"//xmypage\.syosetu\.com/(?:[^?#]*/xid/)?(x\d{4}[a-z]{1,2})/").unwrap());
+
+/// マイページのヘッダーから分かる作者の情報
+///
+/// なろうのマイページではユーザーID、ノクターン系のXマイページではXIDが入る。
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AuthorIdentity {
+    pub userid: Option<u32>,
+    pub xid: Option<String>,
+    pub name: Option<String>,
+    /// ユーザー名の読み（`p-userheader__username-info`）
+    pub yomikata: Option<String>,
+}
+
+/// 作品一覧の中のシリーズ
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkSeries {
+    /// シリーズのコード（`s1765j`など）
+    pub code: String,
+    pub title: Option<String>,
+    /// 一覧に載っている順のncode
+    pub ncodes: Vec<String>,
+}
+
+/// 作者の作品タブの内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuthorWorks {
+    pub author: AuthorIdentity,
+    /// マイページに表示される順の作品（一覧の形式はブックマークと同じ）
+    pub works: Vec<BookmarkEntry>,
+    /// シリーズごとのまとまり（最初に出てくる順）
+    pub series: Vec<WorkSeries>,
+}
+
+impl AuthorWorks {
+    /// 作品が属するシリーズ
+    pub fn series_of(&self, ncode: &str) -> Option<&WorkSeries> {
+        let ncode = ncode.to_lowercase();
+        self.series.iter().find(|series| series.ncodes.contains(&ncode))
+    }
+}
+
+/// なろうのマイページ・ノクターンのXマイページの作品一覧のスクレイパー
+pub struct MypageScraper {
+    fetcher: crate::HtmlFetcher,
+    is_nocturne: bool,
+}
+
+impl MypageScraper {
+    /// なろうのマイページ（ユーザーIDで指定）
+    pub fn new(fetcher: crate::HtmlFetcher) -> Self {
+        Self {
+            fetcher,
+            is_nocturne: false,
+        }
+    }
+
+    /// ノクターン系のXマイページ（XIDで指定）
+    pub fn new_nocturne(fetcher: crate::HtmlFetcher) -> Self {
+        Self {
+            fetcher,
+            is_nocturne: true,
+        }
+    }
+
+    /// 作品一覧のURL（`user`はなろうならユーザーID、ノクターンならXID）
+    pub fn build_works_url(&self, user: &str, page: u32) -> String {
+        if self.is_nocturne {
+            format!("https://xmypage.syosetu.com/mypage/novellist/xid/{}/?p={}", user, page)
+        } else {
+            format!("https://mypage.syosetu.com/mypage/novellist/userid/{}/?p={}", user, page)
+        }
+    }
+
+    /// 作品一覧を全ページ取得し、シリーズごとにまとめる
+    pub async fn fetch_works(&self, user: &str) -> Result<AuthorWorks> {
+        let mut author = None;
+        let mut works = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let url = self.build_works_url(user, page);
+            eprintln!("Fetching works page {}: {}", page, url);
+
+            let html = self.fetcher.fetch(&url).await
+                .with_context(|| format!("Failed to fetch works page {}", page))?;
+            check_page(&html, &url)?;
+
+            if author.is_none() {
+                author = Some(parse_author(&html));
+            }
+            let entries = NarouBookmarkScraper::parse_bookmark_page(&html);
+            if entries.is_empty() {
+                break;
+            }
+            works.extend(entries);
+
+            if !NarouBookmarkScraper::has_next_page(&html) {
+                break;
+            }
+            page += 1;
+        }
+
+        let mut author = author.unwrap_or_default();
+        // ヘッダーから取れなくても、指定したIDは分かっている
+        if self.is_nocturne {
+            author.xid.get_or_insert_with(|| user.to_lowercase());
+        } else if author.userid.is_none() {
+            author.userid = user.parse().ok();
+        }
+
+        Ok(AuthorWorks {
+            author,
+            series: group_series(&works),
+            works,
+        })
+    }
+}
+
+/// マイページのヘッダー（`p-userheader`）から作者の情報を抽出
+pub fn parse_author(html: &str) -> AuthorIdentity {
+    let document = Html::parse_document(html);
+    let name_selector = Selector::parse(".p-userheader__username").unwrap();
+    let info_selector = Selector::parse(".p-userheader__username-info").unwrap();
+    let tab_selector = Selector::parse(".p-userheader__tab-list-item a").unwrap();
+
+    let tabs: Vec<&str> = document.select(&tab_selector).filter_map(|a| a.value().attr("href")).collect();
+    let capture = |re: &Regex| {
+        tabs.iter()
+            .find_map(|href| re.captures(href).map(|c| c[1].to_string()))
+    };
+
+    AuthorIdentity {
+        userid: capture(&USERID_RE).and_then(|id| id.parse().ok()),
+        xid: capture(&XID_RE),
+        name: document.select(&name_selector).next().map(element_text).filter(|t| !t.is_empty()),
+        yomikata: document.select(&info_selector).next().map(element_text).filter(|t| !t.is_empty()),
+    }
+}
+
+/// 作品をシリーズごとにまとめる（シリーズに属さない作品は含めない）
+pub fn group_series(works: &[BookmarkEntry]) -> Vec<WorkSeries> {
+    let mut series: Vec<WorkSeries> = Vec::new();
+    for work in works {
+        let Some(code) = &work.series_code else {
+            continue;
+        };
+        match series.iter_mut().find(|s| &s.code == code) {
+            Some(group) => group.ncodes.push(work.ncode.clone()),
+            None => series.push(WorkSeries {
+                code: code.clone(),
+                title: work.series_title.clone(),
+                ncodes: vec![work.ncode.clone()],
+            }),
+        }
+    }
+    series
+}
+
+/// 作品のリンク先から作者のマイページのIDを取り出す（`https://mypage.syosetu.com/440344/`など）
+pub fn author_id_from_url(url: &str) -> Option<String> {
+    USERID_RE
+        .captures(url)
+        .or_else(|| XID_RE.captures(url))
+        .map(|c| c[1].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_author() {
+        let html = fs::read_to_string("target_pages/narou/bookmarks/59791-1-1.html").unwrap();
+        assert_eq!(
+            parse_author(&html),
+            AuthorIdentity {
+                userid: Some(59791),
+                xid: None,
+                name: Some("黒留ハガネ".to_string()),
+                yomikata: Some("くろどめはがね".to_string()),
+            }
+        );
+
+        let html = fs::read_to_string("target_pages/nocturne/bookmarks/x9487b-1-1.html").unwrap();
+        let author = parse_author(&html);
+        assert_eq!(author.userid, None);
+        assert_eq!(author.xid.as_deref(), Some("x9487b"));
+        assert_eq!(author.name.as_deref(), Some("百均"));
+
+        assert_eq!(author_id_from_url("https://mypage.syosetu.com/440344/").as_deref(), Some("440344"));
+        assert_eq!(author_id_from_url("https://xmypage.syosetu.com/x9487b/").as_deref(), Some("x9487b"));
+        assert_eq!(
+            author_id_from_url("https://mypage.syosetu.com/mypage/novellist/userid/59791/?p=2").as_deref(),
+            Some("59791")
+        );
+        assert_eq!(author_id_from_url("https://ncode.syosetu.com/n8383kn/"), None);
+    }
+
+    #[test]
+    fn test_group_series() {
+        // 作品タブの一覧はブックマークと同じ`c-novel-list`の形式
+        let html = fs::read_to_string("target_pages/narou/bookmarks/59791-1-1.html").unwrap();
+        let works = NarouBookmarkScraper::parse_bookmark_page(&html);
+        let series = group_series(&works);
+
+        assert_eq!(series[0].code, "s1765j");
+        assert_eq!(series[0].title.as_deref(), Some("ウルオール図書館の蔵書"));
+        assert_eq!(series[0].ncodes[0], "n8383kn");
+        let grouped: usize = series.iter().map(|s| s.ncodes.len()).sum();
+        assert_eq!(grouped, works.iter().filter(|w| w.series_code.is_some()).count());
+
+        let works = AuthorWorks {
+            author: parse_author(&html),
+            series,
+            works,
+        };
+        assert_eq!(works.series_of("N8383KN").map(|s| s.code.as_str()), Some("s1765j"));
+    }
+}
@@ -0,0 +1,436 @@
+use crate::novel_scraper::Episode;
+use crate::ruby::{extract_rubies, parse_ruby_html, Ruby, RubySegment};
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+/// 本文以外のサイトの部品（広告・表示調整・リアクション・ブックマーク・SNSボタン）
+pub const SITE_CHROME_SELECTORS: &[&str] = &[
+    ".c-ad",
+    ".p-adjust-layout",
+    ".js-adjust-layout-illustflag",
+    ".p-reaction",
+    ".p-bookmark-bar",
+    ".p-bookmark-modal",
+    ".c-modal",
+    ".c-bookmark-button",
+    ".c-sns",
+    ".c-sns__button",
+];
+
+/// ページからサイトの部品を取り除く
+pub fn strip_site_chrome(document: &mut Html) {
+    for selector in SITE_CHROME_SELECTORS {
+        let selector = Selector::parse(selector).unwrap();
+        let ids: Vec<_> = document.select(&selector).map(|e| e.id()).collect();
+        for id in ids {
+            if let Some(mut node) = document.tree.get_mut(id) {
+                node.detach();
+            }
+        }
+    }
+}
+
+/// 本文の1段落（`<p id="L1">`など）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paragraph {
+    /// 段落のid（本文は`L1`、前書きは`Lp1`、後書きは`La1`）
+    pub id: String,
+    /// 段落の中身のHTML（ルビ・挿絵のタグを含む）
+    pub html: String,
+}
+
+impl Paragraph {
+    /// 空行（`<p><br /></p>`）かどうか
+    pub fn is_blank(&self) -> bool {
+        if self.html.contains("<img") {
+            return false;
+        }
+        let fragment = Html::parse_fragment(&self.html);
+        fragment.root_element().text().all(|t| t.trim().is_empty())
+    }
+
+    /// ルビを含む断片に分解（タグは取り除かれる）
+    pub fn segments(&self) -> Vec<RubySegment> {
+        parse_ruby_html(&self.html)
+    }
+}
+
+/// エピソードHTMLから本文部分を取り出した結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedEpisode {
+    /// エピソード番号（短編の場合は0）
+    pub episode_number: u32,
+    /// サブタイトル（短編の場合は作品タイトル）
+    pub subtitle: String,
+    /// 前書き
+    pub preface: Vec<Paragraph>,
+    /// 本文
+    pub body: Vec<Paragraph>,
+    /// 後書き
+    pub afterword: Vec<Paragraph>,
+}
+
+impl ParsedEpisode {
+    /// エピソードHTMLを解析
+    pub fn parse(episode: &Episode) -> Self {
+        let mut document = Html::parse_document(&episode.html);
+        strip_site_chrome(&mut document);
+        let title_selector = Selector::parse("h1.p-novel__title").unwrap();
+        let text_selector = Selector::parse(".p-novel__body .js-novel-text").unwrap();
+
+        let subtitle = document
+            .select(&title_selector)
+            .next()
+            .map(|e| normalize_text(&e.text().collect::<String>()))
+            .unwrap_or_default();
+
+        let mut parsed = Self {
+            episode_number: episode.episode_number,
+            subtitle,
+            preface: Vec::new(),
+            body: Vec::new(),
+            afterword: Vec::new(),
+        };
+
+        for block in document.select(&text_selector) {
+            let classes: Vec<&str> = block.value().classes().collect();
+            let paragraphs = collect_paragraphs(block);
+            if classes.contains(&"p-novel__text--preface") {
+                parsed.preface.extend(paragraphs);
+            } else if classes.contains(&"p-novel__text--afterword") {
+                parsed.afterword.extend(paragraphs);
+            } else {
+                parsed.body.extend(paragraphs);
+            }
+        }
+
+        parsed
+    }
+
+    /// 前書き・本文・後書きの全段落
+    pub fn paragraphs(&self) -> impl Iterator<Item = &Paragraph> {
+        self.preface.iter().chain(&self.body).chain(&self.afterword)
+    }
+
+    /// 前書き・本文・後書きのルビ（出現順）
+    pub fn rubies(&self) -> Vec<Ruby> {
+        self.paragraphs().flat_map(|p| extract_rubies(&p.html)).collect()
+    }
+
+    /// 本文中の挿絵のURL（出現順・重複なし）
+    pub fn illustration_urls(&self) -> Vec<String> {
+        let img_selector = Selector::parse("img").unwrap();
+        let mut urls = Vec::new();
+
+        for paragraph in self.paragraphs() {
+            let fragment = Html::parse_fragment(&paragraph.html);
+            for img in fragment.select(&img_selector) {
+                if let Some(src) = img.value().attr("src") {
+                    let url = absolute_url(src);
+                    if !urls.contains(&url) {
+                        urls.push(url);
+                    }
+                }
+            }
+        }
+
+        urls
+    }
+}
+
+/// 目次の1話分
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub episode_number: u32,
+    pub subtitle: String,
+    /// 掲載日時（`2016/10/12 13:09`）
+    pub published_at: String,
+    /// 改稿日時（改稿されていなければNone）
+    pub revised_at: Option<String>,
+}
+
+/// 目次の章（章タイトルのない作品は`title`がNoneの章1つになる）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexChapter {
+    pub title: Option<String>,
+    pub episodes: Vec<IndexEntry>,
+}
+
+/// 小説の目次ページ
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct NovelIndex {
+    pub title: String,
+    pub author: String,
+    /// 作者のマイページURL
+    pub author_url: Option<String>,
+    /// あらすじ（改行は`\n`）
+    pub summary: String,
+    pub chapters: Vec<IndexChapter>,
+    /// 次のページがあるか（目次は100話ごとにページ分割される）
+    pub has_next_page: bool,
+}
+
+impl NovelIndex {
+    /// 目次ページのHTMLを解析
+    pub fn parse(html: &str) -> Self {
+        let document = Html::parse_document(html);
+        let title_selector = Selector::parse("h1.p-novel__title").unwrap();
+        let author_selector = Selector::parse(".p-novel__author").unwrap();
+        let link_selector = Selector::parse("a").unwrap();
+        let summary_selector = Selector::parse("#novel_ex").unwrap();
+        let next_selector = Selector::parse("a.c-pager__item--next").unwrap();
+        let item_selector = Selector::parse(".p-eplist .p-eplist__chapter-title, .p-eplist .p-eplist__sublist").unwrap();
+        let subtitle_selector = Selector::parse("a.p-eplist__subtitle").unwrap();
+        let update_selector = Selector::parse(".p-eplist__update").unwrap();
+        let revised_selector = Selector::parse("span[title]").unwrap();
+
+        let title = document
+            .select(&title_selector)
+            .next()
+            .map(|e| normalize_text(&e.text().collect::<String>()))
+            .unwrap_or_default();
+
+        let author_element = document.select(&author_selector).next();
+        let author = author_element
+            .map(|e| {
+                let text = normalize_text(&e.text().collect::<String>());
+                text.trim_start_matches("作者：").trim().to_string()
+            })
+            .unwrap_or_default();
+        let author_url = author_element
+            .and_then(|e| e.select(&link_selector).next())
+            .and_then(|a| a.value().attr("href"))
+            .map(String::from);
+
+        let summary = document
+            .select(&summary_selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_string())
+            .unwrap_or_default();
+
+        let mut chapters: Vec<IndexChapter> = Vec::new();
+        for item in document.select(&item_selector) {
+            if item.value().classes().any(|c| c == "p-eplist__chapter-title") {
+                chapters.push(IndexChapter {
+                    title: Some(normalize_text(&item.text().collect::<String>())),
+                    episodes: Vec::new(),
+                });
+                continue;
+            }
+
+            let Some(link) = item.select(&subtitle_selector).next() else { continue };
+            let Some(episode_number) = link.value().attr("href").and_then(episode_number_from_href) else {
+                continue;
+            };
+            let update = item.select(&update_selector).next();
+            let published_at = update
+                .map(|u| {
+                    u.text()
+                        .map(str::trim)
+                        .find(|t| !t.is_empty())
+                        .unwrap_or("")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            let revised_at = update
+                .and_then(|u| u.select(&revised_selector).next())
+                .and_then(|s| s.value().attr("title"))
+                .map(|t| t.trim_end_matches("改稿").trim().to_string());
+
+            if chapters.is_empty() {
+                chapters.push(IndexChapter { title: None, episodes: Vec::new() });
+            }
+            chapters.last_mut().unwrap().episodes.push(IndexEntry {
+                episode_number,
+                subtitle: normalize_text(&link.text().collect::<String>()),
+                published_at,
+                revised_at,
+            });
+        }
+
+        Self {
+            title,
+            author,
+            author_url,
+            summary,
+            chapters,
+            has_next_page: document.select(&next_selector).next().is_some(),
+        }
+    }
+
+    /// 次のページの目次を連結する
+    ///
+    /// ページの先頭が章の途中から始まる場合は、直前の章に続けて追加する。
+    pub fn merge(&mut self, next: NovelIndex) {
+        for chapter in next.chapters {
+            match (chapter.title.is_none(), self.chapters.last_mut()) {
+                (true, Some(last)) => last.episodes.extend(chapter.episodes),
+                _ => self.chapters.push(chapter),
+            }
+        }
+        self.has_next_page = next.has_next_page;
+    }
+
+    /// 全話数
+    pub fn episode_count(&self) -> usize {
+        self.chapters.iter().map(|c| c.episodes.len()).sum()
+    }
+
+    /// 全エピソードを掲載順に列挙
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.chapters.iter().flat_map(|c| c.episodes.iter())
+    }
+
+    /// 指定した話から始まる章のタイトル
+    pub fn chapter_starting_at(&self, episode_number: u32) -> Option<&str> {
+        self.chapters.iter()
+            .find(|c| c.episodes.first().map(|e| e.episode_number) == Some(episode_number))
+            .and_then(|c| c.title.as_deref())
+    }
+
+    /// 目次上のサブタイトル
+    pub fn subtitle_of(&self, episode_number: u32) -> Option<&str> {
+        self.entries()
+            .find(|e| e.episode_number == episode_number)
+            .map(|e| e.subtitle.as_str())
+    }
+}
+
+/// `/n7775do/2/` のようなリンクから話数を取り出す
+fn episode_number_from_href(href: &str) -> Option<u32> {
+    href.trim_end_matches('/').rsplit('/').next()?.parse().ok()
+}
+
+/// `//img.example/...`のようなスキーム省略URLを補完
+pub(crate) fn absolute_url(src: &str) -> String {
+    if src.starts_with("//") {
+        format!("https:{}", src)
+    } else {
+        src.to_string()
+    }
+}
+
+/// 改行・前後の空白を除いた1行のテキストに（全角スペースは残す）
+fn normalize_text(text: &str) -> String {
+    text.split(|c: char| c.is_ascii_whitespace())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn collect_paragraphs(block: ElementRef) -> Vec<Paragraph> {
+    let p_selector = Selector::parse("p").unwrap();
+    block
+        .select(&p_selector)
+        .map(|p| Paragraph {
+            id: p.value().id().unwrap_or("").to_string(),
+            html: p.inner_html(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(path: &str, episode_number: u32) -> Episode {
+        Episode {
+            episode_number,
+            html: std::fs::read_to_string(format!("target_pages/{}", path)).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_episode() {
+        let parsed = ParsedEpisode::parse(&episode("narou/novel/n7775do-2.html", 2));
+
+        assert_eq!(parsed.subtitle, "天真歴19年　9月14日");
+        assert_eq!(parsed.preface.len(), 1);
+        assert_eq!(parsed.preface[0].id, "Lp1");
+        assert_eq!(parsed.body.first().unwrap().id, "L1");
+        assert_eq!(parsed.body.last().unwrap().id, "L111");
+        assert!(parsed.body[1].is_blank());
+        assert!(!parsed.body[0].is_blank());
+
+        let ruby = parsed.body.iter().find(|p| p.id == "L78").unwrap();
+        assert!(ruby.html.contains("<ruby>前<rp>(</rp><rt>・</rt><rp>)</rp></ruby>"));
+        assert!(parsed.rubies().iter().any(|r| r.base == "前" && r.is_emphasis));
+    }
+
+    #[test]
+    fn test_illustration_urls() {
+        let html = r#"<h1 class="p-novel__title">挿絵</h1><div class="p-novel__body"><div class="js-novel-text p-novel__text">
+<p id="L1"><a href="//12345.mitemin.net/i678/"><img src="//12345.mitemin.net/userpageimage/viewimagebig/icode/i678/" alt="挿絵(By みてみん)" /></a></p>
+</div><div class="js-novel-text p-novel__text p-novel__text--afterword"><p id="La1">あとがき</p></div></div>"#;
+        let parsed = ParsedEpisode::parse(&Episode { episode_number: 1, html: html.to_string() });
+
+        assert_eq!(parsed.afterword[0].id, "La1");
+        assert!(!parsed.body[0].is_blank());
+        assert_eq!(
+            parsed.illustration_urls(),
+            vec!["https://12345.mitemin.net/userpageimage/viewimagebig/icode/i678/".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_strip_site_chrome() {
+        let html = r#"<div class="p-novel__body"><div class="js-novel-text p-novel__text">
+<p id="L1">本文</p><div class="c-ad"><p>広告</p></div>
+<a class="c-sns__button" href="https://twitter.com/"><p>共有</p></a></div></div>"#;
+        let parsed = ParsedEpisode::parse(&Episode { episode_number: 1, html: html.to_string() });
+        assert_eq!(parsed.body.len(), 1);
+
+        let mut document = Html::parse_document(&std::fs::read_to_string("target_pages/narou/novel/n7775do-2.html").unwrap());
+        strip_site_chrome(&mut document);
+        let remaining = document.root_element().html();
+        assert!(!remaining.contains("p-reaction__emoticon"));
+        assert!(!remaining.contains("p-bookmark-modal"));
+        assert!(!remaining.contains("p-adjust-layout__console"));
+        assert!(remaining.contains(r#"<p id="L1">"#));
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let html = std::fs::read_to_string("target_pages/narou/novel_list/n7775do.html").unwrap();
+        let index = NovelIndex::parse(&html);
+
+        assert_eq!(index.title, "お前が神を殺したいなら、とあなたは言った");
+        assert_eq!(index.author, "ふじやま");
+        assert_eq!(index.author_url.as_deref(), Some("https://mypage.syosetu.com/440344/"));
+        assert!(index.summary.starts_with("「ナオキ、君に神を殺してほしいんだ」"));
+        assert!(index.has_next_page);
+        assert_eq!(index.episode_count(), 100);
+
+        let first = &index.chapters[0];
+        assert_eq!(first.title.as_deref(), Some("はじめに"));
+        assert_eq!(first.episodes[0].episode_number, 1);
+        assert_eq!(first.episodes[0].subtitle, "登場人物/教会派閥紹介");
+        assert_eq!(first.episodes[0].published_at, "2017/04/07 02:42");
+        assert_eq!(first.episodes[0].revised_at.as_deref(), Some("2022/08/20 11:17"));
+    }
+
+    #[test]
+    fn test_merge_index_pages() {
+        let html = std::fs::read_to_string("target_pages/narou/novel_list/n7775do.html").unwrap();
+        let mut index = NovelIndex::parse(&html);
+        let chapters = index.chapters.len();
+
+        let next = NovelIndex {
+            chapters: vec![IndexChapter {
+                title: None,
+                episodes: vec![IndexEntry {
+                    episode_number: 101,
+                    subtitle: "続き".to_string(),
+                    published_at: "2020/01/01 00:00".to_string(),
+                    revised_at: None,
+                }],
+            }],
+            ..Default::default()
+        };
+        index.merge(next);
+
+        assert_eq!(index.chapters.len(), chapters);
+        assert_eq!(index.entries().last().unwrap().episode_number, 101);
+        assert!(!index.has_next_page);
+    }
+}
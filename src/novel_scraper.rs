@@ -1,3 +1,6 @@
+use crate::illustrations::IllustrationStore;
+use crate::novel_parser::{NovelIndex, ParsedEpisode};
+use crate::page_classifier::check_page;
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 
@@ -30,11 +33,57 @@ pub struct NovelContent {
     pub episodes: Vec<Episode>,
 }
 
+/// 取得した挿絵画像
+#[derive(Debug, Clone)]
+pub struct Illustration {
+    /// 取得元URL
+    pub url: String,
+    pub data: Vec<u8>,
+    /// 画像の先頭バイトから判定したMIMEタイプ
+    pub media_type: &'static str,
+}
+
+impl Illustration {
+    pub fn new(url: impl Into<String>, data: Vec<u8>) -> Self {
+        let media_type = sniff_image_type(&data);
+        Self {
+            url: url.into(),
+            data,
+            media_type,
+        }
+    }
+
+    /// MIMEタイプに対応する拡張子
+    pub fn extension(&self) -> &'static str {
+        match self.media_type {
+            "image/png" => "png",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            _ => "jpg",
+        }
+    }
+}
+
+/// 画像データの形式を判定（不明な場合はJPEG扱い）
+fn sniff_image_type(data: &[u8]) -> &'static str {
+    if data.starts_with(b"\x89PNG") {
+        "image/png"
+    } else if data.starts_with(b"GIF8") {
+        "image/gif"
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        "image/webp"
+    } else {
+        "image/jpeg"
+    }
+}
+
 /// なろう小説本文スクレイパー
 pub struct NarouNovelScraper {
     fetcher: crate::HtmlFetcher,
     /// ノクターンかどうか
     is_nocturne: bool,
+    /// 取得先のドメインの差し替え（テスト用のサーバーなど）
+    base_url: Option<String>,
 }
 
 impl NarouNovelScraper {
@@ -43,6 +92,7 @@ impl NarouNovelScraper {
         Self {
             fetcher,
             is_nocturne: false,
+            base_url: None,
         }
     }
 
@@ -51,9 +101,24 @@ impl NarouNovelScraper {
         Self {
             fetcher,
             is_nocturne: true,
+            base_url: None,
         }
     }
 
+    /// `https://ncode.syosetu.com`などの代わりに使うURL（末尾の`/`なし）
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn fetcher(&self) -> &crate::HtmlFetcher {
+        &self.fetcher
+    }
+
+    pub fn is_nocturne(&self) -> bool {
+        self.is_nocturne
+    }
+
     /// 小説の全エピソードを取得
     pub async fn fetch_all_episodes(
         &self,
@@ -66,10 +131,11 @@ impl NarouNovelScraper {
             NovelType::ShortStory => {
                 // 短編の場合は1ページのみ
                 let url = self.build_novel_url(ncode, None);
-                println!("Fetching short story: {}", url);
+                eprintln!("Fetching short story: {}", url);
                 
                 let html = self.fetcher.fetch(&url).await
                     .with_context(|| format!("Failed to fetch short story {}", ncode))?;
+                check_page(&html, &url)?;
                 
                 episodes.push(Episode {
                     episode_number: 0,
@@ -80,10 +146,11 @@ impl NarouNovelScraper {
                 // 長編の場合は各エピソードを順次取得
                 for episode_num in 1..=*total_episodes {
                     let url = self.build_novel_url(ncode, Some(episode_num));
-                    println!("Fetching episode {}/{}: {}", episode_num, total_episodes, url);
+                    eprintln!("Fetching episode {}/{}: {}", episode_num, total_episodes, url);
                     
                     let html = self.fetcher.fetch(&url).await
                         .with_context(|| format!("Failed to fetch episode {} of {}", episode_num, ncode))?;
+                    check_page(&html, &url)?;
                     
                     episodes.push(Episode {
                         episode_number: episode_num,
@@ -92,7 +159,7 @@ impl NarouNovelScraper {
                     
                     // 進捗表示
                     if episode_num % 10 == 0 {
-                        println!("Progress: {}/{} episodes fetched", episode_num, total_episodes);
+                        eprintln!("Progress: {}/{} episodes fetched", episode_num, total_episodes);
                     }
                 }
             }
@@ -105,12 +172,78 @@ impl NarouNovelScraper {
         })
     }
 
-    /// 小説URLを構築
-    fn build_novel_url(&self, ncode: &str, episode_number: Option<u32>) -> String {
-        let base_domain = if self.is_nocturne {
-            "https://novel18.syosetu.com"
-        } else {
-            "https://ncode.syosetu.com"
+    /// 目次ページを全ページ取得して連結
+    pub async fn fetch_index(&self, ncode: &str) -> Result<NovelIndex> {
+        let base_url = self.build_novel_url(ncode, None);
+        let mut index: Option<NovelIndex> = None;
+        let mut page = 1;
+
+        loop {
+            let url = if page == 1 {
+                base_url.clone()
+            } else {
+                format!("{}?p={}", base_url, page)
+            };
+            eprintln!("Fetching index page {}: {}", page, url);
+
+            let html = self.fetcher.fetch(&url).await
+                .with_context(|| format!("Failed to fetch index page {} of {}", page, ncode))?;
+            check_page(&html, &url)?;
+
+            let parsed = NovelIndex::parse(&html);
+            let has_next = parsed.has_next_page;
+            match index.as_mut() {
+                Some(index) => index.merge(parsed),
+                None => index = Some(parsed),
+            }
+
+            if !has_next {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(index.unwrap_or_default())
+    }
+
+    /// 挿絵画像を取得（URL順・失敗したものはスキップ）
+    pub async fn fetch_illustrations(&self, urls: &[String]) -> Vec<Illustration> {
+        let mut illustrations = Vec::new();
+
+        for url in urls {
+            eprintln!("Fetching illustration: {}", url);
+            match self.fetcher.fetch_bytes(url, &[]).await {
+                Ok(data) => illustrations.push(Illustration::new(url.clone(), data)),
+                Err(e) => eprintln!("Failed to fetch illustration {}: {}", url, e),
+            }
+        }
+
+        illustrations
+    }
+
+    /// 本文中の挿絵を取得して保存（保存済みの画像は取得しない）
+    ///
+    /// 取得は本文と同じ`HtmlFetcher`を通るため、リクエスト間隔の設定が適用される。
+    /// 戻り値は新たに保存した画像の数。
+    pub async fn download_illustrations(&self, novel: &NovelContent, store: &mut IllustrationStore) -> Result<usize> {
+        let urls = novel.illustration_urls();
+        let missing: Vec<String> = store.missing(&urls).into_iter().cloned().collect();
+
+        let mut saved = 0;
+        for illustration in self.fetch_illustrations(&missing).await {
+            store.store(&illustration)?;
+            saved += 1;
+        }
+
+        Ok(saved)
+    }
+
+    /// 小説URLを構築（`episode_number`がNoneなら目次・短編のURL）
+    pub fn build_novel_url(&self, ncode: &str, episode_number: Option<u32>) -> String {
+        let base_domain = match &self.base_url {
+            Some(base_url) => base_url.as_str(),
+            None if self.is_nocturne => "https://novel18.syosetu.com",
+            None => "https://ncode.syosetu.com",
         };
 
         match episode_number {
@@ -119,6 +252,17 @@ impl NarouNovelScraper {
         }
     }
 
+    /// 1話だけ取得（進捗を表示しない、0は短編の本文）
+    pub async fn fetch_episode(&self, ncode: &str, episode_number: u32) -> Result<Episode> {
+        // 短編は話数のないURLにある（`/{ncode}/0/`は存在しない）
+        let url = self.build_novel_url(ncode, Some(episode_number).filter(|n| *n != 0));
+        let html = self.fetcher.fetch(&url).await
+            .with_context(|| format!("Failed to fetch episode {} of {}", episode_number, ncode))?;
+        check_page(&html, &url)?;
+
+        Ok(Episode { episode_number, html })
+    }
+
     /// 一括取得（バッチ処理用）
     pub async fn fetch_episodes_batch(
         &self,
@@ -129,15 +273,9 @@ impl NarouNovelScraper {
 
         for episode_num in episode_numbers {
             let url = self.build_novel_url(ncode, Some(episode_num));
-            println!("Fetching episode {}: {}", episode_num, url);
+            eprintln!("Fetching episode {}: {}", episode_num, url);
             
-            let html = self.fetcher.fetch(&url).await
-                .with_context(|| format!("Failed to fetch episode {} of {}", episode_num, ncode))?;
-            
-            episodes_map.insert(episode_num, Episode {
-                episode_number: episode_num,
-                html,
-            });
+            episodes_map.insert(episode_num, self.fetch_episode(ncode, episode_num).await?);
         }
 
         Ok(episodes_map)
@@ -167,6 +305,19 @@ impl NovelContent {
         }
     }
 
+    /// 全話の挿絵のURL（出現順・重複なし）
+    pub fn illustration_urls(&self) -> Vec<String> {
+        let mut urls = Vec::new();
+        for episode in &self.episodes {
+            for url in ParsedEpisode::parse(episode).illustration_urls() {
+                if !urls.contains(&url) {
+                    urls.push(url);
+                }
+            }
+        }
+        urls
+    }
+
     /// 総文字数を概算（HTML含む）
     pub fn total_size_bytes(&self) -> usize {
         self.episodes.iter()
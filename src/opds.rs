@@ -0,0 +1,580 @@
+use crate::export::{escape_xml, EpubExporter, EpubMetadata, EpubOptions};
+use crate::library::{Library, NovelRecord};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// なろうのジャンルコード
+pub const GENRES: &[(u16, &str)] = &[
+    (101, "異世界〔恋愛〕"),
+    (102, "現実世界〔恋愛〕"),
+    (201, "ハイファンタジー〔ファンタジー〕"),
+    (202, "ローファンタジー〔ファンタジー〕"),
+    (301, "純文学〔文芸〕"),
+    (302, "ヒューマンドラマ〔文芸〕"),
+    (303, "歴史〔文芸〕"),
+    (304, "推理〔文芸〕"),
+    (305, "ホラー〔文芸〕"),
+    (306, "アクション〔文芸〕"),
+    (307, "コメディー〔文芸〕"),
+    (401, "VRゲーム〔SF〕"),
+    (402, "宇宙〔SF〕"),
+    (403, "空想科学〔SF〕"),
+    (404, "パニック〔SF〕"),
+    (9901, "童話〔その他〕"),
+    (9902, "詩〔その他〕"),
+    (9903, "エッセイ〔その他〕"),
+    (9904, "リプレイ〔その他〕"),
+    (9999, "その他〔その他〕"),
+    (9801, "ノンジャンル〔ノンジャンル〕"),
+];
+
+/// ノクターン系の掲載サイト（`nocgenre`）
+pub const NOCGENRES: &[(u8, &str)] = &[
+    (1, "ノクターンノベルズ(男性向け)"),
+    (2, "ムーンライトノベルズ(女性向け)"),
+    (3, "ムーンライトノベルズ(BL)"),
+    (4, "ミッドナイトノベルズ(大人向け)"),
+];
+
+const NAVIGATION_TYPE: &str = "application/atom+xml;profile=opds-catalog;kind=navigation";
+const ACQUISITION_TYPE: &str = "application/atom+xml;profile=opds-catalog;kind=acquisition";
+
+pub fn genre_name(code: u16) -> Option<&'static str> {
+    GENRES.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+}
+
+pub fn nocgenre_name(code: u8) -> Option<&'static str> {
+    NOCGENRES.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+}
+
+/// カタログに載せる1作品
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CatalogBook {
+    pub ncode: String,
+    pub title: String,
+    pub writer: String,
+    pub summary: String,
+    /// ジャンルの識別子（なろうは`g101`、ノクターン系は`n1`）
+    pub genre: Option<String>,
+    pub completed: bool,
+    /// 最終更新日時（RFC 3339）
+    pub updated: String,
+    pub episode_count: usize,
+    pub is_nocturne: bool,
+}
+
+impl CatalogBook {
+    fn from_record(record: &NovelRecord, episode_count: usize) -> Self {
+        let info = &record.info;
+        let genre = if record.is_nocturne {
+            info["nocgenre"].as_u64().map(|g| format!("n{}", g))
+        } else {
+            info["genre"].as_u64().filter(|g| *g != 0).map(|g| format!("g{}", g))
+        };
+
+        Self {
+            ncode: record.ncode.clone(),
+            title: record.title.clone().unwrap_or_else(|| record.ncode.clone()),
+            writer: record.writer.clone().unwrap_or_default(),
+            summary: info["story"].as_str().unwrap_or_default().to_string(),
+            genre,
+            // 短編（novel_type=2）は完結扱い
+            completed: record.end == Some(0) || record.novel_type == Some(2),
+            updated: record
+                .novelupdated_at
+                .as_deref()
+                .or(record.general_lastup.as_deref())
+                .and_then(api_date_to_rfc3339)
+                .unwrap_or_else(|| record.recorded_at.clone()),
+            episode_count,
+            is_nocturne: record.is_nocturne,
+        }
+    }
+
+    fn minimal(ncode: &str, episode_count: usize) -> Self {
+        Self {
+            ncode: ncode.to_string(),
+            title: ncode.to_string(),
+            writer: String::new(),
+            summary: String::new(),
+            genre: None,
+            completed: false,
+            updated: chrono::Utc::now().to_rfc3339(),
+            episode_count,
+            is_nocturne: false,
+        }
+    }
+}
+
+/// ジャンルの識別子の表示名
+pub fn genre_label(key: &str) -> Option<&'static str> {
+    if let Some(code) = key.strip_prefix('g') {
+        genre_name(code.parse().ok()?)
+    } else {
+        nocgenre_name(key.strip_prefix('n')?.parse().ok()?)
+    }
+}
+
+/// ライブラリのうち本文を保存してある作品の一覧
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    books: Vec<CatalogBook>,
+}
+
+impl Catalog {
+    pub fn from_library(library: &Library) -> Result<Self> {
+        let counts = library.episode_counts()?;
+        let records: BTreeMap<String, NovelRecord> =
+            library.novels()?.into_iter().map(|r| (r.ncode.clone(), r)).collect();
+
+        let books = counts
+            .iter()
+            .map(|(ncode, count)| match records.get(ncode) {
+                Some(record) => CatalogBook::from_record(record, *count),
+                None => CatalogBook::minimal(ncode, *count),
+            })
+            .collect();
+        Ok(Self { books })
+    }
+
+    pub fn books(&self) -> &[CatalogBook] {
+        &self.books
+    }
+
+    pub fn book(&self, ncode: &str) -> Option<&CatalogBook> {
+        let ncode = ncode.to_lowercase();
+        self.books.iter().find(|b| b.ncode == ncode)
+    }
+
+    /// 作者名と作品数（名前順）
+    pub fn authors(&self) -> Vec<(String, usize)> {
+        let mut authors: BTreeMap<String, usize> = BTreeMap::new();
+        for book in self.books.iter().filter(|b| !b.writer.is_empty()) {
+            *authors.entry(book.writer.clone()).or_insert(0) += 1;
+        }
+        authors.into_iter().collect()
+    }
+
+    pub fn by_author(&self, writer: &str) -> Vec<&CatalogBook> {
+        self.books.iter().filter(|b| b.writer == writer).collect()
+    }
+
+    /// ジャンルの識別子・表示名・作品数（コード順）
+    pub fn genres(&self) -> Vec<(String, String, usize)> {
+        let mut genres: BTreeMap<String, usize> = BTreeMap::new();
+        for book in &self.books {
+            if let Some(genre) = &book.genre {
+                *genres.entry(genre.clone()).or_insert(0) += 1;
+            }
+        }
+        genres
+            .into_iter()
+            .map(|(key, count)| {
+                let label = genre_label(&key).unwrap_or(&key).to_string();
+                (key, label, count)
+            })
+            .collect()
+    }
+
+    pub fn by_genre(&self, key: &str) -> Vec<&CatalogBook> {
+        self.books.iter().filter(|b| b.genre.as_deref() == Some(key)).collect()
+    }
+
+    /// 更新の新しい順に`limit`件
+    pub fn recently_updated(&self, limit: usize) -> Vec<&CatalogBook> {
+        let mut books: Vec<&CatalogBook> = self.books.iter().collect();
+        // 日本時間のAPIの日時とUTCの記録日時が混ざるので、文字列ではなく日時で比べる
+        books.sort_by_cached_key(|b| std::cmp::Reverse(chrono::DateTime::parse_from_rfc3339(&b.updated).ok()));
+        books.truncate(limit);
+        books
+    }
+
+    pub fn completed(&self) -> Vec<&CatalogBook> {
+        self.books.iter().filter(|b| b.completed).collect()
+    }
+}
+
+/// ナビゲーションの1項目
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavEntry {
+    pub title: String,
+    /// カタログのルートからの相対パス
+    pub path: String,
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpdsFeedKind {
+    Navigation(Vec<NavEntry>),
+    Acquisition(Vec<CatalogBook>),
+}
+
+/// OPDSの1フィード（1.2のAtomと2.0のJSONの両方で出力できる）
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpdsFeed {
+    pub title: String,
+    /// カタログのルートからの相対パス（ルートは空文字列）
+    pub path: String,
+    pub kind: OpdsFeedKind,
+}
+
+impl OpdsFeed {
+    pub fn root() -> Self {
+        let nav = |title: &str, path: &str| NavEntry {
+            title: title.to_string(),
+            path: path.to_string(),
+            count: None,
+        };
+        Self {
+            title: "ライブラリ".to_string(),
+            path: String::new(),
+            kind: OpdsFeedKind::Navigation(vec![
+                nav("作者別", "authors"),
+                nav("ジャンル別", "genres"),
+                nav("最近更新された作品", "recent"),
+                nav("完結済み", "completed"),
+            ]),
+        }
+    }
+
+    pub fn authors(catalog: &Catalog) -> Self {
+        Self {
+            title: "作者別".to_string(),
+            path: "authors".to_string(),
+            kind: OpdsFeedKind::Navigation(
+                catalog
+                    .authors()
+                    .into_iter()
+                    .map(|(writer, count)| NavEntry {
+                        path: format!("authors/{}", encode_path_segment(&writer)),
+                        title: writer,
+                        count: Some(count),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn author(catalog: &Catalog, writer: &str) -> Self {
+        Self {
+            title: writer.to_string(),
+            path: format!("authors/{}", encode_path_segment(writer)),
+            kind: acquisition(catalog.by_author(writer)),
+        }
+    }
+
+    pub fn genres(catalog: &Catalog) -> Self {
+        Self {
+            title: "ジャンル別".to_string(),
+            path: "genres".to_string(),
+            kind: OpdsFeedKind::Navigation(
+                catalog
+                    .genres()
+                    .into_iter()
+                    .map(|(key, label, count)| NavEntry {
+                        title: label,
+                        path: format!("genres/{}", key),
+                        count: Some(count),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn genre(catalog: &Catalog, key: &str) -> Self {
+        Self {
+            title: genre_label(key).unwrap_or(key).to_string(),
+            path: format!("genres/{}", encode_path_segment(key)),
+            kind: acquisition(catalog.by_genre(key)),
+        }
+    }
+
+    pub fn recent(catalog: &Catalog, limit: usize) -> Self {
+        Self {
+            title: "最近更新された作品".to_string(),
+            path: "recent".to_string(),
+            kind: acquisition(catalog.recently_updated(limit)),
+        }
+    }
+
+    pub fn completed(catalog: &Catalog) -> Self {
+        Self {
+            title: "完結済み".to_string(),
+            path: "completed".to_string(),
+            kind: acquisition(catalog.completed()),
+        }
+    }
+
+    /// OPDS 1.2で配信するときのContent-Type
+    pub fn opds1_content_type(&self) -> &'static str {
+        match self.kind {
+            OpdsFeedKind::Navigation(_) => NAVIGATION_TYPE,
+            OpdsFeedKind::Acquisition(_) => ACQUISITION_TYPE,
+        }
+    }
+
+    /// OPDS 1.2（Atom）として出力
+    ///
+    /// `base`はカタログのURL（`/opds`など）、`books`はEPUBのURLの前置き（`/books`など）。
+    pub fn to_opds1(&self, base: &str, books: &str) -> String {
+        let self_href = join_path(base, &self.path);
+        let feed_type = self.opds1_content_type();
+        let updated = chrono::Utc::now().to_rfc3339();
+
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/terms/\" xmlns:opds=\"http://opds-spec.org/2010/catalog\">\n");
+        out.push_str(&format!("<id>urn:narou:opds:{}</id>\n", escape_xml(&self.path)));
+        out.push_str(&format!("<title>{}</title>\n", escape_xml(&self.title)));
+        out.push_str(&format!("<updated>{}</updated>\n", updated));
+        out.push_str(&format!("<link rel=\"self\" href=\"{}\" type=\"{}\"/>\n", escape_xml(&self_href), feed_type));
+        out.push_str(&format!("<link rel=\"start\" href=\"{}\" type=\"{}\"/>\n", escape_xml(base), NAVIGATION_TYPE));
+
+        match &self.kind {
+            OpdsFeedKind::Navigation(entries) => {
+                for entry in entries {
+                    let href = join_path(base, &entry.path);
+                    let kind = if is_navigation_path(&entry.path) { NAVIGATION_TYPE } else { ACQUISITION_TYPE };
+                    out.push_str("<entry>\n");
+                    out.push_str(&format!("<title>{}</title>\n", escape_xml(&entry.title)));
+                    out.push_str(&format!("<id>urn:narou:opds:{}</id>\n", escape_xml(&entry.path)));
+                    out.push_str(&format!("<updated>{}</updated>\n", updated));
+                    if let Some(count) = entry.count {
+                        out.push_str(&format!("<content type=\"text\">{}作品</content>\n", count));
+                    }
+                    out.push_str(&format!("<link rel=\"subsection\" href=\"{}\" type=\"{}\"/>\n", escape_xml(&href), kind));
+                    out.push_str("</entry>\n");
+                }
+            }
+            OpdsFeedKind::Acquisition(entries) => {
+                for book in entries {
+                    out.push_str("<entry>\n");
+                    out.push_str(&format!("<title>{}</title>\n", escape_xml(&book.title)));
+                    out.push_str(&format!("<id>urn:narou:{}</id>\n", escape_xml(&book.ncode)));
+                    out.push_str(&format!("<updated>{}</updated>\n", escape_xml(&book.updated)));
+                    if !book.writer.is_empty() {
+                        out.push_str(&format!("<author><name>{}</name></author>\n", escape_xml(&book.writer)));
+                    }
+                    out.push_str("<dc:language>ja</dc:language>\n");
+                    if let Some(label) = book.genre.as_deref().and_then(genre_label) {
+                        out.push_str(&format!("<category term=\"{}\" label=\"{}\"/>\n", escape_xml(book.genre.as_deref().unwrap_or_default()), escape_xml(label)));
+                    }
+                    if !book.summary.is_empty() {
+                        out.push_str(&format!("<summary>{}</summary>\n", escape_xml(&book.summary)));
+                    }
+                    out.push_str(&format!(
+                        "<link rel=\"http://opds-spec.org/acquisition\" href=\"{}\" type=\"application/epub+zip\"/>\n",
+                        escape_xml(&format!("{}/{}.epub", books.trim_end_matches('/'), book.ncode))
+                    ));
+                    out.push_str("</entry>\n");
+                }
+            }
+        }
+
+        out.push_str("</feed>\n");
+        out
+    }
+
+    /// OPDS 2.0（JSON）として出力
+    pub fn to_opds2(&self, base: &str, books: &str) -> serde_json::Value {
+        let mut feed = json!({
+            "metadata": { "title": self.title },
+            "links": [
+                { "rel": "self", "href": join_path(base, &self.path), "type": "application/opds+json" },
+                { "rel": "start", "href": base, "type": "application/opds+json" },
+            ],
+        });
+
+        match &self.kind {
+            OpdsFeedKind::Navigation(entries) => {
+                feed["navigation"] = entries
+                    .iter()
+                    .map(|entry| {
+                        let mut link = json!({
+                            "href": join_path(base, &entry.path),
+                            "title": entry.title,
+                            "type": "application/opds+json",
+                        });
+                        if let Some(count) = entry.count {
+                            link["properties"] = json!({ "numberOfItems": count });
+                        }
+                        link
+                    })
+                    .collect();
+            }
+            OpdsFeedKind::Acquisition(entries) => {
+                feed["metadata"]["numberOfItems"] = json!(entries.len());
+                feed["publications"] = entries
+                    .iter()
+                    .map(|book| {
+                        let mut metadata = json!({
+                            "@type": "http://schema.org/Book",
+                            "identifier": format!("urn:narou:{}", book.ncode),
+                            "title": book.title,
+                            "language": "ja",
+                            "modified": book.updated,
+                        });
+                        if !book.writer.is_empty() {
+                            metadata["author"] = json!(book.writer);
+                        }
+                        if !book.summary.is_empty() {
+                            metadata["description"] = json!(book.summary);
+                        }
+                        if let Some(label) = book.genre.as_deref().and_then(genre_label) {
+                            metadata["subject"] = json!([{ "name": label, "code": book.genre }]);
+                        }
+                        json!({
+                            "metadata": metadata,
+                            "links": [{
+                                "rel": "http://opds-spec.org/acquisition",
+                                "href": format!("{}/{}.epub", books.trim_end_matches('/'), book.ncode),
+                                "type": "application/epub+zip",
+                            }],
+                        })
+                    })
+                    .collect();
+            }
+        }
+
+        feed
+    }
+}
+
+/// ライブラリに保存した本文からEPUBを作る（本文がなければNone）
+pub fn build_epub(library: &Library, ncode: &str, options: EpubOptions) -> Result<Option<Vec<u8>>> {
+    let Some(novel) = library.novel_content(ncode)? else {
+        return Ok(None);
+    };
+    let metadata = match library.novel(ncode)? {
+        Some(record) => EpubMetadata::from_novel_record(&record),
+        None => EpubMetadata::new(&novel.ncode, &novel.ncode),
+    };
+    Ok(Some(EpubExporter::new(metadata).options(options).build(&novel)?))
+}
+
+fn acquisition(books: Vec<&CatalogBook>) -> OpdsFeedKind {
+    OpdsFeedKind::Acquisition(books.into_iter().cloned().collect())
+}
+
+/// 子がナビゲーションになるパス（`authors`・`genres`）
+fn is_navigation_path(path: &str) -> bool {
+    matches!(path, "authors" | "genres")
+}
+
+fn join_path(base: &str, path: &str) -> String {
+    if path.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}/{}", base.trim_end_matches('/'), path)
+    }
+}
+
+/// URLのパスの1区切りとしてエンコード（作者名など）
+fn encode_path_segment(text: &str) -> String {
+    let mut out = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// APIの日時（`2024-01-02 03:04:05`、日本時間）をRFC 3339に
+fn api_date_to_rfc3339(date: &str) -> Option<String> {
+    let naive = chrono::NaiveDateTime::parse_from_str(date.trim(), "%Y-%m-%d %H:%M:%S").ok()?;
+    let jst = chrono::FixedOffset::east_opt(9 * 3600)?;
+    Some(naive.and_local_timezone(jst).single()?.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::endpoints::narou::NarouNovelInfo;
+    use crate::novel_scraper::{Episode, NovelContent, NovelType};
+
+    fn library() -> Library {
+        let mut library = Library::open_in_memory().unwrap();
+        let novels = [
+            ("n0001aa", "作品A", "作者/甲", 201, 0, "2024-01-02 00:00:00"),
+            ("n0002bb", "作品B", "作者/甲", 101, 1, "2024-01-03 00:00:00"),
+            ("n0003cc", "作品C", "乙", 201, 1, "2024-01-01 00:00:00"),
+        ];
+        for (ncode, title, writer, genre, end, updated) in novels {
+            library
+                .upsert_novel(&NarouNovelInfo {
+                    ncode: Some(ncode.to_string()),
+                    title: Some(title.to_string()),
+                    writer: Some(writer.to_string()),
+                    genre: Some(genre),
+                    end: Some(end),
+                    novel_type: Some(1),
+                    novelupdated_at: Some(updated.to_string()),
+                    ..Default::default()
+                })
+                .unwrap();
+            library
+                .upsert_novel_content(&NovelContent {
+                    ncode: ncode.to_string(),
+                    novel_type: NovelType::Serial { total_episodes: 1 },
+                    episodes: vec![Episode {
+                        episode_number: 1,
+                        html: r#"<h1 class="p-novel__title">第一話</h1><div class="p-novel__body"><div class="js-novel-text p-novel__text"><p id="L1">本文</p></div></div>"#.to_string(),
+                    }],
+                })
+                .unwrap();
+        }
+        library
+    }
+
+    #[test]
+    fn test_catalog_navigation() {
+        let catalog = Catalog::from_library(&library()).unwrap();
+        assert_eq!(catalog.authors(), vec![("乙".to_string(), 1), ("作者/甲".to_string(), 2)]);
+        assert_eq!(
+            catalog.genres(),
+            vec![
+                ("g101".to_string(), "異世界〔恋愛〕".to_string(), 1),
+                ("g201".to_string(), "ハイファンタジー〔ファンタジー〕".to_string(), 2),
+            ]
+        );
+        assert_eq!(catalog.recently_updated(2).iter().map(|b| b.ncode.as_str()).collect::<Vec<_>>(), vec!["n0002bb", "n0001aa"]);
+        assert_eq!(catalog.completed().len(), 1);
+        assert_eq!(catalog.book("N0001AA").unwrap().updated, "2024-01-02T00:00:00+09:00");
+
+        // 日本時間とUTCが混ざっていても実際の日時の順
+        let book = |ncode: &str, updated: &str| CatalogBook { updated: updated.to_string(), ..CatalogBook::minimal(ncode, 1) };
+        let catalog = Catalog {
+            books: vec![book("n0001aa", "2024-01-02T08:00:00+09:00"), book("n0002bb", "2024-01-01T23:30:00+00:00")],
+        };
+        assert_eq!(catalog.recently_updated(2)[0].ncode, "n0002bb");
+    }
+
+    #[test]
+    fn test_opds_output() {
+        let catalog = Catalog::from_library(&library()).unwrap();
+
+        let authors = OpdsFeed::authors(&catalog).to_opds1("/opds", "/books");
+        assert!(authors.contains(r#"<link rel="subsection" href="/opds/authors/%E4%BD%9C%E8%80%85%2F%E7%94%B2" type="application/atom+xml;profile=opds-catalog;kind=acquisition"/>"#));
+
+        let genre = OpdsFeed::genre(&catalog, "g201").to_opds1("/opds", "/books");
+        assert_eq!(genre.matches("<entry>").count(), 2);
+        assert!(genre.contains(r#"<link rel="http://opds-spec.org/acquisition" href="/books/n0001aa.epub" type="application/epub+zip"/>"#));
+
+        let root = OpdsFeed::root().to_opds2("/opds2", "/books");
+        assert_eq!(root["navigation"][0]["href"], "/opds2/authors");
+        let completed = OpdsFeed::completed(&catalog).to_opds2("/opds2", "/books");
+        assert_eq!(completed["publications"][0]["metadata"]["title"], "作品A");
+        assert_eq!(completed["publications"][0]["links"][0]["href"], "/books/n0001aa.epub");
+    }
+
+    #[test]
+    fn test_build_epub() {
+        let library = library();
+        let epub = build_epub(&library, "n0001aa", EpubOptions::default()).unwrap().unwrap();
+        assert!(epub.starts_with(b"PK"));
+        assert!(build_epub(&library, "n9999zz", EpubOptions::default()).unwrap().is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+/// 取得したページの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageKind {
+    /// 通常のページ（本文・目次・マイページなど）
+    Normal,
+    /// メンテナンス中の告知ページ
+    Maintenance,
+    /// 削除済み・存在しない作品のエラーページ
+    NovelDeleted,
+    /// ログインを求めるページ
+    LoginRequired,
+    /// ノクターンの年齢確認ページ
+    AgeGate,
+}
+
+/// ステータス200で返ってくるエラーページ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageError {
+    NovelDeleted(String),
+    Maintenance(String),
+    LoginRequired(String),
+    AgeGate(String),
+}
+
+impl fmt::Display for PageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageError::NovelDeleted(url) => write!(f, "Novel has been deleted or does not exist: {}", url),
+            PageError::Maintenance(url) => write!(f, "Site is under maintenance: {}", url),
+            PageError::LoginRequired(url) => write!(f, "Login required: {}", url),
+            PageError::AgeGate(url) => write!(f, "Age verification required (over18 cookie not set): {}", url),
+        }
+    }
+}
+
+impl std::error::Error for PageError {}
+
+/// 通常ページにだけ含まれる要素のクラス
+const CONTENT_MARKERS: &[&str] = &[
+    "p-novel__body",
+    "p-novel__title",
+    "p-eplist",
+    "p-novelhyoka-list",
+    "p-userheader",
+    "c-novel-list",
+];
+
+const MAINTENANCE_PHRASES: &[&str] = &["メンテナンス中", "メンテナンスを行っております"];
+
+const DELETED_PHRASES: &[&str] = &[
+    "作品は存在しません",
+    "小説が見つかりません",
+    "作品は削除されました",
+    "掲載を終了しました",
+];
+
+const AGE_GATE_MARKERS: &[&str] = &["ageauth", r#"id="yes18""#, "年齢確認"];
+
+/// ページの種類を判定
+///
+/// 本文・目次などの要素があるページは、本文中にエラー文言と同じ語句があっても通常ページとみなす。
+pub fn classify_page(html: &str) -> PageKind {
+    if CONTENT_MARKERS.iter().any(|m| html.contains(m)) {
+        return PageKind::Normal;
+    }
+
+    let title = extract_title(html);
+
+    if MAINTENANCE_PHRASES.iter().any(|p| title.contains(p) || html.contains(p)) {
+        return PageKind::Maintenance;
+    }
+    if AGE_GATE_MARKERS.iter().any(|m| html.contains(m)) {
+        return PageKind::AgeGate;
+    }
+    if DELETED_PHRASES.iter().any(|p| html.contains(p)) {
+        return PageKind::NovelDeleted;
+    }
+    if html.contains(r#"type="password""#) || title.contains("ログイン") || is_login_announce(html) {
+        return PageKind::LoginRequired;
+    }
+
+    PageKind::Normal
+}
+
+/// エラーページであればエラーを返す
+pub fn check_page(html: &str, url: &str) -> Result<(), PageError> {
+    match classify_page(html) {
+        PageKind::Normal => Ok(()),
+        PageKind::Maintenance => Err(PageError::Maintenance(url.to_string())),
+        PageKind::NovelDeleted => Err(PageError::NovelDeleted(url.to_string())),
+        PageKind::LoginRequired => Err(PageError::LoginRequired(url.to_string())),
+        PageKind::AgeGate => Err(PageError::AgeGate(url.to_string())),
+    }
+}
+
+fn extract_title(html: &str) -> &str {
+    html.find("<title>")
+        .and_then(|start| {
+            let rest = &html[start + "<title>".len()..];
+            rest.find("</title>").map(|end| &rest[..end])
+        })
+        .unwrap_or("")
+}
+
+/// 「ログインしてください」の告知ボックス
+fn is_login_announce(html: &str) -> bool {
+    html.split(r#"class="c-announce"#)
+        .skip(1)
+        .any(|block| {
+            let block = &block[..block.find("</div>").unwrap_or(block.len())];
+            block.contains("login/input") && block.contains("ログイン")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(path: &str) -> String {
+        std::fs::read_to_string(format!("target_pages/{}", path)).unwrap()
+    }
+
+    #[test]
+    fn test_fixtures_are_normal() {
+        for path in [
+            "narou/novel/n7775do-2.html",
+            "narou/novel_list/n7775do.html",
+            "narou/rating/59791-1.html",
+            "narou/bookmarks/59791-1-1.html",
+            "nocturne/novel/n9598df-1.html",
+            "nocturne/bookmarks/x9487b-1-1.html",
+        ] {
+            assert_eq!(classify_page(&fixture(path)), PageKind::Normal, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_error_pages() {
+        let maintenance = "<html><head><title>メンテナンス中です</title></head><body>ただいまメンテナンス中です。</body></html>";
+        assert_eq!(classify_page(maintenance), PageKind::Maintenance);
+
+        let deleted = r#"<html><head><title>エラー</title></head><body><div class="nothing">この作品は存在しません。</div></body></html>"#;
+        assert_eq!(classify_page(deleted), PageKind::NovelDeleted);
+
+        let age_gate = r#"<html><head><title>年齢確認</title></head><body><a id="yes18" href="https://novel18.syosetu.com/">Enter</a></body></html>"#;
+        assert_eq!(classify_page(age_gate), PageKind::AgeGate);
+
+        let login = r#"<html><body><div class="c-announce c-announce--note">このページを見るには<a href="https://syosetu.com/login/input/">ログイン</a>してください。</div></body></html>"#;
+        assert_eq!(classify_page(login), PageKind::LoginRequired);
+    }
+
+    #[test]
+    fn test_check_page_error() {
+        let err = check_page("<title>メンテナンス中</title>", "https://ncode.syosetu.com/n0000a/").unwrap_err();
+        assert_eq!(err, PageError::Maintenance("https://ncode.syosetu.com/n0000a/".to_string()));
+    }
+}
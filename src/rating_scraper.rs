@@ -1,7 +1,9 @@
+use crate::page_classifier::check_page;
 use anyhow::{Context, Result};
+use serde::Serialize;
 
 /// 評価した小説の情報
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct RatingEntry {
     /// 小説のncode（URLから取得）
     pub ncode: String,
@@ -37,11 +39,12 @@ impl NarouRatingScraper {
                 format!("https://mypage.syosetu.com/mypagenovelhyoka/list/userid/{}/?p={}", user_id, page)
             };
 
-            println!("Fetching page {}: {}", page, url);
+            eprintln!("Fetching page {}: {}", page, url);
 
             // HTMLを取得
             let html = self.fetcher.fetch(&url).await
                 .with_context(|| format!("Failed to fetch page {}", page))?;
+            check_page(&html, &url)?;
 
             // ページから評価エントリを抽出
             let entries = Self::parse_rating_page(&html)?;
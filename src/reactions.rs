@@ -0,0 +1,163 @@
+use crate::novel_scraper::{Episode, NovelContent};
+use scraper::{Html, Selector};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 1話分のリアクション数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EpisodeReactions {
+    /// エピソード番号（短編の場合は0）
+    pub episode_number: u32,
+    /// リアクションID（`data-reactionid`）→件数
+    pub counts: BTreeMap<u32, u32>,
+}
+
+impl EpisodeReactions {
+    /// エピソードのHTMLからリアクション欄（`p-reaction__emoticon`）を読み取る
+    ///
+    /// リアクション欄がないページ（ログイン必須など）は件数なしになる。
+    pub fn parse(episode: &Episode) -> Self {
+        let document = Html::parse_document(&episode.html);
+        let emoticon_selector = Selector::parse(".p-reaction__emoticon[data-reactionid]").unwrap();
+        let count_selector = Selector::parse(".p-reaction__count[data-count]").unwrap();
+
+        let mut counts = BTreeMap::new();
+        for emoticon in document.select(&emoticon_selector) {
+            let Some(id) = emoticon.value().attr("data-reactionid").and_then(|id| id.trim().parse().ok()) else {
+                continue;
+            };
+            let count = emoticon
+                .select(&count_selector)
+                .next()
+                .and_then(|c| c.value().attr("data-count"))
+                .and_then(|c| c.trim().parse().ok())
+                .unwrap_or(0);
+            counts.insert(id, count);
+        }
+
+        Self {
+            episode_number: episode.episode_number,
+            counts,
+        }
+    }
+
+    /// 指定IDの件数
+    pub fn count(&self, reaction_id: u32) -> u32 {
+        self.counts.get(&reaction_id).copied().unwrap_or(0)
+    }
+
+    /// 全リアクションの合計
+    pub fn total(&self) -> u32 {
+        self.counts.values().sum()
+    }
+}
+
+/// 作品全体のリアクション数の推移（話数順）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReactionSeries {
+    pub ncode: String,
+    pub episodes: Vec<EpisodeReactions>,
+}
+
+impl ReactionSeries {
+    /// 取得済みの全話から作成
+    pub fn from_novel(novel: &NovelContent) -> Self {
+        let mut episodes: Vec<EpisodeReactions> = novel.episodes.iter().map(EpisodeReactions::parse).collect();
+        episodes.sort_by_key(|e| e.episode_number);
+
+        Self {
+            ncode: novel.ncode.clone(),
+            episodes,
+        }
+    }
+
+    /// 出現した全てのリアクションID
+    pub fn reaction_ids(&self) -> Vec<u32> {
+        let ids: BTreeSet<u32> = self.episodes.iter().flat_map(|e| e.counts.keys().copied()).collect();
+        ids.into_iter().collect()
+    }
+
+    /// IDごとの全話合計
+    pub fn totals(&self) -> BTreeMap<u32, u32> {
+        let mut totals = BTreeMap::new();
+        for episode in &self.episodes {
+            for (id, count) in &episode.counts {
+                *totals.entry(*id).or_insert(0) += count;
+            }
+        }
+        totals
+    }
+
+    /// 合計リアクション数の多い順に`limit`話（同数なら話数の若い順）
+    pub fn most_reacted(&self, limit: usize) -> Vec<&EpisodeReactions> {
+        let mut ranked: Vec<&EpisodeReactions> = self.episodes.iter().collect();
+        ranked.sort_by(|a, b| b.total().cmp(&a.total()).then(a.episode_number.cmp(&b.episode_number)));
+        ranked.truncate(limit);
+        ranked
+    }
+
+    /// 指定IDの件数が多い順に`limit`話
+    pub fn most_reacted_by(&self, reaction_id: u32, limit: usize) -> Vec<&EpisodeReactions> {
+        let mut ranked: Vec<&EpisodeReactions> = self.episodes.iter().collect();
+        ranked.sort_by(|a, b| {
+            b.count(reaction_id)
+                .cmp(&a.count(reaction_id))
+                .then(a.episode_number.cmp(&b.episode_number))
+        });
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel_scraper::NovelType;
+    use std::fs;
+
+    fn load(path: &str, episode_number: u32) -> Episode {
+        Episode {
+            episode_number,
+            html: fs::read_to_string(path).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_reactions() {
+        let reactions = EpisodeReactions::parse(&load("target_pages/narou/novel/n7775do-2.html", 2));
+        assert_eq!(reactions.episode_number, 2);
+        assert_eq!(reactions.counts.keys().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(reactions.count(1), 27);
+        assert_eq!(reactions.total(), 27);
+
+        let none = EpisodeReactions::parse(&Episode { episode_number: 1, html: "<p>本文</p>".to_string() });
+        assert!(none.counts.is_empty());
+    }
+
+    #[test]
+    fn test_series_ranking() {
+        let html = |counts: [u32; 2]| {
+            format!(
+                r#"<div class="p-reaction__emoticon" data-reactionid="1"><span class="p-reaction__count" data-count={}></span></div>
+<div class="p-reaction__emoticon is-empty" data-reactionid="2"><span class="p-reaction__count" data-count={}></span></div>"#,
+                counts[0], counts[1]
+            )
+        };
+        let novel = NovelContent {
+            ncode: "n0000aa".to_string(),
+            novel_type: NovelType::Serial { total_episodes: 3 },
+            episodes: vec![
+                Episode { episode_number: 3, html: html([1, 9]) },
+                Episode { episode_number: 1, html: html([5, 0]) },
+                Episode { episode_number: 2, html: html([4, 1]) },
+            ],
+        };
+
+        let series = ReactionSeries::from_novel(&novel);
+        assert_eq!(series.episodes.iter().map(|e| e.episode_number).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(series.reaction_ids(), vec![1, 2]);
+        assert_eq!(series.totals(), BTreeMap::from([(1, 10), (2, 10)]));
+        assert_eq!(series.most_reacted(2).iter().map(|e| e.episode_number).collect::<Vec<_>>(), vec![3, 1]);
+        assert_eq!(series.most_reacted_by(1, 1)[0].episode_number, 1);
+    }
+}
@@ -0,0 +1,491 @@
+use crate::archive::episode_files;
+use crate::export::{escape_xml, HtmlExporter, HtmlOptions, HtmlTemplate};
+use crate::novel_parser::{IndexChapter, IndexEntry, NovelIndex, ParsedEpisode};
+use crate::novel_scraper::Episode;
+use crate::ruby::RubyMode;
+use anyhow::{Context, Result};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 読書位置を保存するファイル（出力ディレクトリ直下）
+pub const POSITIONS_FILE: &str = "reader_positions.json";
+
+/// ブラウザ用リーダーのページのテンプレート
+///
+/// 縦書き・文字サイズはブラウザに保存し、ルビの表示方法はCookie（`ruby`）でサーバーに伝える。
+/// 本文ページではスクロールに合わせて読んでいる段落を`/api/position`に送る。
+const READER_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="ja">
+<head>
+<meta charset="UTF-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>
+:root { --font-size: 18px; }
+body { margin: 0; font-family: serif; font-size: var(--font-size); line-height: 1.9; background: #fbf8f1; color: #222; }
+main { max-width: 40em; margin: 0 auto; padding: 4em 1em 2em; }
+p { margin: 0; }
+a { color: #35619c; }
+.toolbar { position: fixed; top: 0; left: 0; right: 0; z-index: 1; display: flex; gap: .5em; align-items: center; padding: .4em 1em; background: #eee8da; font: 14px sans-serif; writing-mode: horizontal-tb; }
+.toolbar .spacer { flex: 1; }
+.preface, .afterword { margin: 2em 0; padding: 1em; background: #f1ece0; font-size: 0.9em; }
+.episode-nav { display: flex; justify-content: space-between; margin: 2em 0; font-family: sans-serif; }
+img.illustration { max-width: 100%; max-height: 80vh; }
+body.vertical main { writing-mode: vertical-rl; max-width: none; height: calc(100vh - 6em); margin: 3em 0 0; padding: 1em 2em; overflow-x: auto; }
+body.vertical .episode-nav { flex-direction: column; }
+body.vertical .preface, body.vertical .afterword { margin: 0 2em; }
+</style>
+</head>
+<body>
+<div class="toolbar">
+<a href="/">本棚</a>
+<span id="resume"></span>
+<span class="spacer"></span>
+<button type="button" id="writing-mode">縦書き</button>
+<button type="button" id="font-smaller">A−</button>
+<button type="button" id="font-larger">A＋</button>
+<select id="ruby-mode">
+<option value="keep">ルビ</option>
+<option value="paren">括弧書き</option>
+<option value="strip">ルビなし</option>
+<option value="hiragana">ひらがな</option>
+</select>
+</div>
+<main>
+{{nav}}
+{{content}}
+{{nav}}
+</main>
+<script>
+(function () {
+  const store = window.localStorage;
+  const body = document.body;
+  const main = document.querySelector("main");
+
+  let fontSize = parseInt(store.getItem("reader.fontSize") || "18", 10);
+  const applyFont = () => document.documentElement.style.setProperty("--font-size", fontSize + "px");
+  document.getElementById("font-smaller").onclick = () => { fontSize = Math.max(12, fontSize - 2); store.setItem("reader.fontSize", fontSize); applyFont(); };
+  document.getElementById("font-larger").onclick = () => { fontSize = Math.min(40, fontSize + 2); store.setItem("reader.fontSize", fontSize); applyFont(); };
+  applyFont();
+
+  const modeButton = document.getElementById("writing-mode");
+  const applyMode = () => {
+    const vertical = store.getItem("reader.vertical") === "1";
+    body.classList.toggle("vertical", vertical);
+    modeButton.textContent = vertical ? "横書き" : "縦書き";
+  };
+  modeButton.onclick = () => { store.setItem("reader.vertical", body.classList.contains("vertical") ? "0" : "1"); applyMode(); };
+  applyMode();
+
+  const ruby = document.getElementById("ruby-mode");
+  const cookie = document.cookie.split("; ").find((c) => c.startsWith("ruby="));
+  ruby.value = cookie ? cookie.slice(5) : "keep";
+  ruby.onchange = () => { document.cookie = "ruby=" + ruby.value + "; path=/; max-age=31536000"; location.reload(); };
+
+  // /novels/{ncode}/0001.html・/novels/{ncode}/{ncode}.html・/novels/{ncode}/index.html
+  const match = location.pathname.match(/^\/novels\/([^/]+)\/(?:(\d+)|([^/]+))\.html$/);
+  const ncode = match ? match[1] : null;
+  const episode = match ? (match[2] ? parseInt(match[2], 10) : (match[3] === ncode ? 0 : null)) : null;
+
+  if (ncode && episode === null) {
+    fetch("/api/position/" + ncode).then((r) => r.ok ? r.json() : null).then((position) => {
+      if (!position) return;
+      const file = position.episode_number === 0 ? ncode + ".html" : String(position.episode_number).padStart(4, "0") + ".html";
+      const link = document.createElement("a");
+      link.href = file + (position.paragraph_id ? "#" + position.paragraph_id : "");
+      link.textContent = "しおりから読む（" + (position.episode_number || "短編") + "）";
+      document.getElementById("resume").appendChild(link);
+    });
+  }
+
+  if (ncode && episode !== null) {
+    const paragraphs = Array.from(document.querySelectorAll("main p[id]"));
+    const current = () => {
+      const vertical = body.classList.contains("vertical");
+      return paragraphs.find((p) => {
+        const rect = p.getBoundingClientRect();
+        return vertical ? rect.left < window.innerWidth && rect.right <= main.getBoundingClientRect().right + 1 : rect.bottom > 48;
+      });
+    };
+    let timer = null;
+    const save = () => {
+      const paragraph = current();
+      fetch("/api/position", {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ ncode: ncode, episode_number: episode, paragraph_id: paragraph ? paragraph.id : null }),
+      });
+    };
+    const schedule = () => { clearTimeout(timer); timer = setTimeout(save, 1000); };
+    window.addEventListener("scroll", schedule, { passive: true });
+    main.addEventListener("scroll", schedule, { passive: true });
+    save();
+  }
+})();
+</script>
+</body>
+</html>
+"##;
+
+/// 読者ごとの読書位置（しおり）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingPosition {
+    pub episode_number: u32,
+    /// 最後に読んでいた段落のid（`L12`など）
+    pub paragraph_id: Option<String>,
+    /// 保存日時（RFC 3339）
+    pub updated_at: String,
+}
+
+/// 全読者の読書位置（読者名→ncode→位置）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReadingPositions {
+    pub users: BTreeMap<String, BTreeMap<String, ReadingPosition>>,
+}
+
+impl ReadingPositions {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn get(&self, user: &str, ncode: &str) -> Option<&ReadingPosition> {
+        self.users.get(user)?.get(&ncode.to_lowercase())
+    }
+
+    pub fn for_user(&self, user: &str) -> Option<&BTreeMap<String, ReadingPosition>> {
+        self.users.get(user)
+    }
+
+    pub fn set(&mut self, user: &str, ncode: &str, episode_number: u32, paragraph_id: Option<String>) {
+        self.users.entry(user.to_string()).or_default().insert(
+            ncode.to_lowercase(),
+            ReadingPosition {
+                episode_number,
+                paragraph_id,
+                updated_at: chrono::Utc::now().to_rfc3339(),
+            },
+        );
+    }
+}
+
+/// 出力ディレクトリに保存された1作品
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedNovel {
+    pub ncode: String,
+    pub title: String,
+    pub author: String,
+    pub dir: PathBuf,
+    /// 話数とファイルの組（話数順、短編は0話の1つだけ）
+    pub files: Vec<(u32, PathBuf)>,
+}
+
+impl SavedNovel {
+    pub fn is_short(&self) -> bool {
+        matches!(self.files.as_slice(), [(0, _)])
+    }
+
+    /// リーダー上のファイル名（短編は`{ncode}.html`、連載は`0001.html`）
+    pub fn file_name(&self, episode_number: u32) -> String {
+        match episode_number {
+            0 => format!("{}.html", self.ncode),
+            n => format!("{:04}.html", n),
+        }
+    }
+
+    pub fn episode(&self, episode_number: u32) -> Result<Option<Episode>> {
+        let Some((_, path)) = self.files.iter().find(|(n, _)| *n == episode_number) else {
+            return Ok(None);
+        };
+        let html = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(Episode { episode_number, html }))
+    }
+
+    /// 前後の話数
+    pub fn neighbours(&self, episode_number: u32) -> (Option<u32>, Option<u32>) {
+        let Some(i) = self.files.iter().position(|(n, _)| *n == episode_number) else {
+            return (None, None);
+        };
+        let prev = i.checked_sub(1).map(|p| self.files[p].0);
+        let next = self.files.get(i + 1).map(|(n, _)| *n);
+        (prev, next)
+    }
+}
+
+/// 本文ページの`.c-announce`から作品名と作者名を取り出す
+pub fn announce_header(html: &str) -> Option<(String, String)> {
+    let document = Html::parse_document(html);
+    let link_selector = Selector::parse(".c-announce a").unwrap();
+    let mut title = None;
+    let mut author = String::new();
+    for link in document.select(&link_selector) {
+        let href = link.value().attr("href").unwrap_or_default();
+        let text = link.text().collect::<String>().trim().to_string();
+        if href.contains("mypage") || href.contains("xmypage") {
+            author = text;
+        } else if title.is_none() && href.starts_with('/') && !text.is_empty() {
+            title = Some(text);
+        }
+    }
+    title.map(|title| (title, author))
+}
+
+/// 出力ディレクトリを本棚として読む
+///
+/// サブタイトルはファイルの更新日時ごとに覚えておき、変わったファイルだけ読み直す。
+pub struct Bookshelf {
+    root: PathBuf,
+    subtitles: HashMap<PathBuf, (SystemTime, String)>,
+}
+
+impl Bookshelf {
+    pub fn open(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            subtitles: HashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 本文を保存してある作品（ncode順）
+    pub fn novels(&self) -> Result<Vec<SavedNovel>> {
+        let mut novels = Vec::new();
+        for entry in fs::read_dir(&self.root).with_context(|| format!("Failed to read {}", self.root.display()))? {
+            let path = entry?.path();
+            let Some(ncode) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if path.is_dir() {
+                if let Some(novel) = self.novel(ncode)? {
+                    novels.push(novel);
+                }
+            }
+        }
+        novels.sort_by(|a, b| a.ncode.cmp(&b.ncode));
+        Ok(novels)
+    }
+
+    pub fn novel(&self, ncode: &str) -> Result<Option<SavedNovel>> {
+        let ncode = ncode.to_lowercase();
+        // パスとして使うので英数字以外は受け付けない
+        if ncode.is_empty() || !ncode.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Ok(None);
+        }
+        let dir = self.root.join(&ncode);
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+        let files = episode_files(&dir, &ncode)?;
+        let Some((_, first)) = files.first() else {
+            return Ok(None);
+        };
+
+        let html = fs::read_to_string(first).with_context(|| format!("Failed to read {}", first.display()))?;
+        let (title, author) = announce_header(&html).unwrap_or_else(|| {
+            // 短編には作品名の告知がないので本文の見出しを使う
+            let parsed = ParsedEpisode::parse(&Episode { episode_number: 0, html });
+            (if parsed.subtitle.is_empty() { ncode.clone() } else { parsed.subtitle }, String::new())
+        });
+
+        Ok(Some(SavedNovel {
+            ncode,
+            title,
+            author,
+            dir,
+            files,
+        }))
+    }
+
+    /// 保存済みの話だけの目次
+    pub fn index(&mut self, novel: &SavedNovel) -> Result<NovelIndex> {
+        let mut episodes = Vec::new();
+        for (episode_number, path) in &novel.files {
+            let modified = fs::metadata(path)?.modified()?;
+            let subtitle = match self.subtitles.get(path) {
+                Some((cached, subtitle)) if *cached == modified => subtitle.clone(),
+                _ => {
+                    let html = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+                    let subtitle = ParsedEpisode::parse(&Episode { episode_number: *episode_number, html }).subtitle;
+                    self.subtitles.insert(path.clone(), (modified, subtitle.clone()));
+                    subtitle
+                }
+            };
+            episodes.push(IndexEntry {
+                episode_number: *episode_number,
+                subtitle,
+                published_at: String::new(),
+                revised_at: None,
+            });
+        }
+
+        Ok(NovelIndex {
+            title: novel.title.clone(),
+            author: novel.author.clone(),
+            chapters: vec![IndexChapter { title: None, episodes }],
+            ..Default::default()
+        })
+    }
+}
+
+/// Cookieの値からルビの表示方法を決める（`keep`・`paren`・`strip`・`hiragana`）
+pub fn ruby_mode_from_key(key: &str) -> RubyMode {
+    match key {
+        "paren" => RubyMode::Parenthesize,
+        "strip" => RubyMode::Strip,
+        "hiragana" => RubyMode::HiraganaOnly,
+        _ => RubyMode::Keep,
+    }
+}
+
+fn exporter(novel: &SavedNovel, ruby: RubyMode) -> HtmlExporter {
+    HtmlExporter::new(HtmlOptions {
+        ruby,
+        ..Default::default()
+    })
+    .template(HtmlTemplate::new(READER_TEMPLATE).expect("reader template has {{content}}"))
+    .header(novel.title.clone(), novel.author.clone())
+}
+
+/// 1話分のページ
+pub fn render_episode(novel: &SavedNovel, episode: &Episode, ruby: RubyMode) -> String {
+    let (prev, next) = novel.neighbours(episode.episode_number);
+    let prev = prev.map(|n| novel.file_name(n));
+    let next = next.map(|n| novel.file_name(n));
+    exporter(novel, ruby).render_episode(
+        &ParsedEpisode::parse(episode),
+        prev.as_deref(),
+        next.as_deref(),
+        Some("index.html"),
+    )
+}
+
+/// 目次ページ
+pub fn render_index(novel: &SavedNovel, index: NovelIndex) -> String {
+    let files: Vec<(u32, String)> = novel.files.iter().map(|(n, _)| (*n, novel.file_name(*n))).collect();
+    exporter(novel, RubyMode::Keep).index(index).render_index_page(&files)
+}
+
+/// 本棚（作品一覧と読者の切り替え）
+pub fn render_shelf(novels: &[SavedNovel], user: &str, positions: Option<&BTreeMap<String, ReadingPosition>>) -> String {
+    let mut content = String::from("<h1 class=\"title\">本棚</h1>\n");
+    content.push_str(&format!(
+        "<form method=\"post\" action=\"/user\">読者：<input name=\"name\" value=\"{}\"> <button type=\"submit\">切り替え</button></form>\n",
+        escape_xml(user)
+    ));
+
+    content.push_str("<ul class=\"shelf\">\n");
+    for novel in novels {
+        let start = if novel.is_short() { novel.file_name(0) } else { "index.html".to_string() };
+        content.push_str(&format!(
+            "<li><a href=\"/novels/{}/{}\">{}</a>",
+            novel.ncode,
+            start,
+            escape_xml(&novel.title)
+        ));
+        if !novel.author.is_empty() {
+            content.push_str(&format!("（{}）", escape_xml(&novel.author)));
+        }
+        content.push_str(&format!(" {}話", novel.files.len()));
+        if let Some(position) = positions.and_then(|p| p.get(&novel.ncode)) {
+            let anchor = position.paragraph_id.as_ref().map(|id| format!("#{}", id)).unwrap_or_default();
+            content.push_str(&format!(
+                " <a href=\"/novels/{}/{}{}\">しおり</a>",
+                novel.ncode,
+                novel.file_name(position.episode_number),
+                escape_xml(&anchor)
+            ));
+        }
+        content.push_str("</li>\n");
+    }
+    content.push_str("</ul>\n");
+
+    HtmlTemplate::new(READER_TEMPLATE)
+        .expect("reader template has {{content}}")
+        .render(&[("title", "本棚"), ("novel_title", ""), ("author", ""), ("nav", ""), ("content", &content)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shelf_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("reader-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let novel_dir = dir.join("n7775do");
+        fs::create_dir_all(&novel_dir).unwrap();
+        for (file, fixture) in [("0002.html", "n7775do-2"), ("0008.html", "n7775do-8"), ("0011.html", "n7775do-11")] {
+            fs::copy(format!("target_pages/narou/novel/{}.html", fixture), novel_dir.join(file)).unwrap();
+        }
+        fs::write(novel_dir.join("metadata.json"), "{}").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_bookshelf() {
+        let dir = shelf_dir("shelf");
+        let mut shelf = Bookshelf::open(&dir);
+
+        let novels = shelf.novels().unwrap();
+        assert_eq!(novels.len(), 1);
+        let novel = &novels[0];
+        assert_eq!(novel.title, "お前が神を殺したいなら、とあなたは言った");
+        assert_eq!(novel.author, "ふじやま");
+        assert_eq!(novel.files.iter().map(|(n, _)| *n).collect::<Vec<_>>(), vec![2, 8, 11]);
+        assert_eq!(novel.neighbours(8), (Some(2), Some(11)));
+        assert_eq!(novel.neighbours(2), (None, Some(8)));
+        assert!(shelf.novel("../etc").unwrap().is_none());
+
+        let index = shelf.index(novel).unwrap();
+        assert_eq!(index.chapters[0].episodes[0].subtitle, "天真歴19年　9月14日");
+        let page = render_index(novel, index);
+        assert!(page.contains("<a href=\"0002.html\">天真歴19年　9月14日</a>"));
+
+        let episode = novel.episode(8).unwrap().unwrap();
+        let page = render_episode(novel, &episode, RubyMode::Strip);
+        assert!(page.contains("<a href=\"0002.html\">前へ</a>"));
+        assert!(page.contains("<a href=\"0011.html\">次へ</a>"));
+        assert!(page.contains("<p id=\"L1\">"));
+        assert!(!page.contains("<ruby>"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reading_positions() {
+        let dir = shelf_dir("positions");
+        let path = dir.join(POSITIONS_FILE);
+
+        let mut positions = ReadingPositions::load(&path).unwrap();
+        positions.set("alice", "N7775DO", 8, Some("L12".to_string()));
+        positions.set("bob", "n7775do", 2, None);
+        positions.save(&path).unwrap();
+
+        let positions = ReadingPositions::load(&path).unwrap();
+        assert_eq!(positions.get("alice", "n7775do").unwrap().paragraph_id.as_deref(), Some("L12"));
+        assert_eq!(positions.get("bob", "n7775do").unwrap().episode_number, 2);
+        assert!(positions.get("carol", "n7775do").is_none());
+
+        let novels = Bookshelf::open(&dir).novels().unwrap();
+        let shelf = render_shelf(&novels, "alice", positions.for_user("alice"));
+        assert!(shelf.contains("<a href=\"/novels/n7775do/0008.html#L12\">しおり</a>"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
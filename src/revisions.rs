@@ -0,0 +1,341 @@
+use crate::novel_parser::{IndexEntry, NovelIndex, ParsedEpisode};
+use crate::novel_scraper::{Episode, NovelContent};
+use crate::ruby::{render_segments, RubyMode};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 保存済みの1版
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    /// 版のID（`{改稿日時}-{連番:04}-{本文ハッシュ}`）
+    pub id: String,
+    pub episode_number: u32,
+    /// 版の日時（`202208201117`形式。目次の改稿日時・掲載日時、なければ取得日時）
+    pub stamp: String,
+    /// 話ごとの保存順の連番（目次の日時が同じ版の順序を決める。連番のない古い形式のIDは0）
+    pub sequence: u32,
+    /// 前書き・本文・後書きのハッシュ（先頭16桁）
+    pub content_hash: String,
+    pub path: PathBuf,
+}
+
+/// エピソードの版を保存するディレクトリ（`{dir}/{話数:04}/{版ID}.html`）
+///
+/// 本文が変わったときだけ新しい版を追加するので、再取得しても過去の版は残る。
+pub struct RevisionStore {
+    dir: PathBuf,
+}
+
+impl RevisionStore {
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 保存済みの話数
+    pub fn episode_numbers(&self) -> Result<Vec<u32>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut numbers = Vec::new();
+        for entry in fs::read_dir(&self.dir).with_context(|| format!("Failed to read {}", self.dir.display()))? {
+            if let Some(number) = entry?.file_name().to_str().and_then(|name| name.parse().ok()) {
+                numbers.push(number);
+            }
+        }
+        numbers.sort();
+        Ok(numbers)
+    }
+
+    /// 保存済みの版（古い順）
+    pub fn revisions(&self, episode_number: u32) -> Result<Vec<Revision>> {
+        let dir = self.episode_dir(episode_number);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut revisions = Vec::new();
+        for entry in fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let path = entry?.path();
+            let Some(id) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".html")) else {
+                continue;
+            };
+            let (stamp, sequence, hash) = match id.split('-').collect::<Vec<_>>()[..] {
+                [stamp, hash] => (stamp, 0, hash),
+                [stamp, sequence, hash] => match sequence.parse() {
+                    Ok(sequence) => (stamp, sequence, hash),
+                    Err(_) => continue,
+                },
+                _ => continue,
+            };
+            revisions.push(Revision {
+                id: id.to_string(),
+                episode_number,
+                stamp: stamp.to_string(),
+                sequence,
+                content_hash: hash.to_string(),
+                path: path.clone(),
+            });
+        }
+        revisions.sort_by(|a, b| (&a.stamp, a.sequence, &a.id).cmp(&(&b.stamp, b.sequence, &b.id)));
+        Ok(revisions)
+    }
+
+    /// 最新の版
+    pub fn latest(&self, episode_number: u32) -> Result<Option<Revision>> {
+        Ok(self.revisions(episode_number)?.pop())
+    }
+
+    /// 本文が最新の版と違えば新しい版として保存する（同じならNone）
+    ///
+    /// `revised_at`は目次の改稿日時（`2022/08/20 11:17`）。
+    pub fn save(&self, episode: &Episode, revised_at: Option<&str>) -> Result<Option<Revision>> {
+        let hash = content_hash(&ParsedEpisode::parse(episode));
+        let revisions = self.revisions(episode.episode_number)?;
+        if revisions.last().is_some_and(|latest| latest.content_hash == hash) {
+            return Ok(None);
+        }
+        let sequence = revisions.iter().map(|r| r.sequence).max().unwrap_or(0) + 1;
+
+        let stamp = revised_at
+            .map(stamp_of)
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| chrono::Local::now().format("%Y%m%d%H%M").to_string());
+        let id = format!("{}-{:04}-{}", stamp, sequence, hash);
+        let dir = self.episode_dir(episode.episode_number);
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!("{}.html", id));
+        fs::write(&path, &episode.html).with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(Some(Revision {
+            id,
+            episode_number: episode.episode_number,
+            stamp,
+            sequence,
+            content_hash: hash,
+            path,
+        }))
+    }
+
+    /// 作品の全話を保存し、新しく保存した版の数を返す（`index`があれば目次の改稿日時・掲載日時を使う）
+    pub fn save_novel(&self, novel: &NovelContent, index: Option<&NovelIndex>) -> Result<usize> {
+        let mut saved = 0;
+        for episode in &novel.episodes {
+            let revised_at = index
+                .and_then(|i| i.entries().find(|e| e.episode_number == episode.episode_number))
+                .map(|e| e.revised_at.as_deref().unwrap_or(&e.published_at));
+            if self.save(episode, revised_at)?.is_some() {
+                saved += 1;
+            }
+        }
+        Ok(saved)
+    }
+
+    /// 指定した版を読み込む
+    pub fn load(&self, episode_number: u32, id: &str) -> Result<Episode> {
+        let path = self.episode_dir(episode_number).join(format!("{}.html", id));
+        if !path.exists() {
+            bail!("Revision {} of episode {} not found in {}", id, episode_number, self.dir.display());
+        }
+        let html = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Episode { episode_number, html })
+    }
+
+    /// 目次の改稿日時が保存済みの最新版より新しい（または未保存）なら取り直しが必要
+    pub fn is_outdated(&self, entry: &IndexEntry) -> Result<bool> {
+        let updated = stamp_of(entry.revised_at.as_deref().unwrap_or(&entry.published_at));
+        Ok(match self.latest(entry.episode_number)? {
+            Some(latest) => latest.stamp < updated,
+            None => true,
+        })
+    }
+
+    fn episode_dir(&self, episode_number: u32) -> PathBuf {
+        self.dir.join(format!("{:04}", episode_number))
+    }
+}
+
+/// 段落単位の変更
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ParagraphChange {
+    Added { id: String, text: String },
+    Removed { id: String, text: String },
+    Modified { old_id: String, new_id: String, old_text: String, new_text: String },
+}
+
+/// 2つの版の段落単位の差分（前書き・本文・後書きを通して比較）
+///
+/// 段落の対応は本文の最長共通部分列で取り、行の挿入でidがずれても残りの行は変更扱いにしない。
+/// 同じ位置で削除と追加が並んだ段落は書き換えとしてまとめる。
+pub fn diff_paragraphs(old: &ParsedEpisode, new: &ParsedEpisode) -> Vec<ParagraphChange> {
+    let lines = |parsed: &ParsedEpisode| -> Vec<(String, String)> {
+        parsed
+            .paragraphs()
+            .map(|p| (p.id.clone(), render_segments(&p.segments(), RubyMode::Keep)))
+            .collect()
+    };
+    let old = lines(old);
+    let new = lines(new);
+
+    // lcs[i][j] = old[i..]とnew[j..]の最長共通部分列の長さ
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i].1 == new[j].1 {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut removed: Vec<&(String, String)> = Vec::new();
+    let mut added: Vec<&(String, String)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i].1 == new[j].1 {
+            flush_changes(&mut removed, &mut added, &mut changes);
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(&new[j]);
+            j += 1;
+        } else {
+            removed.push(&old[i]);
+            i += 1;
+        }
+    }
+    flush_changes(&mut removed, &mut added, &mut changes);
+
+    changes
+}
+
+fn flush_changes(
+    removed: &mut Vec<&(String, String)>,
+    added: &mut Vec<&(String, String)>,
+    changes: &mut Vec<ParagraphChange>,
+) {
+    let paired = removed.len().min(added.len());
+    for (old, new) in removed.iter().zip(added.iter()) {
+        changes.push(ParagraphChange::Modified {
+            old_id: old.0.clone(),
+            new_id: new.0.clone(),
+            old_text: old.1.clone(),
+            new_text: new.1.clone(),
+        });
+    }
+    for (id, text) in removed.drain(..).skip(paired) {
+        changes.push(ParagraphChange::Removed { id: id.clone(), text: text.clone() });
+    }
+    for (id, text) in added.drain(..).skip(paired) {
+        changes.push(ParagraphChange::Added { id: id.clone(), text: text.clone() });
+    }
+}
+
+/// 本文部分のハッシュ（ページごとに変わるトークンなどの影響を受けない）
+pub(crate) fn content_hash(parsed: &ParsedEpisode) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(parsed.subtitle.as_bytes());
+    for paragraph in parsed.paragraphs() {
+        hasher.update(b"\n");
+        hasher.update(paragraph.id.as_bytes());
+        hasher.update(b"\t");
+        hasher.update(paragraph.html.as_bytes());
+    }
+    hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `2022/08/20 11:17`→`202208201117`
+fn stamp_of(date: &str) -> String {
+    date.chars().filter(char::is_ascii_digit).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(lines: &[&str]) -> Episode {
+        let body: String = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!(r#"<p id="L{}">{}</p>"#, i + 1, line))
+            .collect();
+        Episode {
+            episode_number: 1,
+            html: format!(
+                r#"<h1 class="p-novel__title">第一話</h1><div class="p-novel__body"><div class="js-novel-text p-novel__text">{}</div></div>"#,
+                body
+            ),
+        }
+    }
+
+    #[test]
+    fn test_store_keeps_changed_revisions() {
+        let dir = std::env::temp_dir().join(format!("wns-revisions-{}", std::process::id()));
+        let store = RevisionStore::open(&dir);
+
+        let first = store.save(&episode(&["一行目", "二行目"]), Some("2016/11/04 00:41")).unwrap().unwrap();
+        assert_eq!(first.stamp, "201611040041");
+        assert!(store.save(&episode(&["一行目", "二行目"]), Some("2016/11/04 00:41")).unwrap().is_none());
+        let second = store.save(&episode(&["一行目", "二行目を直した"]), Some("2019/07/15 02:18")).unwrap().unwrap();
+
+        assert_eq!(store.revisions(1).unwrap(), vec![first.clone(), second]);
+        assert_eq!(store.episode_numbers().unwrap(), vec![1]);
+        assert!(store.load(1, &first.id).unwrap().html.contains("二行目</p>"));
+
+        let entry = IndexEntry {
+            episode_number: 1,
+            subtitle: "第一話".to_string(),
+            published_at: "2016/10/12 13:09".to_string(),
+            revised_at: Some("2022/08/20 11:17".to_string()),
+        };
+        assert!(store.is_outdated(&entry).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_revisions_with_same_stamp_keep_save_order() {
+        let dir = std::env::temp_dir().join(format!("wns-revisions-order-{}", std::process::id()));
+        let store = RevisionStore::open(&dir);
+
+        // 目次の日時が変わらないまま本文だけ直された場合も、後に保存した版が最新
+        for text in ["一", "二", "三", "四"] {
+            let saved = store.save(&episode(&[text]), Some("2016/11/04 00:41")).unwrap().unwrap();
+            assert_eq!(store.latest(1).unwrap().unwrap(), saved);
+        }
+        let sequences: Vec<u32> = store.revisions(1).unwrap().iter().map(|r| r.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3, 4]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_diff_paragraphs() {
+        let old = ParsedEpisode::parse(&episode(&["朝", "昼", "夜", "夢"]));
+        let new = ParsedEpisode::parse(&episode(&["朝", "挿入", "昼", "夕方", "夢"]));
+
+        assert_eq!(
+            diff_paragraphs(&old, &new),
+            vec![
+                ParagraphChange::Added { id: "L2".to_string(), text: "挿入".to_string() },
+                ParagraphChange::Modified {
+                    old_id: "L3".to_string(),
+                    new_id: "L4".to_string(),
+                    old_text: "夜".to_string(),
+                    new_text: "夕方".to_string(),
+                },
+            ]
+        );
+        assert!(diff_paragraphs(&old, &old).is_empty());
+    }
+}
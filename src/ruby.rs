@@ -0,0 +1,328 @@
+use scraper::{ElementRef, Html, Node};
+
+/// ルビ（振り仮名）・傍点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ruby {
+    /// 親文字
+    pub base: String,
+    /// ルビ文字
+    pub reading: String,
+    /// 傍点（ルビが「・」などの記号だけ）かどうか
+    pub is_emphasis: bool,
+}
+
+/// ルビの出力方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RubyMode {
+    /// 出力形式ごとのルビ記法のまま残す（HTMLは`<ruby>`、テキストは`｜漢字《かな》`）
+    #[default]
+    Keep,
+    /// 親文字だけにする
+    Strip,
+    /// `漢字（かな）`のように括弧書きにする
+    Parenthesize,
+    /// 親文字をひらがなの読みに置き換える
+    HiraganaOnly,
+}
+
+/// ルビを含むテキストの断片
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RubySegment {
+    Text(String),
+    Ruby(Ruby),
+}
+
+impl Ruby {
+    pub fn new(base: impl Into<String>, reading: impl Into<String>) -> Self {
+        let reading = reading.into();
+        let is_emphasis = is_emphasis_reading(&reading);
+        Self {
+            base: base.into(),
+            reading,
+            is_emphasis,
+        }
+    }
+
+    /// `<ruby>`要素から作成（`<rp>`は無視し、`<rb>`があれば親文字として使う）
+    pub fn from_element(element: ElementRef) -> Self {
+        let mut base = String::new();
+        let mut reading = String::new();
+
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => base.push_str(text),
+                Node::Element(e) if e.name() == "rt" => reading.extend(ElementRef::wrap(child).unwrap().text()),
+                Node::Element(e) if e.name() == "rb" => base.extend(ElementRef::wrap(child).unwrap().text()),
+                _ => {}
+            }
+        }
+
+        Self::new(base, reading)
+    }
+
+    /// テキストとして出力（`Keep`は青空文庫・なろう互換の`｜漢字《かな》`）
+    pub fn render(&self, mode: RubyMode) -> String {
+        if self.reading.is_empty() {
+            return self.base.clone();
+        }
+        // 傍点は読みではないので親文字だけ残す
+        if self.is_emphasis && mode != RubyMode::Keep {
+            return self.base.clone();
+        }
+
+        match mode {
+            RubyMode::Keep => format!("｜{}《{}》", self.base, self.reading),
+            RubyMode::Strip => self.base.clone(),
+            RubyMode::Parenthesize => format!("{}（{}）", self.base, self.reading),
+            RubyMode::HiraganaOnly => to_hiragana(&self.reading),
+        }
+    }
+
+    /// `<ruby>`要素として出力（XHTMLとしても整形式）
+    pub fn to_html(&self) -> String {
+        format!(
+            "<ruby>{}<rp>(</rp><rt>{}</rt><rp>)</rp></ruby>",
+            escape_html(&self.base),
+            escape_html(&self.reading)
+        )
+    }
+}
+
+/// 断片の列をテキストとして連結
+pub fn render_segments(segments: &[RubySegment], mode: RubyMode) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            RubySegment::Text(text) => text.clone(),
+            RubySegment::Ruby(ruby) => ruby.render(mode),
+        })
+        .collect()
+}
+
+/// なろう記法のルビ（`｜漢字《かな》`・`漢字《かな》`）を含むテキストを分解
+///
+/// `｜`がない場合は`《`の直前に続く漢字を親文字とみなす。`｜《`は`《`そのものを表す。
+pub fn parse_ruby_text(text: &str) -> Vec<RubySegment> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut segments = Vec::new();
+    let mut plain = String::new();
+    // `｜`で始まった親文字の開始位置（plain内のバイト位置）
+    let mut explicit_start: Option<usize> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '｜' || c == '|' {
+            if chars.get(i + 1) == Some(&'《') {
+                plain.push('《');
+                i += 2;
+                continue;
+            }
+            // 後ろに`《…》`が続かない縦線はただの文字
+            if has_ruby_after_bar(&chars[i + 1..]) {
+                explicit_start = Some(plain.len());
+                i += 1;
+                continue;
+            }
+        }
+
+        if c == '《' {
+            if let Some(close) = chars[i + 1..].iter().position(|&c| c == '》') {
+                let reading: String = chars[i + 1..i + 1 + close].iter().collect();
+                let base_start = explicit_start.take().unwrap_or_else(|| implicit_base_start(&plain));
+
+                if base_start < plain.len() && !reading.is_empty() {
+                    let base = plain.split_off(base_start);
+                    if !plain.is_empty() {
+                        segments.push(RubySegment::Text(std::mem::take(&mut plain)));
+                    }
+                    segments.push(RubySegment::Ruby(Ruby::new(base, reading)));
+                    i += close + 2;
+                    continue;
+                }
+            }
+        }
+
+        plain.push(c);
+        i += 1;
+    }
+
+    if !plain.is_empty() {
+        segments.push(RubySegment::Text(plain));
+    }
+    segments
+}
+
+/// `｜`で指定できる親文字の最大文字数（なろうの仕様）
+const MAX_EXPLICIT_BASE: usize = 10;
+
+/// `｜`の直後から、親文字と空でない`《…》`が続くか
+///
+/// 親文字が長すぎるか、途中に別の`｜`・`《`・`》`・改行があれば記法ではない。
+fn has_ruby_after_bar(rest: &[char]) -> bool {
+    let Some(open) = rest.iter().position(|&c| matches!(c, '《' | '》' | '｜' | '|' | '\n')) else {
+        return false;
+    };
+    if open == 0 || open > MAX_EXPLICIT_BASE || rest[open] != '《' {
+        return false;
+    }
+    match rest[open + 1..].iter().position(|&c| c == '》') {
+        Some(close) => close > 0,
+        None => false,
+    }
+}
+
+/// 段落などのHTMLから`<ruby>`要素を含む断片を取り出す
+///
+/// サーバーが出力したHTMLでは有効な記法はすでに`<ruby>`になっているので、
+/// テキストに残った`｜`や`《》`は記法として解釈せずそのまま残す。
+pub fn parse_ruby_html(html: &str) -> Vec<RubySegment> {
+    let fragment = Html::parse_fragment(html);
+    let mut segments = Vec::new();
+    collect_segments(fragment.root_element(), &mut segments);
+    merge_text(segments)
+}
+
+/// HTMLに含まれるルビを全て取り出す
+pub fn extract_rubies(html: &str) -> Vec<Ruby> {
+    parse_ruby_html(html)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            RubySegment::Ruby(ruby) => Some(ruby),
+            RubySegment::Text(_) => None,
+        })
+        .collect()
+}
+
+fn collect_segments(element: ElementRef, segments: &mut Vec<RubySegment>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => segments.push(RubySegment::Text(text.to_string())),
+            Node::Element(e) => {
+                let child = ElementRef::wrap(child).unwrap();
+                match e.name() {
+                    "ruby" => segments.push(RubySegment::Ruby(Ruby::from_element(child))),
+                    "rp" | "rt" => {}
+                    _ => collect_segments(child, segments),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn merge_text(segments: Vec<RubySegment>) -> Vec<RubySegment> {
+    let mut merged: Vec<RubySegment> = Vec::new();
+    for segment in segments {
+        match (merged.last_mut(), segment) {
+            (Some(RubySegment::Text(last)), RubySegment::Text(text)) => last.push_str(&text),
+            (_, segment) => merged.push(segment),
+        }
+    }
+    merged
+}
+
+/// `《`の直前に続く漢字の開始位置
+fn implicit_base_start(plain: &str) -> usize {
+    plain
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_kanji(*c))
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(plain.len())
+}
+
+fn is_kanji(c: char) -> bool {
+    matches!(c,
+        '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{20000}'..='\u{2FFFF}'
+        | '々' | '〆' | '〇' | 'ヶ')
+}
+
+/// 読みが傍点の記号だけか
+fn is_emphasis_reading(reading: &str) -> bool {
+    !reading.is_empty() && reading.chars().all(|c| matches!(c, '・' | '﹅' | '﹆' | '●' | '○' | '•'))
+}
+
+/// カタカナをひらがなに変換
+fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_inline_notation() {
+        assert_eq!(
+            parse_ruby_text("彼は｜魔法使い《ウィザード》だ。東京《とうきょう》へ｜《》"),
+            vec![
+                RubySegment::Text("彼は".to_string()),
+                RubySegment::Ruby(Ruby::new("魔法使い", "ウィザード")),
+                RubySegment::Text("だ。".to_string()),
+                RubySegment::Ruby(Ruby::new("東京", "とうきょう")),
+                RubySegment::Text("へ《》".to_string()),
+            ]
+        );
+        // 漢字がなければルビにしない
+        assert_eq!(parse_ruby_text("あ《い》"), vec![RubySegment::Text("あ《い》".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_literal_bar_and_brackets() {
+        assert_eq!(parse_ruby_text("A|B"), vec![RubySegment::Text("A|B".to_string())]);
+        // 後ろの`《》`の親文字は縦線まで伸ばさない
+        assert_eq!(
+            parse_ruby_text("A｜B、そして遠い遠い東京《とうきょう》"),
+            vec![
+                RubySegment::Text("A｜B、そして遠い遠い".to_string()),
+                RubySegment::Ruby(Ruby::new("東京", "とうきょう")),
+            ]
+        );
+        assert_eq!(parse_ruby_text("｜漢字《》"), vec![RubySegment::Text("｜漢字《》".to_string())]);
+        assert_eq!(parse_ruby_text("《》と｜《"), vec![RubySegment::Text("《》と《".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_ruby_html() {
+        let segments = parse_ruby_html("「ナオキ<ruby>前<rp>(</rp><rt>・</rt><rp>)</rp></ruby>教祖様と<ruby>神<rt>かみ</rt></ruby>」");
+        assert_eq!(segments.len(), 5);
+        assert_eq!(segments[1], RubySegment::Ruby(Ruby { base: "前".to_string(), reading: "・".to_string(), is_emphasis: true }));
+
+        // テキストに残った記法風の文字列は本文そのもの
+        assert_eq!(
+            parse_ruby_html("A|B、東京《とうきょう》と<ruby>剣<rt>けん</rt></ruby>"),
+            vec![
+                RubySegment::Text("A|B、東京《とうきょう》と".to_string()),
+                RubySegment::Ruby(Ruby::new("剣", "けん")),
+            ]
+        );
+
+        let rubies = extract_rubies(r#"<ruby><rb>漢字</rb><rp>(</rp><rt>カンジ</rt><rp>)</rp></ruby>"#);
+        assert_eq!(rubies, vec![Ruby::new("漢字", "カンジ")]);
+    }
+
+    #[test]
+    fn test_render_modes() {
+        let segments = parse_ruby_html("<ruby>漢字<rt>カンジ</rt></ruby>と<ruby>前<rt>・</rt></ruby>");
+        assert_eq!(render_segments(&segments, RubyMode::Keep), "｜漢字《カンジ》と｜前《・》");
+        assert_eq!(render_segments(&segments, RubyMode::Strip), "漢字と前");
+        assert_eq!(render_segments(&segments, RubyMode::Parenthesize), "漢字（カンジ）と前");
+        assert_eq!(render_segments(&segments, RubyMode::HiraganaOnly), "かんじと前");
+        assert_eq!(Ruby::new("a<b", "c").to_html(), "<ruby>a&lt;b<rp>(</rp><rt>c</rt><rp>)</rp></ruby>");
+    }
+}
@@ -0,0 +1,360 @@
+use crate::archive::episode_files;
+use crate::library::Library;
+use crate::novel_parser::ParsedEpisode;
+use crate::novel_scraper::{Episode, NovelContent};
+use crate::ruby::{render_segments, RubyMode};
+use anyhow::{Context, Result};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS documents (
+        id INTEGER PRIMARY KEY,
+        ncode TEXT NOT NULL,
+        episode_number INTEGER NOT NULL,
+        subtitle TEXT NOT NULL,
+        content_hash TEXT NOT NULL,
+        UNIQUE (ncode, episode_number)
+    );
+    CREATE TABLE IF NOT EXISTS lines (
+        id INTEGER PRIMARY KEY,
+        document_id INTEGER NOT NULL,
+        line_id TEXT NOT NULL,
+        text TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS lines_by_document ON lines (document_id);
+    CREATE TABLE IF NOT EXISTS grams (
+        gram TEXT NOT NULL,
+        line INTEGER NOT NULL,
+        PRIMARY KEY (gram, line)
+    ) WITHOUT ROWID;
+";
+
+/// スニペットとして一致箇所の前後に残す文字数
+const SNIPPET_CONTEXT: usize = 30;
+
+/// 検索結果1件（1段落）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub ncode: String,
+    pub episode_number: u32,
+    pub subtitle: String,
+    /// 段落のid（`L12`など）
+    pub line_id: String,
+    /// 段落の本文（ルビは親文字のみ）
+    pub text: String,
+    /// 一致箇所を`【】`で囲んだ抜粋
+    pub snippet: String,
+}
+
+/// ダウンロード済みの本文の全文検索インデックス
+///
+/// 日本語は単語の区切りがないため、段落を文字bigramに分けて索引する。
+/// 候補の段落は最後に本文と照合するので、bigramの偶然の一致は結果に出ない。
+pub struct SearchIndex {
+    conn: Connection,
+}
+
+impl SearchIndex {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let conn = Connection::open(path).with_context(|| format!("Failed to open search index {}", path.display()))?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// 1話を索引する（前回から本文が変わっていなければ何もせずfalse）
+    pub fn index_episode(&mut self, ncode: &str, episode: &Episode) -> Result<bool> {
+        let ncode = ncode.to_lowercase();
+        let parsed = ParsedEpisode::parse(episode);
+        let lines: Vec<(String, String)> = parsed
+            .paragraphs()
+            .filter(|p| !p.is_blank())
+            .map(|p| (p.id.clone(), render_segments(&p.segments(), RubyMode::Strip)))
+            .collect();
+
+        let mut hasher = Sha256::new();
+        hasher.update(parsed.subtitle.as_bytes());
+        for (id, text) in &lines {
+            hasher.update(format!("\n{}\t{}", id, text).as_bytes());
+        }
+        let hash: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+
+        let existing: Option<(i64, String)> = self
+            .conn
+            .query_row(
+                "SELECT id, content_hash FROM documents WHERE ncode = ?1 AND episode_number = ?2",
+                params![ncode, episode.episode_number],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if existing.as_ref().is_some_and(|(_, h)| *h == hash) {
+            return Ok(false);
+        }
+
+        let tx = self.conn.transaction()?;
+        if let Some((document_id, _)) = existing {
+            tx.execute(
+                "DELETE FROM grams WHERE line IN (SELECT id FROM lines WHERE document_id = ?1)",
+                [document_id],
+            )?;
+            tx.execute("DELETE FROM lines WHERE document_id = ?1", [document_id])?;
+            tx.execute("DELETE FROM documents WHERE id = ?1", [document_id])?;
+        }
+        tx.execute(
+            "INSERT INTO documents (ncode, episode_number, subtitle, content_hash) VALUES (?1, ?2, ?3, ?4)",
+            params![ncode, episode.episode_number, parsed.subtitle, hash],
+        )?;
+        let document_id = tx.last_insert_rowid();
+
+        for (line_id, text) in &lines {
+            tx.execute(
+                "INSERT INTO lines (document_id, line_id, text) VALUES (?1, ?2, ?3)",
+                params![document_id, line_id, text],
+            )?;
+            let line = tx.last_insert_rowid();
+            for gram in bigrams(&normalize(text)) {
+                tx.execute("INSERT OR IGNORE INTO grams (gram, line) VALUES (?1, ?2)", params![gram, line])?;
+            }
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// 取得した全話を索引し、索引し直した話数を返す
+    pub fn index_novel(&mut self, novel: &NovelContent) -> Result<usize> {
+        let mut updated = 0;
+        for episode in &novel.episodes {
+            if self.index_episode(&novel.ncode, episode)? {
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+
+    /// fetch_novelの出力（`{dir}/{ncode}/0001.html`・短編は`{dir}/{ncode}/{ncode}.html`）を索引する
+    pub fn index_output_dir(&mut self, dir: &Path) -> Result<usize> {
+        let mut updated = 0;
+        for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+            let novel_dir = entry?.path();
+            let Some(ncode) = novel_dir.file_name().and_then(|n| n.to_str()).map(str::to_string) else {
+                continue;
+            };
+            if !novel_dir.is_dir() {
+                continue;
+            }
+
+            for (episode_number, path) in episode_files(&novel_dir, &ncode)? {
+                let html = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+                if self.index_episode(&ncode, &Episode { episode_number, html })? {
+                    updated += 1;
+                }
+            }
+        }
+        Ok(updated)
+    }
+
+    /// ライブラリに保存された全話を索引する
+    pub fn index_library(&mut self, library: &Library) -> Result<usize> {
+        let mut updated = 0;
+        for ncode in library.episode_ncodes()? {
+            for record in library.episodes_for(&ncode)? {
+                if self.index_episode(&ncode, &record.to_episode())? {
+                    updated += 1;
+                }
+            }
+        }
+        Ok(updated)
+    }
+
+    /// 空白区切りの語を全て含む段落を検索（`ncode`で作品を絞り込める）
+    pub fn search(&self, query: &str, ncode: Option<&str>, limit: usize) -> Result<Vec<SearchHit>> {
+        let terms: Vec<String> = query.split_whitespace().map(normalize).collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let grams: BTreeSet<String> = terms.iter().flat_map(|t| bigrams(t)).collect();
+
+        let mut sql = String::from(
+            "SELECT d.ncode, d.episode_number, d.subtitle, l.line_id, l.text
+             FROM lines l JOIN documents d ON d.id = l.document_id WHERE 1 = 1",
+        );
+        let mut values: Vec<String> = Vec::new();
+        if !grams.is_empty() {
+            let placeholders = vec!["?"; grams.len()].join(", ");
+            sql.push_str(&format!(
+                " AND l.id IN (SELECT line FROM grams WHERE gram IN ({}) GROUP BY line HAVING COUNT(*) = {})",
+                placeholders,
+                grams.len()
+            ));
+            values.extend(grams.iter().cloned());
+        }
+        if let Some(ncode) = ncode {
+            sql.push_str(" AND d.ncode = ?");
+            values.push(ncode.to_lowercase());
+        }
+        sql.push_str(" ORDER BY d.ncode, d.episode_number, l.id");
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(values.iter()))?;
+        let mut hits = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            let text: String = row.get(4)?;
+            let normalized = normalize(&text);
+            if !terms.iter().all(|t| normalized.contains(t.as_str())) {
+                continue;
+            }
+            hits.push(SearchHit {
+                ncode: row.get(0)?,
+                episode_number: row.get(1)?,
+                subtitle: row.get(2)?,
+                line_id: row.get(3)?,
+                snippet: snippet(&text, &terms),
+                text,
+            });
+            if hits.len() >= limit {
+                break;
+            }
+        }
+
+        Ok(hits)
+    }
+
+    /// 索引済みの話数
+    pub fn document_count(&self) -> Result<usize> {
+        Ok(self.conn.query_row("SELECT COUNT(*) FROM documents", [], |row| row.get::<_, i64>(0))? as usize)
+    }
+}
+
+/// 検索用の正規化（全角英数字を半角に、英字を小文字に。文字数は変えない）
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 空白をまたがない文字bigram
+fn bigrams(text: &str) -> BTreeSet<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .windows(2)
+        .filter(|w| !w[0].is_whitespace() && !w[1].is_whitespace())
+        .map(|w| w.iter().collect())
+        .collect()
+}
+
+/// 最初に一致した語の前後を切り出し、一致箇所を`【】`で囲む
+fn snippet(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let normalized: Vec<char> = normalize(text).chars().collect();
+    // 正規化で文字数が変わった場合（大文字の特殊な小文字化など）は強調せずに返す
+    if normalized.len() != chars.len() {
+        return text.to_string();
+    }
+
+    let mut marked = vec![false; chars.len()];
+    for term in terms {
+        let term: Vec<char> = term.chars().collect();
+        if term.is_empty() || term.len() > normalized.len() {
+            continue;
+        }
+        for start in 0..=normalized.len() - term.len() {
+            if normalized[start..start + term.len()] == term[..] {
+                marked[start..start + term.len()].iter_mut().for_each(|m| *m = true);
+            }
+        }
+    }
+
+    let first = marked.iter().position(|m| *m).unwrap_or(0);
+    let last = marked.iter().rposition(|m| *m).unwrap_or(0);
+    let start = first.saturating_sub(SNIPPET_CONTEXT);
+    let end = (last + 1 + SNIPPET_CONTEXT).min(chars.len());
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+    for i in start..end {
+        if marked[i] && (i == start || !marked[i - 1]) {
+            out.push('【');
+        }
+        out.push(chars[i]);
+        if marked[i] && (i + 1 == end || !marked[i + 1]) {
+            out.push('】');
+        }
+    }
+    if end < chars.len() {
+        out.push('…');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(episode_number: u32, lines: &[&str]) -> Episode {
+        let body: String = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!(r#"<p id="L{}">{}</p>"#, i + 1, line))
+            .collect();
+        Episode {
+            episode_number,
+            html: format!(
+                r#"<h1 class="p-novel__title">第{}話</h1><div class="p-novel__body"><div class="js-novel-text p-novel__text">{}</div></div>"#,
+                episode_number, body
+            ),
+        }
+    }
+
+    #[test]
+    fn test_search_japanese() {
+        let mut index = SearchIndex::open_in_memory().unwrap();
+        assert!(index.index_episode("N0001AA", &episode(1, &["彼は<ruby>魔法<rt>まほう</rt></ruby>使いだ。", "剣を抜いた。"])).unwrap());
+        assert!(index.index_episode("n0001aa", &episode(2, &["ＡＢＣの魔法使いと法使"])).unwrap());
+        assert!(!index.index_episode("n0001aa", &episode(2, &["ＡＢＣの魔法使いと法使"])).unwrap());
+        assert_eq!(index.document_count().unwrap(), 2);
+
+        let hits = index.search("魔法使い", None, 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!((hits[0].ncode.as_str(), hits[0].episode_number, hits[0].line_id.as_str()), ("n0001aa", 1, "L1"));
+        assert_eq!(hits[0].snippet, "彼は【魔法使い】だ。");
+
+        // 全角・半角と大文字・小文字を区別しない
+        assert_eq!(index.search("abc 使い", None, 10).unwrap()[0].snippet, "【ＡＢＣ】の魔法【使い】と法使");
+        // 1文字の語と、bigramは揃うが並びが違う語
+        assert_eq!(index.search("剣", None, 10).unwrap().len(), 1);
+        assert!(index.search("使法", None, 10).unwrap().is_empty());
+        assert!(index.search("魔法", Some("n9999zz"), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reindex_replaces_lines() {
+        let mut index = SearchIndex::open_in_memory().unwrap();
+        index.index_episode("n0001aa", &episode(1, &["古い本文"])).unwrap();
+        assert!(index.index_episode("n0001aa", &episode(1, &["新しい本文"])).unwrap());
+
+        assert!(index.search("古い", None, 10).unwrap().is_empty());
+        assert_eq!(index.search("本文", None, 10).unwrap().len(), 1);
+    }
+}
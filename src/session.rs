@@ -0,0 +1,378 @@
+use anyhow::{Context, Result};
+use reqwest::Url;
+use scraper::{Html, Selector};
+use std::fmt;
+use std::path::PathBuf;
+
+/// なろうのログインフォームのURL
+pub const DEFAULT_LOGIN_URL: &str = "https://syosetu.com/login/input/";
+
+/// ログイン情報
+#[derive(Clone)]
+pub struct LoginCredentials {
+    /// ログインID（メールアドレスまたはユーザID）
+    pub narouid: String,
+    pub password: String,
+}
+
+impl fmt::Debug for LoginCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoginCredentials")
+            .field("narouid", &self.narouid)
+            .field("password", &"********")
+            .finish()
+    }
+}
+
+/// セッション関連のエラー
+#[derive(Debug)]
+pub enum SessionError {
+    /// ログイン情報が設定されていない
+    MissingCredentials,
+    /// ログインページにフォームが見つからない
+    LoginFormNotFound(String),
+    /// ID・パスワードが受け付けられなかった
+    LoginFailed,
+    /// ログインが必要なページで、セッションが切れている
+    Expired(String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::MissingCredentials => write!(f, "Login credentials are not set"),
+            SessionError::LoginFormNotFound(url) => write!(f, "Login form not found at {}", url),
+            SessionError::LoginFailed => write!(f, "Login failed: check the ID and password"),
+            SessionError::Expired(url) => write!(f, "Session expired or not logged in: {}", url),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+/// ログインフォームの解析結果
+#[derive(Debug)]
+struct LoginForm {
+    action: Url,
+    id_field: String,
+    password_field: String,
+    /// hidden項目（CSRFトークンなど）
+    hidden_fields: Vec<(String, String)>,
+}
+
+/// ログイン状態を管理するセッション
+///
+/// 内部の`HtmlFetcher`はクッキーストアを共有するため、
+/// `fetcher()`をスクレイパーに渡せばログイン状態のまま取得できる。
+pub struct NarouSession {
+    fetcher: crate::HtmlFetcher,
+    login_url: String,
+    credentials: Option<LoginCredentials>,
+    cookie_file: Option<PathBuf>,
+}
+
+impl NarouSession {
+    /// 新しいセッションを作成
+    pub fn new(fetcher: crate::HtmlFetcher) -> Self {
+        Self {
+            fetcher,
+            login_url: DEFAULT_LOGIN_URL.to_string(),
+            credentials: None,
+            cookie_file: None,
+        }
+    }
+
+    /// ログインフォームのURLを変更（テスト用のローカルサーバーなど）
+    pub fn with_login_url(mut self, login_url: impl Into<String>) -> Self {
+        self.login_url = login_url.into();
+        self
+    }
+
+    /// ログイン情報を設定（セッション切れ時の自動再ログインにも使う）
+    pub fn with_credentials(mut self, credentials: LoginCredentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// クッキーファイルを設定し、存在すれば読み込む
+    pub fn with_cookie_file(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            self.fetcher.load_cookies(&path)?;
+        }
+        self.cookie_file = Some(path);
+        Ok(self)
+    }
+
+    /// セッションのクッキーを共有するfetcher
+    pub fn fetcher(&self) -> &crate::HtmlFetcher {
+        &self.fetcher
+    }
+
+    /// ログインフォームからログイン
+    pub async fn login(&self) -> Result<()> {
+        let credentials = self.credentials.as_ref().ok_or(SessionError::MissingCredentials)?;
+
+        let login_page = self.fetcher.fetch(&self.login_url).await
+            .with_context(|| format!("Failed to fetch login page {}", self.login_url))?;
+        let page_url = self.login_url.parse::<Url>()?;
+        let form = Self::parse_login_form(&login_page, &page_url)
+            .ok_or_else(|| SessionError::LoginFormNotFound(self.login_url.clone()))?;
+
+        let mut fields: Vec<(&str, &str)> = form.hidden_fields.iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        fields.push((&form.id_field, &credentials.narouid));
+        fields.push((&form.password_field, &credentials.password));
+
+        let html = self.fetcher.post_form(form.action.as_str(), &fields).await
+            .context("Failed to submit login form")?;
+
+        if is_logged_out_page(&html) {
+            return Err(SessionError::LoginFailed.into());
+        }
+
+        self.persist()
+    }
+
+    /// ログインが必要なページを取得
+    ///
+    /// セッション切れを検出した場合、ログイン情報があれば一度だけ再ログインして取り直す。
+    pub async fn fetch(&self, url: &str) -> Result<String> {
+        let html = self.fetcher.fetch(url).await?;
+        if !is_logged_out_page(&html) {
+            self.persist()?;
+            return Ok(html);
+        }
+
+        if self.credentials.is_none() {
+            return Err(SessionError::Expired(url.to_string()).into());
+        }
+
+        self.login().await?;
+        // 未ログインのページがキャッシュに残っていると、それが返ってきてしまう
+        self.fetcher.evict_cached(url)?;
+        let html = self.fetcher.fetch(url).await?;
+        if is_logged_out_page(&html) {
+            return Err(SessionError::Expired(url.to_string()).into());
+        }
+
+        self.persist()?;
+        Ok(html)
+    }
+
+    /// クッキーを破棄してログアウト状態にする
+    pub fn logout(&self) -> Result<()> {
+        self.fetcher.clear_cookies();
+        self.persist()
+    }
+
+    /// クッキーファイルが設定されていれば保存
+    pub fn persist(&self) -> Result<()> {
+        match &self.cookie_file {
+            Some(path) => self.fetcher.save_cookies(path),
+            None => Ok(()),
+        }
+    }
+
+    /// パスワード入力欄を含むフォームを探す
+    fn parse_login_form(html: &str, page_url: &Url) -> Option<LoginForm> {
+        let document = Html::parse_document(html);
+        let form_selector = Selector::parse("form").unwrap();
+        let input_selector = Selector::parse("input").unwrap();
+
+        for form in document.select(&form_selector) {
+            let mut id_field = None;
+            let mut password_field = None;
+            let mut hidden_fields = Vec::new();
+
+            for input in form.select(&input_selector) {
+                let Some(name) = input.value().attr("name") else { continue };
+                let input_type = input.value().attr("type").unwrap_or("text").to_ascii_lowercase();
+
+                match input_type.as_str() {
+                    "password" => password_field = Some(name.to_string()),
+                    "hidden" => hidden_fields.push((
+                        name.to_string(),
+                        input.value().attr("value").unwrap_or("").to_string(),
+                    )),
+                    "text" | "email" if id_field.is_none() || name == "narouid" => {
+                        id_field = Some(name.to_string());
+                    }
+                    _ => {}
+                }
+            }
+
+            if let (Some(id_field), Some(password_field)) = (id_field, password_field) {
+                let action = form.value().attr("action").unwrap_or("");
+                let action = page_url.join(action).ok()?;
+                return Some(LoginForm {
+                    action,
+                    id_field,
+                    password_field,
+                    hidden_fields,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+/// ログインしていない状態のページか判定
+///
+/// 未ログイン時はヘッダーに「ログイン」リンクが出る。ログインページ自体も未ログイン扱い。
+pub fn is_logged_out_page(html: &str) -> bool {
+    html.contains("p-icon--login") || html.contains(r#"type="password""#)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CacheConfig, FetcherConfig, HtmlFetcher, RequestDelayConfig, RetryPolicy, UserAgentMode};
+    use std::time::Duration;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const LOGIN_PAGE: &str = r#"<html><body>
+<form action="/login/login/" method="post">
+<input type="hidden" name="token" value="csrf123">
+<input type="text" name="narouid">
+<input type="password" name="pass">
+<input type="submit" value="ログイン">
+</form></body></html>"#;
+    const LOGGED_IN_PAGE: &str = r#"<html><body><a href="/logout/">ログアウト</a><p>マイページ</p></body></html>"#;
+    const LOGGED_OUT_PAGE: &str = r#"<html><body><a href="/login/input/"><span class="p-icon p-icon--login"></span>ログイン</a></body></html>"#;
+
+    async fn start_stand_in_server() -> MockServer {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET")).and(path("/login/input/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(LOGIN_PAGE))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/login/login/"))
+            .and(body_string_contains("token=csrf123"))
+            .and(body_string_contains("narouid=reader"))
+            .and(body_string_contains("pass=secret"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("set-cookie", "userl=session-token; Path=/")
+                .set_body_string(LOGGED_IN_PAGE))
+            .with_priority(1)
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/login/login/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(LOGIN_PAGE))
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/mypage/"))
+            .and(header("cookie", "userl=session-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(LOGGED_IN_PAGE))
+            .with_priority(1)
+            .mount(&server).await;
+        Mock::given(method("GET")).and(path("/mypage/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(LOGGED_OUT_PAGE))
+            .mount(&server).await;
+
+        server
+    }
+
+    fn session_for(server: &MockServer, password: &str) -> NarouSession {
+        let fetcher = HtmlFetcher::with_fixed_user_agent("TestBot/1.0".to_string()).unwrap();
+        fetcher.set_delay_config(RequestDelayConfig::disabled());
+        session_with(fetcher, server, password)
+    }
+
+    fn session_with(fetcher: HtmlFetcher, server: &MockServer, password: &str) -> NarouSession {
+        NarouSession::new(fetcher)
+            .with_login_url(format!("{}/login/input/", server.uri()))
+            .with_credentials(LoginCredentials {
+                narouid: "reader".to_string(),
+                password: password.to_string(),
+            })
+    }
+
+    #[tokio::test]
+    async fn test_login_and_fetch() {
+        let server = start_stand_in_server().await;
+        let session = session_for(&server, "secret");
+
+        session.login().await.unwrap();
+        assert!(session.fetcher().cookies().iter().any(|c| c.name == "userl"));
+
+        let html = session.fetch(&format!("{}/mypage/", server.uri())).await.unwrap();
+        assert!(html.contains("マイページ"));
+    }
+
+    #[tokio::test]
+    async fn test_login_failure() {
+        let server = start_stand_in_server().await;
+        let session = session_for(&server, "wrong");
+
+        let err = session.login().await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<SessionError>(), Some(SessionError::LoginFailed)));
+    }
+
+    #[tokio::test]
+    async fn test_relogin_on_expired_session() {
+        let server = start_stand_in_server().await;
+        let session = session_for(&server, "secret");
+
+        // 未ログインの状態から取得すると自動でログインしてから取り直す
+        let html = session.fetch(&format!("{}/mypage/", server.uri())).await.unwrap();
+        assert!(html.contains("マイページ"));
+    }
+
+    #[tokio::test]
+    async fn test_relogin_with_response_cache() {
+        let server = start_stand_in_server().await;
+        let fetcher = HtmlFetcher::from_config(
+            FetcherConfig::new()
+                .user_agent_mode(UserAgentMode::Fixed(Some("TestBot/1.0".to_string())))
+                .delay(RequestDelayConfig::disabled())
+                .cache(CacheConfig::in_memory(Duration::from_secs(60))),
+        )
+        .unwrap();
+        let session = session_with(fetcher, &server, "secret");
+
+        let html = session.fetch(&format!("{}/mypage/", server.uri())).await.unwrap();
+        assert!(html.contains("マイページ"));
+    }
+
+    #[tokio::test]
+    async fn test_login_post_is_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/login/input/"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(LOGIN_PAGE))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/login/login/"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server).await;
+
+        let fetcher = HtmlFetcher::from_config(
+            FetcherConfig::new()
+                .user_agent_mode(UserAgentMode::Fixed(Some("TestBot/1.0".to_string())))
+                .delay(RequestDelayConfig::disabled())
+                .retry(RetryPolicy::new(2, Duration::from_millis(10))),
+        )
+        .unwrap();
+        let session = session_with(fetcher, &server, "secret");
+        assert!(session.login().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expired_without_credentials() {
+        let server = start_stand_in_server().await;
+        let fetcher = HtmlFetcher::with_fixed_user_agent("TestBot/1.0".to_string()).unwrap();
+        fetcher.set_delay_config(RequestDelayConfig::disabled());
+        let session = NarouSession::new(fetcher);
+
+        let err = session.fetch(&format!("{}/mypage/", server.uri())).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<SessionError>(), Some(SessionError::Expired(_))));
+    }
+
+    #[test]
+    fn test_logged_out_fixture() {
+        let html = std::fs::read_to_string("target_pages/narou/rating/59791-1.html").unwrap();
+        assert!(is_logged_out_page(&html));
+        assert!(!is_logged_out_page(LOGGED_IN_PAGE));
+    }
+}
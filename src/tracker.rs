@@ -0,0 +1,253 @@
+use crate::api::endpoints::{narou::NarouNovelInfo, nocturne::NocturneNovelInfo};
+use crate::api::{HttpClient, NarouApiClient, NarouRequest, NocturneApiClient, NocturneRequest};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// 1回のAPI呼び出しで問い合わせるncodeの数（ハイフン区切りで指定する）
+pub const NOVEL_API_BATCH: usize = 100;
+
+/// ある時点の作品の指標
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricSnapshot {
+    pub ncode: String,
+    /// 記録日時（RFC 3339）
+    pub recorded_at: String,
+    pub global_point: Option<u32>,
+    pub daily_point: Option<u32>,
+    pub weekly_point: Option<u32>,
+    pub all_point: Option<u32>,
+    pub all_hyoka_cnt: Option<u32>,
+    pub fav_novel_cnt: Option<u32>,
+    pub impression_cnt: Option<u32>,
+    pub review_cnt: Option<u32>,
+    pub weekly_unique: Option<u32>,
+    pub general_all_no: Option<u32>,
+}
+
+impl MetricSnapshot {
+    pub fn from_narou_info(info: &NarouNovelInfo, recorded_at: DateTime<Utc>) -> Option<Self> {
+        Some(Self {
+            ncode: info.ncode.as_ref()?.to_lowercase(),
+            recorded_at: recorded_at.to_rfc3339(),
+            global_point: info.global_point,
+            daily_point: info.daily_point,
+            weekly_point: info.weekly_point,
+            all_point: info.all_point,
+            all_hyoka_cnt: info.all_hyoka_cnt,
+            fav_novel_cnt: info.fav_novel_cnt,
+            impression_cnt: info.impression_cnt,
+            review_cnt: info.review_cnt,
+            weekly_unique: info.weekly_unique,
+            general_all_no: info.general_all_no,
+        })
+    }
+
+    pub fn from_nocturne_info(info: &NocturneNovelInfo, recorded_at: DateTime<Utc>) -> Option<Self> {
+        Some(Self {
+            ncode: info.ncode.as_ref()?.to_lowercase(),
+            recorded_at: recorded_at.to_rfc3339(),
+            global_point: info.global_point,
+            daily_point: info.daily_point,
+            weekly_point: info.weekly_point,
+            all_point: info.all_point,
+            all_hyoka_cnt: info.all_hyoka_cnt,
+            fav_novel_cnt: info.fav_novel_cnt,
+            impression_cnt: info.impression_cnt,
+            review_cnt: info.review_cnt,
+            weekly_unique: info.weekly_unique,
+            general_all_no: info.general_all_no,
+        })
+    }
+
+    pub fn recorded_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.recorded_at).ok().map(|t| t.with_timezone(&Utc))
+    }
+}
+
+/// 期間中の伸び
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Growth {
+    pub ncode: String,
+    pub from: String,
+    pub to: String,
+    pub days: f64,
+    pub points_per_day: Option<f64>,
+    pub bookmarks_per_week: Option<f64>,
+    pub ratings_per_day: Option<f64>,
+    pub unique_readers_change: Option<i64>,
+    pub episodes_added: Option<i64>,
+    /// 最新の値
+    pub latest: MetricSnapshot,
+}
+
+impl Growth {
+    /// 時系列（古い順）の伸び。`window`を指定すると最新からその期間内の最古の記録と比べる
+    ///
+    /// 記録が1件しかない、または同時刻の記録しかない場合はNone。
+    pub fn from_series(series: &[MetricSnapshot], window: Option<Duration>) -> Option<Self> {
+        let last = series.last()?;
+        let last_at = last.recorded_at()?;
+        let first = series
+            .iter()
+            .find(|s| match (window, s.recorded_at()) {
+                (Some(window), Some(at)) => at >= last_at - window,
+                (None, Some(_)) => true,
+                (_, None) => false,
+            })?;
+
+        let days = (last_at - first.recorded_at()?).num_seconds() as f64 / 86_400.0;
+        if days <= 0.0 {
+            return None;
+        }
+        let delta = |f: fn(&MetricSnapshot) -> Option<u32>| Some(f(last)? as i64 - f(first)? as i64);
+
+        Some(Self {
+            ncode: last.ncode.clone(),
+            from: first.recorded_at.clone(),
+            to: last.recorded_at.clone(),
+            days,
+            points_per_day: delta(|s| s.global_point).map(|d| d as f64 / days),
+            bookmarks_per_week: delta(|s| s.fav_novel_cnt).map(|d| d as f64 / days * 7.0),
+            ratings_per_day: delta(|s| s.all_hyoka_cnt).map(|d| d as f64 / days),
+            unique_readers_change: delta(|s| s.weekly_unique),
+            episodes_added: delta(|s| s.general_all_no),
+            latest: last.clone(),
+        })
+    }
+}
+
+/// ウォッチリストのファイル（1行1ncode、`#`以降はコメント）を読む
+pub fn read_watchlist(path: &Path) -> Result<Vec<String>> {
+    let text = fs::read_to_string(path).with_context(|| format!("Failed to read watchlist {}", path.display()))?;
+    let mut ncodes: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let ncode = line.split('#').next().unwrap_or("").trim().to_lowercase();
+        if !ncode.is_empty() && !ncodes.contains(&ncode) {
+            ncodes.push(ncode);
+        }
+    }
+    Ok(ncodes)
+}
+
+/// なろうAPIで作品情報をまとめて取得（ncodeをハイフン区切りで`NOVEL_API_BATCH`件ずつ）
+pub async fn fetch_narou_infos(client: &HttpClient, ncodes: &[String]) -> Result<Vec<NarouNovelInfo>> {
+    let mut novels = Vec::new();
+    for batch in ncodes.chunks(NOVEL_API_BATCH) {
+        let mut request = NarouRequest::new();
+        request.ncode = Some(batch.join("-"));
+        request.lim = Some(batch.len() as u32);
+        let response = client.execute(&NarouApiClient, &request).await?;
+        novels.extend(response.novels);
+    }
+    Ok(novels)
+}
+
+/// ノクターンAPIで作品情報をまとめて取得
+pub async fn fetch_nocturne_infos(client: &HttpClient, ncodes: &[String]) -> Result<Vec<NocturneNovelInfo>> {
+    let mut novels = Vec::new();
+    for batch in ncodes.chunks(NOVEL_API_BATCH) {
+        let mut request = NocturneRequest::new();
+        request.ncode = Some(batch.join("-"));
+        request.lim = Some(batch.len() as u32);
+        let response = client.execute(&NocturneApiClient, &request).await?;
+        novels.extend(response.novels);
+    }
+    Ok(novels)
+}
+
+/// ウォッチリストの現在の指標を取得
+pub async fn fetch_snapshots(client: &HttpClient, ncodes: &[String], nocturne: bool) -> Result<Vec<MetricSnapshot>> {
+    let now = Utc::now();
+    let snapshots = if nocturne {
+        fetch_nocturne_infos(client, ncodes)
+            .await?
+            .iter()
+            .filter_map(|info| MetricSnapshot::from_nocturne_info(info, now))
+            .collect()
+    } else {
+        fetch_narou_infos(client, ncodes)
+            .await?
+            .iter()
+            .filter_map(|info| MetricSnapshot::from_narou_info(info, now))
+            .collect()
+    };
+    Ok(snapshots)
+}
+
+/// 時系列をCSVで書き出す
+pub fn write_metrics_csv(snapshots: &[MetricSnapshot], mut out: impl Write) -> Result<()> {
+    writeln!(
+        out,
+        "ncode,recorded_at,global_point,daily_point,weekly_point,all_point,all_hyoka_cnt,fav_novel_cnt,impression_cnt,review_cnt,weekly_unique,general_all_no"
+    )?;
+    let value = |v: Option<u32>| v.map(|v| v.to_string()).unwrap_or_default();
+    for s in snapshots {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            s.ncode,
+            s.recorded_at,
+            value(s.global_point),
+            value(s.daily_point),
+            value(s.weekly_point),
+            value(s.all_point),
+            value(s.all_hyoka_cnt),
+            value(s.fav_novel_cnt),
+            value(s.impression_cnt),
+            value(s.review_cnt),
+            value(s.weekly_unique),
+            value(s.general_all_no),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn snapshot(day: u32, global_point: u32, fav_novel_cnt: u32) -> MetricSnapshot {
+        let info = NarouNovelInfo {
+            ncode: Some("N0001AA".to_string()),
+            global_point: Some(global_point),
+            fav_novel_cnt: Some(fav_novel_cnt),
+            ..Default::default()
+        };
+        MetricSnapshot::from_narou_info(&info, Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_growth() {
+        let series = vec![snapshot(1, 100, 10), snapshot(3, 200, 12), snapshot(8, 800, 20)];
+
+        let growth = Growth::from_series(&series, None).unwrap();
+        assert_eq!(growth.ncode, "n0001aa");
+        assert_eq!(growth.days, 7.0);
+        assert_eq!(growth.points_per_day, Some(100.0));
+        assert_eq!(growth.bookmarks_per_week, Some(10.0));
+        assert_eq!(growth.ratings_per_day, None);
+
+        let recent = Growth::from_series(&series, Some(Duration::days(5))).unwrap();
+        assert_eq!(recent.points_per_day, Some(120.0));
+
+        assert!(Growth::from_series(&series[..1], None).is_none());
+    }
+
+    #[test]
+    fn test_watchlist_and_csv() {
+        let path = std::env::temp_dir().join(format!("wns-watchlist-{}.txt", std::process::id()));
+        fs::write(&path, "N0001AA  # 追っている作品\n\n# コメント\nn0002bb\nn0001aa\n").unwrap();
+        assert_eq!(read_watchlist(&path).unwrap(), vec!["n0001aa", "n0002bb"]);
+        fs::remove_file(&path).unwrap();
+
+        let mut csv = Vec::new();
+        write_metrics_csv(&[snapshot(1, 100, 10)], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().nth(1).unwrap(), "n0001aa,2024-01-01T00:00:00+00:00,100,,,,,10,,,,");
+    }
+}
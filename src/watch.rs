@@ -0,0 +1,487 @@
+use crate::api::endpoints::{narou::NarouNovelInfo, nocturne::NocturneNovelInfo};
+use crate::api::HttpClient;
+use crate::export::escape_xml;
+use crate::library::Library;
+use crate::novel_parser::ParsedEpisode;
+use crate::novel_scraper::NarouNovelScraper;
+use crate::ruby::{render_segments, RubyMode};
+use crate::tracker::{fetch_narou_infos, fetch_nocturne_infos};
+use crate::HtmlFetcher;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 抜粋の最大文字数
+pub const EXCERPT_CHARS: usize = 120;
+
+/// RSSに残す項目数
+const RSS_MAX_ITEMS: usize = 100;
+
+/// APIから得た作品の更新状況
+#[derive(Debug, Clone, PartialEq)]
+pub struct NovelStatus {
+    pub ncode: String,
+    pub title: String,
+    pub writer: String,
+    /// 全話数（短編は1）
+    pub general_all_no: u32,
+    pub novelupdated_at: Option<String>,
+    pub general_lastup: Option<String>,
+    pub is_short: bool,
+}
+
+impl NovelStatus {
+    pub fn from_narou_info(info: &NarouNovelInfo) -> Option<Self> {
+        Some(Self {
+            ncode: info.ncode.as_ref()?.to_lowercase(),
+            title: info.title.clone().unwrap_or_default(),
+            writer: info.writer.clone().unwrap_or_default(),
+            general_all_no: info.general_all_no.unwrap_or(0),
+            novelupdated_at: info.novelupdated_at.clone(),
+            general_lastup: info.general_lastup.clone(),
+            is_short: info.novel_type == Some(2),
+        })
+    }
+
+    pub fn from_nocturne_info(info: &NocturneNovelInfo) -> Option<Self> {
+        Some(Self {
+            ncode: info.ncode.as_ref()?.to_lowercase(),
+            title: info.title.clone().unwrap_or_default(),
+            writer: info.writer.clone().unwrap_or_default(),
+            general_all_no: info.general_all_no.unwrap_or(0),
+            novelupdated_at: info.novelupdated_at.clone(),
+            general_lastup: info.general_lastup.clone(),
+            is_short: info.novel_type == Some(2),
+        })
+    }
+}
+
+/// 作品ごとの確認済みの状態
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchedNovel {
+    /// 通知済みの最後の話数
+    pub last_episode: u32,
+    pub novelupdated_at: Option<String>,
+}
+
+/// 監視の状態（再起動しても同じ話を通知しないようにファイルに保存する）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WatchState {
+    pub novels: BTreeMap<String, WatchedNovel>,
+}
+
+impl WatchState {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// まだ通知していない話数（初めて見る作品は現在の話数を基準にするだけで通知しない）
+    pub fn new_episodes(&self, status: &NovelStatus) -> Vec<u32> {
+        match self.novels.get(&status.ncode) {
+            Some(seen) if seen.novelupdated_at != status.novelupdated_at && !status.is_short => {
+                (seen.last_episode + 1..=status.general_all_no).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// 1話の通知が済んだことを記録（更新日時は据え置き、残りの話は次回に回す）
+    pub fn mark_episode(&mut self, ncode: &str, episode_number: u32) {
+        if let Some(seen) = self.novels.get_mut(ncode) {
+            seen.last_episode = seen.last_episode.max(episode_number);
+        }
+    }
+
+    /// 通知し終えた状態を記録
+    pub fn mark_seen(&mut self, status: &NovelStatus) {
+        self.novels.insert(
+            status.ncode.clone(),
+            WatchedNovel {
+                last_episode: status.general_all_no,
+                novelupdated_at: status.novelupdated_at.clone(),
+            },
+        );
+    }
+}
+
+/// 新しい話の通知
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchEvent {
+    pub ncode: String,
+    pub title: String,
+    pub writer: String,
+    pub episode_number: u32,
+    pub subtitle: String,
+    pub url: String,
+    /// 本文の冒頭
+    pub excerpt: String,
+    pub novelupdated_at: Option<String>,
+    /// 検出日時（RFC 3339）
+    pub detected_at: String,
+}
+
+/// 通知の送り先
+#[async_trait]
+pub trait EventSink: Send {
+    async fn emit(&mut self, event: &WatchEvent) -> Result<()>;
+}
+
+/// 標準出力に1行1イベントのJSONで書く
+pub struct StdoutSink;
+
+#[async_trait]
+impl EventSink for StdoutSink {
+    async fn emit(&mut self, event: &WatchEvent) -> Result<()> {
+        println!("{}", serde_json::to_string(event)?);
+        Ok(())
+    }
+}
+
+/// イベントをJSONでPOSTする
+pub struct WebhookSink {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    async fn emit(&mut self, event: &WatchEvent) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(event)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to post event to {}", self.url))?;
+        Ok(())
+    }
+}
+
+/// RSS 2.0のファイルを書き出す（項目は`{path}.json`に保存して次回に引き継ぐ）
+pub struct RssSink {
+    path: PathBuf,
+    title: String,
+    items: Vec<WatchEvent>,
+}
+
+impl RssSink {
+    pub fn open(path: impl Into<PathBuf>, title: impl Into<String>) -> Result<Self> {
+        let path = path.into();
+        let items_path = Self::items_path(&path);
+        let items = if items_path.exists() {
+            let text = fs::read_to_string(&items_path)?;
+            serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", items_path.display()))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            path,
+            title: title.into(),
+            items,
+        })
+    }
+
+    /// 新しい順のRSS
+    pub fn render(&self) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n");
+        out.push_str(&format!("<title>{}</title>\n", escape_xml(&self.title)));
+        out.push_str("<link>https://syosetu.com/</link>\n");
+        out.push_str(&format!("<description>{}</description>\n", escape_xml(&self.title)));
+        for item in &self.items {
+            out.push_str("<item>\n");
+            out.push_str(&format!(
+                "<title>{}</title>\n",
+                escape_xml(&format!("{} {}", item.title, item.subtitle))
+            ));
+            out.push_str(&format!("<link>{}</link>\n", escape_xml(&item.url)));
+            out.push_str(&format!("<guid isPermaLink=\"true\">{}</guid>\n", escape_xml(&item.url)));
+            if let Ok(date) = chrono::DateTime::parse_from_rfc3339(&item.detected_at) {
+                out.push_str(&format!("<pubDate>{}</pubDate>\n", date.to_rfc2822()));
+            }
+            out.push_str(&format!("<description>{}</description>\n", escape_xml(&item.excerpt)));
+            out.push_str("</item>\n");
+        }
+        out.push_str("</channel>\n</rss>\n");
+        out
+    }
+
+    fn items_path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".json");
+        PathBuf::from(name)
+    }
+}
+
+#[async_trait]
+impl EventSink for RssSink {
+    async fn emit(&mut self, event: &WatchEvent) -> Result<()> {
+        self.items.insert(0, event.clone());
+        self.items.truncate(RSS_MAX_ITEMS);
+
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(Self::items_path(&self.path), serde_json::to_string_pretty(&self.items)?)?;
+        fs::write(&self.path, self.render()).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// ウォッチリストの作品の更新をAPIで確認し、新しい話を取得して通知する
+pub struct Watcher {
+    client: HttpClient,
+    scraper: NarouNovelScraper,
+    nocturne: bool,
+    state: WatchState,
+    state_path: PathBuf,
+    library: Option<Library>,
+    sinks: Vec<Box<dyn EventSink>>,
+}
+
+impl Watcher {
+    /// `state_path`の状態を読み込んで作成
+    pub fn new(fetcher: HtmlFetcher, nocturne: bool, state_path: impl Into<PathBuf>) -> Result<Self> {
+        let state_path = state_path.into();
+        let scraper = if nocturne {
+            NarouNovelScraper::new_nocturne(fetcher.clone())
+        } else {
+            NarouNovelScraper::new(fetcher.clone())
+        };
+        Ok(Self {
+            client: HttpClient::from_fetcher(fetcher),
+            scraper,
+            nocturne,
+            state: WatchState::load(&state_path)?,
+            state_path,
+            library: None,
+            sinks: Vec::new(),
+        })
+    }
+
+    /// 取得した話をライブラリにも保存する
+    pub fn library(mut self, library: Library) -> Self {
+        self.library = Some(library);
+        self
+    }
+
+    /// 通知先を追加
+    pub fn sink(mut self, sink: Box<dyn EventSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    pub fn state(&self) -> &WatchState {
+        &self.state
+    }
+
+    /// 1回確認して、通知したイベントを返す
+    ///
+    /// 状態は1話通知するごとに進めるので、途中で失敗しても通知済みの話を次回また送ることはない。
+    pub async fn poll(&mut self, ncodes: &[String]) -> Result<Vec<WatchEvent>> {
+        let statuses: Vec<NovelStatus> = if self.nocturne {
+            fetch_nocturne_infos(&self.client, ncodes)
+                .await?
+                .iter()
+                .filter_map(NovelStatus::from_nocturne_info)
+                .collect()
+        } else {
+            fetch_narou_infos(&self.client, ncodes)
+                .await?
+                .iter()
+                .filter_map(NovelStatus::from_narou_info)
+                .collect()
+        };
+
+        let mut events = Vec::new();
+        for status in &statuses {
+            match self.process(status, &mut events).await {
+                Ok(()) => {
+                    self.state.mark_seen(status);
+                    self.state.save(&self.state_path)?;
+                }
+                Err(e) => eprintln!("⚠️  Failed to process {}: {:#}", status.ncode, e),
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// `interval`ごとに確認し続ける（1回の失敗では止まらない）
+    pub async fn run(&mut self, ncodes: &[String], interval: Duration) -> Result<()> {
+        loop {
+            if let Err(e) = self.poll(ncodes).await {
+                eprintln!("⚠️  Poll failed: {:#}", e);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn process(&mut self, status: &NovelStatus, events: &mut Vec<WatchEvent>) -> Result<()> {
+        for episode_number in self.state.new_episodes(status) {
+            let episode = self.scraper.fetch_episode(&status.ncode, episode_number).await?;
+            if let Some(library) = &self.library {
+                library.upsert_episode(&status.ncode, &episode)?;
+            }
+
+            let parsed = ParsedEpisode::parse(&episode);
+            let event = WatchEvent {
+                ncode: status.ncode.clone(),
+                title: status.title.clone(),
+                writer: status.writer.clone(),
+                episode_number,
+                subtitle: parsed.subtitle.clone(),
+                url: self.scraper.build_novel_url(&status.ncode, Some(episode_number)),
+                excerpt: excerpt(&parsed, EXCERPT_CHARS),
+                novelupdated_at: status.novelupdated_at.clone(),
+                detected_at: chrono::Utc::now().to_rfc3339(),
+            };
+            for sink in &mut self.sinks {
+                sink.emit(&event).await?;
+            }
+            self.state.mark_episode(&status.ncode, episode_number);
+            self.state.save(&self.state_path)?;
+            events.push(event);
+        }
+        Ok(())
+    }
+}
+
+/// 本文の冒頭（ルビは親文字のみ、空行は詰める）
+pub fn excerpt(parsed: &ParsedEpisode, max_chars: usize) -> String {
+    let mut text = String::new();
+    for paragraph in parsed.body.iter().filter(|p| !p.is_blank()) {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(render_segments(&paragraph.segments(), RubyMode::Strip).trim());
+        if text.chars().count() > max_chars {
+            break;
+        }
+    }
+
+    if text.chars().count() > max_chars {
+        let mut cut: String = text.chars().take(max_chars).collect();
+        cut.push('…');
+        cut
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::novel_scraper::Episode;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn status(general_all_no: u32, novelupdated_at: &str) -> NovelStatus {
+        NovelStatus {
+            ncode: "n0001aa".to_string(),
+            title: "作品".to_string(),
+            writer: "作者".to_string(),
+            general_all_no,
+            novelupdated_at: Some(novelupdated_at.to_string()),
+            general_lastup: None,
+            is_short: false,
+        }
+    }
+
+    fn event(episode_number: u32) -> WatchEvent {
+        WatchEvent {
+            ncode: "n0001aa".to_string(),
+            title: "作品 & <副題>".to_string(),
+            writer: "作者".to_string(),
+            episode_number,
+            subtitle: format!("第{}話", episode_number),
+            url: format!("https://ncode.syosetu.com/n0001aa/{}/", episode_number),
+            excerpt: "本文".to_string(),
+            novelupdated_at: None,
+            detected_at: "2024-01-01T00:00:00+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_watch_state() {
+        let mut state = WatchState::default();
+        // 初回は基準を記録するだけ
+        assert!(state.new_episodes(&status(3, "2024-01-01 00:00:00")).is_empty());
+        state.mark_seen(&status(3, "2024-01-01 00:00:00"));
+
+        assert!(state.new_episodes(&status(3, "2024-01-01 00:00:00")).is_empty());
+        assert_eq!(state.new_episodes(&status(5, "2024-01-02 00:00:00")), vec![4, 5]);
+        // 改稿だけの更新
+        assert!(state.new_episodes(&status(3, "2024-01-03 00:00:00")).is_empty());
+
+        // 4話だけ通知して失敗した場合、次回は5話から
+        state.mark_episode("n0001aa", 4);
+        assert_eq!(state.new_episodes(&status(5, "2024-01-02 00:00:00")), vec![5]);
+        state.mark_seen(&status(5, "2024-01-02 00:00:00"));
+        assert!(state.new_episodes(&status(5, "2024-01-02 00:00:00")).is_empty());
+    }
+
+    #[test]
+    fn test_excerpt() {
+        let html = r#"<div class="p-novel__body"><div class="js-novel-text p-novel__text"><p id="L1">　<ruby>魔法<rt>まほう</rt></ruby>の国。</p><p id="L2"><br /></p><p id="L3">二行目。</p></div></div>"#;
+        let parsed = ParsedEpisode::parse(&Episode { episode_number: 1, html: html.to_string() });
+        assert_eq!(excerpt(&parsed, 100), "魔法の国。 二行目。");
+        assert_eq!(excerpt(&parsed, 3), "魔法の…");
+    }
+
+    #[tokio::test]
+    async fn test_rss_sink() {
+        let path = std::env::temp_dir().join(format!("wns-watch-{}.rss", std::process::id()));
+        let mut sink = RssSink::open(&path, "更新情報").unwrap();
+        sink.emit(&event(1)).await.unwrap();
+        sink.emit(&event(2)).await.unwrap();
+
+        let reopened = RssSink::open(&path, "更新情報").unwrap();
+        let rss = reopened.render();
+        assert_eq!(rss, fs::read_to_string(&path).unwrap());
+        assert!(rss.contains("<title>作品 &amp; &lt;副題&gt; 第2話</title>"));
+        assert!(rss.find("/2/</link>").unwrap() < rss.find("/1/</link>").unwrap());
+        assert!(rss.contains("<pubDate>Mon, 1 Jan 2024 00:00:00 +0000</pubDate>"));
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(RssSink::items_path(&path)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_webhook_sink() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_partial_json(serde_json::json!({"ncode": "n0001aa", "episode_number": 4})))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut sink = WebhookSink::new(format!("{}/hook", server.uri()));
+        sink.emit(&event(4)).await.unwrap();
+        assert!(WebhookSink::new(format!("{}/missing", server.uri())).emit(&event(4)).await.is_err());
+    }
+}